@@ -0,0 +1,1309 @@
+//! Conversion between parsed blocks and Apache Arrow record batches.
+//!
+//! ClickHouse types map to Arrow the way ClickHouse's own `Arrow` output format maps them:
+//! wide integers, `UUID`, `IPv6` and `FixedString` become fixed size binaries, `IPv4` becomes
+//! `UInt32`, enums become strings and `BFloat16` becomes `Float32`. Fixed-width data is copied
+//! once into an Arrow buffer, `Nullable` masks become validity bitmaps, `LowCardinality`
+//! becomes a dictionary with `UInt32` keys, `Array`, `Map` and `Tuple` become `List`, `Map` and
+//! `Struct`, and `Variant` and `Dynamic` become dense unions with an extra `Null` member for
+//! NULL rows; `Dynamic` rows in the shared variant are not decoded and fail the conversion.
+//! Arrow strings are UTF-8, so a `String` row that is not fails the conversion.
+//!
+//! [`native_block`] goes the other way, encoding a record batch as a Native block with the
+//! given column types, e.g. for inserting data produced by Arrow-native tools.
+
+use std::{borrow::Cow, sync::Arc};
+
+use arrow_array::{
+    Array, ArrayRef, ArrowPrimitiveType, BooleanArray, Date32Array, Decimal128Array,
+    Decimal256Array, DictionaryArray, FixedSizeBinaryArray, Float32Array, ListArray, MapArray,
+    NullArray, PrimitiveArray, RecordBatch, RecordBatchOptions, StringArray, StructArray,
+    TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+    TimestampSecondArray, UInt32Array, UnionArray,
+    cast::AsArray as _,
+    make_array, new_empty_array, new_null_array,
+    types::{
+        Date32Type, Decimal128Type, Decimal256Type, Float32Type, Float64Type, Int8Type, Int16Type,
+        Int32Type, Int64Type, TimestampSecondType, UInt8Type, UInt16Type, UInt32Type, UInt64Type,
+    },
+};
+use arrow_buffer::{Buffer, NullBuffer, OffsetBuffer, ScalarBuffer, i256};
+use arrow_cast::{CastOptions, cast, cast_with_options};
+use arrow_schema::{DataType, Field, Fields, Schema, TimeUnit, UnionFields, UnionMode};
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+use crate::{
+    ByteExt as _, ParsedBlock, Result,
+    error::Error,
+    mark::{Dynamic, LowCardinality, Mark, Tuple, Variant},
+    parse::consts::{HAS_ADDITIONAL_KEYS_BIT, TUINT8, TUINT16, TUINT32, TUINT64},
+    types::{Offsets, Type},
+    write,
+};
+
+/// Converts the block into a record batch with a column per block column.
+pub fn record_batch(block: &ParsedBlock<'_>) -> Result<RecordBatch> {
+    let mut fields = Vec::with_capacity(block.markers.len());
+    let mut columns = Vec::with_capacity(block.markers.len());
+    for ((name, typ), mark) in block.col_names.iter().zip(&block.types).zip(&block.markers) {
+        let column = array(typ, mark)?;
+        fields.push(Field::new(
+            *name,
+            column.data_type().clone(),
+            is_nullable(typ),
+        ));
+        columns.push(column);
+    }
+
+    let options = RecordBatchOptions::new().with_row_count(Some(block.num_rows));
+    Ok(RecordBatch::try_new_with_options(
+        Arc::new(Schema::new(fields)),
+        columns,
+        &options,
+    )?)
+}
+
+/// The Arrow type [`array`] produces for a column of type `typ`. `Dynamic` and `JSON` have no
+/// such type: the union members of a `Dynamic` column depend on the block.
+pub fn data_type(typ: &Type<'_>) -> Result<DataType> {
+    let data_type = match typ {
+        Type::Bool => DataType::Boolean,
+        Type::Int8 => DataType::Int8,
+        Type::Int16 => DataType::Int16,
+        Type::Int32 => DataType::Int32,
+        Type::Int64 => DataType::Int64,
+        Type::UInt8 => DataType::UInt8,
+        Type::UInt16 => DataType::UInt16,
+        Type::UInt32 | Type::Ipv4 => DataType::UInt32,
+        Type::UInt64 => DataType::UInt64,
+        Type::Int128 | Type::UInt128 | Type::Uuid | Type::Ipv6 => DataType::FixedSizeBinary(16),
+        Type::Int256 | Type::UInt256 => DataType::FixedSizeBinary(32),
+        Type::Float32 | Type::BFloat16 => DataType::Float32,
+        Type::Float64 => DataType::Float64,
+        Type::Decimal32(precision, scale)
+        | Type::Decimal64(precision, scale)
+        | Type::Decimal128(precision, scale) => {
+            DataType::Decimal128(*precision, i8::try_from(*scale)?)
+        }
+        Type::Decimal256(precision, scale) => {
+            DataType::Decimal256(*precision, i8::try_from(*scale)?)
+        }
+        Type::String | Type::Enum8(_) | Type::Enum16(_) => DataType::Utf8,
+        Type::FixedString(size) => DataType::FixedSizeBinary(i32::try_from(*size)?),
+        Type::Date | Type::Date32 => DataType::Date32,
+        Type::DateTime(tz) => DataType::Timestamp(TimeUnit::Second, Some(tz.name().into())),
+        Type::DateTime64(precision, tz) => {
+            DataType::Timestamp(time_unit(*precision)?.0, Some(tz.name().into()))
+        }
+        Type::Nullable(inner) => data_type(inner)?,
+        Type::LowCardinality(inner) => DataType::Dictionary(
+            Box::new(DataType::UInt32),
+            Box::new(data_type(inner.strip_null())?),
+        ),
+        Type::Array(inner) => DataType::List(Arc::new(Field::new(
+            "item",
+            data_type(inner)?,
+            is_nullable(inner),
+        ))),
+        Type::Map(key, value) => {
+            let entries = DataType::Struct(Fields::from(vec![
+                Field::new("keys", data_type(key)?, false),
+                Field::new("values", data_type(value)?, is_nullable(value)),
+            ]));
+            DataType::Map(Arc::new(Field::new("entries", entries, false)), false)
+        }
+        Type::Tuple(types, names) => DataType::Struct(
+            element_names(types, names)
+                .zip(types)
+                .map(|(name, typ)| Ok(Field::new(name, data_type(typ)?, is_nullable(typ))))
+                .collect::<Result<Fields>>()?,
+        ),
+        Type::Nested(fields) => {
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    Ok(Field::new(
+                        field.name,
+                        data_type(&field.typ)?,
+                        is_nullable(&field.typ),
+                    ))
+                })
+                .collect::<Result<Fields>>()?;
+            DataType::List(Arc::new(Field::new(
+                "item",
+                DataType::Struct(fields),
+                false,
+            )))
+        }
+        Type::Variant(types) => {
+            let fields = types
+                .iter()
+                .map(|typ| Ok(Field::new(typ.to_string(), data_type(typ)?, true)))
+                .chain([Ok(null_field())])
+                .collect::<Result<Vec<_>>>()?;
+            DataType::Union(union_fields(fields)?, UnionMode::Dense)
+        }
+        Type::Point
+        | Type::Ring
+        | Type::Polygon
+        | Type::MultiPolygon
+        | Type::LineString
+        | Type::MultiLineString => data_type(&geo(typ)?)?,
+        Type::Dynamic | Type::Json | Type::SharedVariant => {
+            return Err(Error::NotImplemented(format!("Arrow type for {typ}")));
+        }
+    };
+
+    Ok(data_type)
+}
+
+/// Converts a column of type `typ` to an Arrow array.
+pub fn array(typ: &Type<'_>, mark: &Mark<'_>) -> Result<ArrayRef> {
+    let array: ArrayRef = match (typ, mark) {
+        (Type::Bool, Mark::Bool(bytes)) => Arc::new(BooleanArray::from(
+            bytes.iter().map(|&byte| byte != 0).collect::<Vec<_>>(),
+        )),
+        (Type::Int8, Mark::Int8(bv)) => Arc::new(primitive::<Int8Type>(bv.as_bytes())),
+        (Type::Int16, Mark::Int16(bv)) => Arc::new(primitive::<Int16Type>(bv.as_bytes())),
+        (Type::Int32, Mark::Int32(bv)) => Arc::new(primitive::<Int32Type>(bv.as_bytes())),
+        (Type::Int64, Mark::Int64(bv)) => Arc::new(primitive::<Int64Type>(bv.as_bytes())),
+        (Type::UInt8, Mark::UInt8(bv)) => Arc::new(primitive::<UInt8Type>(bv.as_bytes())),
+        (Type::UInt16, Mark::UInt16(bv)) => Arc::new(primitive::<UInt16Type>(bv.as_bytes())),
+        (Type::UInt32, Mark::UInt32(bv)) => Arc::new(primitive::<UInt32Type>(bv.as_bytes())),
+        (Type::UInt64, Mark::UInt64(bv)) => Arc::new(primitive::<UInt64Type>(bv.as_bytes())),
+        (Type::Int128, Mark::Int128(bv)) => fixed_size_binary(16, bv.as_bytes())?,
+        (Type::UInt128, Mark::UInt128(bv)) => fixed_size_binary(16, bv.as_bytes())?,
+        (Type::Int256, Mark::Int256(bv)) => fixed_size_binary(32, bv.as_bytes())?,
+        (Type::UInt256, Mark::UInt256(bv)) => fixed_size_binary(32, bv.as_bytes())?,
+        (Type::Float32, Mark::Float32(bv)) => Arc::new(primitive::<Float32Type>(bv.as_bytes())),
+        (Type::Float64, Mark::Float64(bv)) => Arc::new(primitive::<Float64Type>(bv.as_bytes())),
+        (Type::BFloat16, Mark::BFloat16(bv)) => Arc::new(Float32Array::from_iter_values(
+            bv.as_slice()
+                .iter()
+                .map(|&value| half::bf16::from(value).to_f32()),
+        )),
+        (Type::Decimal32(precision, scale), Mark::Decimal32(d)) => Arc::new(
+            Decimal128Array::from_iter_values(
+                d.data
+                    .as_slice()
+                    .iter()
+                    .map(|value| i128::from(value.0.get())),
+            )
+            .with_precision_and_scale(*precision, i8::try_from(*scale)?)?,
+        ),
+        (Type::Decimal64(precision, scale), Mark::Decimal64(d)) => Arc::new(
+            Decimal128Array::from_iter_values(
+                d.data
+                    .as_slice()
+                    .iter()
+                    .map(|value| i128::from(value.0.get())),
+            )
+            .with_precision_and_scale(*precision, i8::try_from(*scale)?)?,
+        ),
+        (Type::Decimal128(precision, scale), Mark::Decimal128(d)) => Arc::new(
+            primitive::<Decimal128Type>(d.data.as_bytes())
+                .with_precision_and_scale(*precision, i8::try_from(*scale)?)?,
+        ),
+        (Type::Decimal256(precision, scale), Mark::Decimal256(d)) => Arc::new(
+            Decimal256Array::from_iter_values(
+                d.data
+                    .as_slice()
+                    .iter()
+                    .map(|value| i256::from_le_bytes(value.0.0)),
+            )
+            .with_precision_and_scale(*precision, i8::try_from(*scale)?)?,
+        ),
+        (Type::String, Mark::String(strings)) => Arc::new(StringArray::from_iter_values(
+            strings
+                .iter()
+                .map(|s| s.to_utf8())
+                .collect::<Result<Vec<_>>>()?,
+        )),
+        (Type::FixedString(size), Mark::FixedString(fs)) => {
+            fixed_size_binary(i32::try_from(*size)?, fs.data)?
+        }
+        (Type::Uuid, Mark::Uuid(bv)) => {
+            // ClickHouse stores the two halves as little-endian integers, Arrow readers expect
+            // the RFC 4122 byte order
+            let bytes = bv
+                .as_slice()
+                .iter()
+                .flat_map(|&uuid| Uuid::from(uuid).into_bytes())
+                .collect::<Vec<_>>();
+            fixed_size_binary(16, &bytes)?
+        }
+        (Type::Date, Mark::Date(bv)) => Arc::new(Date32Array::from_iter_values(
+            bv.as_slice().iter().map(|value| i32::from(value.0.get())),
+        )),
+        (Type::Date32, Mark::Date32(bv)) => Arc::new(primitive::<Date32Type>(bv.as_bytes())),
+        (Type::DateTime(tz), Mark::DateTime(d)) => Arc::new(
+            TimestampSecondArray::from_iter_values(
+                d.data
+                    .as_slice()
+                    .iter()
+                    .map(|value| i64::from(value.0.get())),
+            )
+            .with_timezone(tz.name()),
+        ),
+        (Type::DateTime64(precision, tz), Mark::DateTime64(d)) => {
+            timestamps(*precision, *tz, d.data.as_slice().iter().map(|v| v.0.get()))?
+        }
+        (Type::Ipv4, Mark::Ipv4(bv)) => Arc::new(primitive::<UInt32Type>(bv.as_bytes())),
+        (Type::Ipv6, Mark::Ipv6(bv)) => fixed_size_binary(16, bv.as_bytes())?,
+        (Type::Enum8(_), Mark::Enum8(e)) => {
+            enum_names(&e.variants, e.data.as_slice().iter().copied())?
+        }
+        (Type::Enum16(_), Mark::Enum16(e)) => enum_names(
+            &e.variants,
+            e.data.as_slice().iter().map(|value| value.get()),
+        )?,
+        (Type::Nullable(inner), Mark::Nullable(n)) => {
+            let values = array(inner, &n.data)?;
+            if n.mask.len() != values.len() {
+                return Err(Error::CorruptedData(format!(
+                    "Null map has {} rows, values have {}",
+                    n.mask.len(),
+                    values.len()
+                )));
+            }
+            let nulls = NullBuffer::from(n.mask.iter().map(|&null| null == 0).collect::<Vec<_>>());
+            make_array(values.to_data().into_builder().nulls(Some(nulls)).build()?)
+        }
+        (Type::LowCardinality(inner), Mark::LowCardinality(lc)) => dictionary(inner, lc)?,
+        (Type::Array(inner), Mark::Array(a)) => {
+            let values = array(inner, &a.values)?;
+            list(is_nullable(inner), &a.offsets, values)?
+        }
+        (Type::Map(key, value), Mark::Map(m)) => {
+            let keys = array(key, &m.keys)?;
+            let values = array(value, &m.values)?;
+            let fields = Fields::from(vec![
+                Field::new("keys", keys.data_type().clone(), false),
+                Field::new("values", values.data_type().clone(), is_nullable(value)),
+            ]);
+            let entries = StructArray::try_new(fields, vec![keys, values], None)?;
+            let field = Field::new("entries", entries.data_type().clone(), false);
+            Arc::new(MapArray::try_new(
+                Arc::new(field),
+                offsets(&m.offsets)?,
+                entries,
+                None,
+                false,
+            )?)
+        }
+        (Type::Tuple(types, names), Mark::Tuple(tuple)) => {
+            structure(element_names(types, names), types.iter(), tuple)?
+        }
+        (Type::Nested(fields), Mark::Nested(n)) => {
+            let Mark::Array(a) = n.array_of_tuples.as_ref() else {
+                return Err(mismatch(typ, &n.array_of_tuples));
+            };
+            let Mark::Tuple(tuple) = a.values.as_ref() else {
+                return Err(mismatch(typ, &a.values));
+            };
+            let names = fields.iter().map(|field| field.name.to_owned());
+            let values = structure(names, fields.iter().map(|field| &field.typ), tuple)?;
+            list(false, &a.offsets, values)?
+        }
+        (Type::Variant(types), Mark::Variant(v)) => variant(types, v)?,
+        (Type::Dynamic, Mark::Dynamic(d)) => dynamic(d)?,
+        (
+            Type::Point
+            | Type::Ring
+            | Type::Polygon
+            | Type::MultiPolygon
+            | Type::LineString
+            | Type::MultiLineString,
+            mark,
+        ) => array(&geo(typ)?, mark)?,
+        (Type::Json, _) => return Err(Error::NotImplemented("Arrow array for JSON".to_owned())),
+        // columns without rows may be decoded without their structure
+        (typ, Mark::Empty) => new_empty_array(&data_type(typ)?),
+        (typ, mark) => return Err(mismatch(typ, mark)),
+    };
+
+    Ok(array)
+}
+
+/// Whether the Arrow field for a column of type `typ` is nullable.
+pub(crate) fn is_nullable(typ: &Type<'_>) -> bool {
+    match typ {
+        Type::Nullable(_) | Type::Variant(_) | Type::Dynamic => true,
+        Type::LowCardinality(inner) => matches!(inner.as_ref(), Type::Nullable(_)),
+        _ => false,
+    }
+}
+
+fn mismatch(typ: &Type<'_>, mark: &Mark<'_>) -> Error {
+    Error::CorruptedData(format!("{typ} column holds {} data", mark.as_str()))
+}
+
+/// The plain type a geo type is stored as.
+fn geo(typ: &Type<'_>) -> Result<Type<'static>> {
+    Ok(match typ {
+        Type::Point => Type::Tuple(vec![Type::Float64, Type::Float64], Box::default()),
+        Type::Ring | Type::LineString => Type::Array(Box::new(Type::Point)),
+        Type::Polygon | Type::MultiLineString => Type::Array(Box::new(Type::Ring)),
+        Type::MultiPolygon => Type::Array(Box::new(Type::Polygon)),
+        _ => return Err(Error::NotImplemented(format!("{typ} as a geo type"))),
+    })
+}
+
+/// Copies fixed-width little-endian values into an Arrow array at once.
+fn primitive<T: ArrowPrimitiveType>(bytes: &[u8]) -> PrimitiveArray<T> {
+    let width = size_of::<T::Native>();
+    let buffer = if cfg!(target_endian = "little") {
+        Buffer::from_slice_ref(bytes)
+    } else {
+        let mut swapped = bytes.to_vec();
+        swapped.chunks_exact_mut(width).for_each(<[u8]>::reverse);
+        Buffer::from_slice_ref(&swapped)
+    };
+    PrimitiveArray::new(ScalarBuffer::new(buffer, 0, bytes.len() / width), None)
+}
+
+/// Arrow time unit for a `DateTime64` precision, with the factor to scale values by.
+fn time_unit(precision: u8) -> Result<(TimeUnit, i64)> {
+    let (unit, digits) = match precision {
+        0 => (TimeUnit::Second, 0),
+        1..=3 => (TimeUnit::Millisecond, 3),
+        4..=6 => (TimeUnit::Microsecond, 6),
+        7..=9 => (TimeUnit::Nanosecond, 9),
+        _ => {
+            return Err(Error::ValueOutOfRange(
+                "u8",
+                "DateTime64 precision",
+                precision.to_string(),
+            ));
+        }
+    };
+
+    Ok((unit, 10_i64.pow(u32::from(digits - precision))))
+}
+
+fn timestamps(precision: u8, tz: Tz, values: impl Iterator<Item = i64>) -> Result<ArrayRef> {
+    let (unit, factor) = time_unit(precision)?;
+    let values = values
+        .map(|value| {
+            value
+                .checked_mul(factor)
+                .ok_or_else(|| Error::Overflow(format!("DateTime64({precision}) value {value}")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let array: ArrayRef = match unit {
+        TimeUnit::Second => Arc::new(TimestampSecondArray::from(values).with_timezone(tz.name())),
+        TimeUnit::Millisecond => {
+            Arc::new(TimestampMillisecondArray::from(values).with_timezone(tz.name()))
+        }
+        TimeUnit::Microsecond => {
+            Arc::new(TimestampMicrosecondArray::from(values).with_timezone(tz.name()))
+        }
+        TimeUnit::Nanosecond => {
+            Arc::new(TimestampNanosecondArray::from(values).with_timezone(tz.name()))
+        }
+    };
+    Ok(array)
+}
+
+fn fixed_size_binary(size: i32, bytes: &[u8]) -> Result<ArrayRef> {
+    let array = FixedSizeBinaryArray::try_new(size, Buffer::from_slice_ref(bytes), None)?;
+    Ok(Arc::new(array))
+}
+
+fn enum_names<T>(
+    variants: &[(Cow<'_, str>, T)],
+    values: impl Iterator<Item = T>,
+) -> Result<ArrayRef>
+where
+    T: Copy + Ord + ToString,
+{
+    let names = values
+        .map(|value| {
+            variants
+                .binary_search_by_key(&value, |(_, id)| *id)
+                .map(|index| variants[index].0.as_ref())
+                .map_err(|_| {
+                    Error::CorruptedData(format!("Unknown enum value {}", value.to_string()))
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(StringArray::from(names)))
+}
+
+/// ClickHouse offsets are the end of every row, Arrow offsets also have the start of the first.
+fn offsets(offsets: &Offsets<'_>) -> Result<OffsetBuffer<i32>> {
+    let mut arrow = Vec::with_capacity(offsets.len() + 1);
+    arrow.push(0);
+    for offset in offsets.as_slice() {
+        let offset = i32::try_from(offset.get())?;
+        if arrow.last().is_some_and(|&previous| previous > offset) {
+            return Err(Error::CorruptedData(format!(
+                "Offsets decrease to {offset}"
+            )));
+        }
+        arrow.push(offset);
+    }
+    Ok(OffsetBuffer::new(ScalarBuffer::from(arrow)))
+}
+
+fn list(nullable: bool, ends: &Offsets<'_>, values: ArrayRef) -> Result<ArrayRef> {
+    let field = Field::new("item", values.data_type().clone(), nullable);
+    let array = ListArray::try_new(Arc::new(field), offsets(ends)?, values, None)?;
+    Ok(Arc::new(array))
+}
+
+/// Struct field names for the elements of a tuple: their names, or their positions counting
+/// from 1 if the tuple is unnamed.
+fn element_names<'n>(types: &[Type<'_>], names: &'n [&str]) -> impl Iterator<Item = String> + 'n {
+    let positions = (names.is_empty()).then(|| (1..=types.len()).map(|index| index.to_string()));
+    names
+        .iter()
+        .map(ToString::to_string)
+        .chain(positions.into_iter().flatten())
+}
+
+fn structure<'t>(
+    names: impl Iterator<Item = String>,
+    types: impl Iterator<Item = &'t Type<'t>>,
+    tuple: &Tuple<'_>,
+) -> Result<ArrayRef> {
+    let mut fields = Vec::with_capacity(tuple.values.len());
+    let mut arrays = Vec::with_capacity(tuple.values.len());
+    for ((name, typ), mark) in names.zip(types).zip(&tuple.values) {
+        let array = array(typ, mark)?;
+        fields.push(Field::new(
+            name,
+            array.data_type().clone(),
+            is_nullable(typ),
+        ));
+        arrays.push(array);
+    }
+    if arrays.is_empty() {
+        return Err(Error::NotImplemented(
+            "Arrow array for an empty Tuple".to_owned(),
+        ));
+    }
+
+    Ok(Arc::new(StructArray::try_new(
+        Fields::from(fields),
+        arrays,
+        None,
+    )?))
+}
+
+fn dictionary(inner: &Type<'_>, lc: &LowCardinality<'_>) -> Result<ArrayRef> {
+    let num_rows = lc.indices.num_rows().unwrap_or_default();
+    let mut keys = Vec::with_capacity(num_rows);
+    for index in 0..num_rows {
+        let key = lc
+            .value_index(index)
+            .ok_or_else(|| mismatch(inner, &lc.indices))?;
+        keys.push(u32::try_from(key)?);
+    }
+    // the first dictionary entry stands for NULL in a nullable column
+    let nulls = lc
+        .is_nullable
+        .then(|| NullBuffer::from(keys.iter().map(|&key| key != 0).collect::<Vec<_>>()));
+
+    let values = match &lc.additional_keys {
+        Some(dictionary) => array(inner.strip_null(), dictionary)?,
+        None => new_empty_array(&data_type(inner.strip_null())?),
+    };
+    let keys = UInt32Array::new(ScalarBuffer::from(keys), nulls);
+    Ok(Arc::new(DictionaryArray::<UInt32Type>::try_new(
+        keys, values,
+    )?))
+}
+
+fn null_field() -> Field {
+    Field::new("Null", DataType::Null, true)
+}
+
+fn union_fields(fields: Vec<Field>) -> Result<UnionFields> {
+    let type_ids = (0..i8::try_from(fields.len())?).collect::<Vec<_>>();
+    Ok(UnionFields::new(type_ids, fields))
+}
+
+/// A dense union of `children`, plus a `Null` member for the rows without a discriminator.
+fn union(
+    mut fields: Vec<Field>,
+    mut children: Vec<ArrayRef>,
+    discriminators: impl Iterator<Item = Option<usize>>,
+    offsets: &[usize],
+) -> Result<ArrayRef> {
+    let null_id = i8::try_from(children.len())?;
+    let mut num_nulls = 0;
+    let mut type_ids = Vec::with_capacity(offsets.len());
+    let mut union_offsets = Vec::with_capacity(offsets.len());
+    for (discriminator, &offset) in discriminators.zip(offsets) {
+        match discriminator {
+            Some(discriminator) => {
+                type_ids.push(i8::try_from(discriminator)?);
+                union_offsets.push(i32::try_from(offset)?);
+            }
+            None => {
+                type_ids.push(null_id);
+                union_offsets.push(num_nulls);
+                num_nulls += 1;
+            }
+        }
+    }
+
+    fields.push(null_field());
+    children.push(Arc::new(NullArray::new(usize::try_from(num_nulls)?)));
+    let array = UnionArray::try_new(
+        union_fields(fields)?,
+        ScalarBuffer::from(type_ids),
+        Some(ScalarBuffer::from(union_offsets)),
+        children,
+    )?;
+    Ok(Arc::new(array))
+}
+
+fn variant(types: &[Type<'_>], v: &Variant<'_>) -> Result<ArrayRef> {
+    let mut fields = Vec::with_capacity(types.len() + 1);
+    let mut children = Vec::with_capacity(types.len() + 1);
+    for (typ, mark) in types.iter().zip(&v.types) {
+        let child = array(typ, mark)?;
+        fields.push(Field::new(typ.to_string(), child.data_type().clone(), true));
+        children.push(child);
+    }
+
+    let discriminators = v
+        .discriminators
+        .iter()
+        .map(|&discriminator| (discriminator != u8::MAX).then_some(usize::from(discriminator)));
+    union(fields, children, discriminators, &v.offsets)
+}
+
+fn dynamic(d: &Dynamic<'_>) -> Result<ArrayRef> {
+    let mut fields = Vec::with_capacity(d.types.len() + 1);
+    let mut children = Vec::with_capacity(d.types.len() + 1);
+    for (index, (typ, mark)) in d.types.iter().zip(&d.columns).enumerate() {
+        let child: ArrayRef = if matches!(typ, Type::SharedVariant) {
+            // values of the shared variant are not decoded, so there is nothing to export
+            if d.discriminators.contains(&index) {
+                return Err(Error::NotImplemented(
+                    "Arrow array for Dynamic values in the shared variant".to_owned(),
+                ));
+            }
+            Arc::new(NullArray::new(0))
+        } else {
+            array(typ, mark)?
+        };
+        fields.push(Field::new(typ.to_string(), child.data_type().clone(), true));
+        children.push(child);
+    }
+
+    union(
+        fields,
+        children,
+        d.discriminators.iter().copied().map(Some),
+        &d.offsets,
+    )
+}
+
+/// The ClickHouse type an Arrow column of type `data_type` is naturally stored as: dictionaries
+/// become `LowCardinality`, lists `Array`, structs `Tuple`, maps `Map`, timestamps
+/// `DateTime64` with the precision of their unit (in UTC without a timezone), strings and
+/// binaries `String`, and `Float16` `Float32`. A nullable column becomes `Nullable` where
+/// ClickHouse allows it, inside the `LowCardinality` for dictionaries.
+pub fn native_type(data_type: &DataType, nullable: bool) -> Result<Type<'static>> {
+    let typ = match data_type {
+        DataType::Boolean => Type::Bool,
+        DataType::Int8 => Type::Int8,
+        DataType::Int16 => Type::Int16,
+        DataType::Int32 => Type::Int32,
+        DataType::Int64 => Type::Int64,
+        DataType::UInt8 => Type::UInt8,
+        DataType::UInt16 => Type::UInt16,
+        DataType::UInt32 => Type::UInt32,
+        DataType::UInt64 => Type::UInt64,
+        DataType::Float16 | DataType::Float32 => Type::Float32,
+        DataType::Float64 => Type::Float64,
+        DataType::Decimal128(precision, scale) => {
+            let scale = u8::try_from(*scale)?;
+            match *precision {
+                0..=9 => Type::Decimal32(*precision, scale),
+                10..=18 => Type::Decimal64(*precision, scale),
+                _ => Type::Decimal128(*precision, scale),
+            }
+        }
+        DataType::Decimal256(precision, scale) => {
+            Type::Decimal256(*precision, u8::try_from(*scale)?)
+        }
+        DataType::Utf8
+        | DataType::LargeUtf8
+        | DataType::Utf8View
+        | DataType::Binary
+        | DataType::LargeBinary => Type::String,
+        DataType::FixedSizeBinary(size) => Type::FixedString(usize::try_from(*size)?),
+        DataType::Date32 | DataType::Date64 => Type::Date32,
+        DataType::Timestamp(unit, tz) => {
+            let precision = match unit {
+                TimeUnit::Second => 0,
+                TimeUnit::Millisecond => 3,
+                TimeUnit::Microsecond => 6,
+                TimeUnit::Nanosecond => 9,
+            };
+            let tz = match tz {
+                Some(tz) => tz
+                    .parse::<Tz>()
+                    .map_err(|_| Error::Parse(format!("Unknown time zone {tz}")))?,
+                None => Tz::UTC,
+            };
+            Type::DateTime64(precision, tz)
+        }
+        // the nulls of a dictionary column are in its keys
+        DataType::Dictionary(_, value) => {
+            return Ok(Type::LowCardinality(Box::new(native_type(
+                value, nullable,
+            )?)));
+        }
+        DataType::List(field) | DataType::LargeList(field) | DataType::FixedSizeList(field, _) => {
+            Type::Array(Box::new(native_type(
+                field.data_type(),
+                field.is_nullable(),
+            )?))
+        }
+        DataType::Struct(fields) => Type::Tuple(
+            fields
+                .iter()
+                .map(|field| native_type(field.data_type(), field.is_nullable()))
+                .collect::<Result<_>>()?,
+            Box::default(),
+        ),
+        DataType::Map(entries, _) => {
+            let DataType::Struct(fields) = entries.data_type() else {
+                return Err(Error::CorruptedData(format!(
+                    "Map entries are {}",
+                    entries.data_type()
+                )));
+            };
+            let [key, value] = fields.iter().as_slice() else {
+                return Err(Error::CorruptedData(format!(
+                    "Map entries have {} fields",
+                    fields.len()
+                )));
+            };
+            Type::Map(
+                Box::new(native_type(key.data_type(), false)?),
+                Box::new(native_type(value.data_type(), value.is_nullable())?),
+            )
+        }
+        _ => {
+            return Err(Error::NotImplemented(format!(
+                "ClickHouse type for Arrow {data_type}"
+            )));
+        }
+    };
+
+    // ClickHouse does not wrap composite types in Nullable
+    let composite = matches!(typ, Type::Array(_) | Type::Tuple(..) | Type::Map(..));
+    Ok(if nullable && !composite {
+        Type::Nullable(Box::new(typ))
+    } else {
+        typ
+    })
+}
+
+/// Encodes the batch as a Native block whose columns have the given types, appending it to
+/// `out`; nothing is appended on error.
+///
+/// Columns are first cast to the Arrow type [`data_type`] gives for their ClickHouse type, so
+/// e.g. `Int32` values fill an `Int64` column, strings fill a `LowCardinality(String)` one and
+/// timestamps of any unit and timezone fill a `DateTime64`. Values that do not fit the target,
+/// and NULLs in columns that cannot hold them, are errors. `Variant`, `Dynamic` and `JSON`
+/// columns are not supported.
+pub fn native_block(batch: &RecordBatch, types: &[Type<'_>], out: &mut Vec<u8>) -> Result<()> {
+    if types.len() != batch.num_columns() {
+        return Err(Error::ProgrammingError(format!(
+            "{} types for {} columns",
+            types.len(),
+            batch.num_columns()
+        )));
+    }
+
+    let start = out.len();
+    let result = write_block(batch, types, out);
+    if result.is_err() {
+        out.truncate(start);
+    }
+    result
+}
+
+fn write_block(batch: &RecordBatch, types: &[Type<'_>], out: &mut Vec<u8>) -> Result<()> {
+    let options = CastOptions {
+        safe: false,
+        ..CastOptions::default()
+    };
+
+    write::varuint(out, u64::try_from(batch.num_columns())?);
+    write::varuint(out, u64::try_from(batch.num_rows())?);
+    let schema = batch.schema();
+    for ((field, column), typ) in schema.fields().iter().zip(batch.columns()).zip(types) {
+        write::var_str(out, field.name().as_bytes());
+        write::var_str(out, typ.to_string().as_bytes());
+        write::prefix(out, typ);
+
+        let target = data_type(typ)?;
+        if column.data_type() == &target {
+            encode(typ, column, out)?;
+        } else {
+            encode(typ, &cast_with_options(column, &target, &options)?, out)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the data of `array`, which has the Arrow type [`data_type`] gives for `typ`.
+fn encode(typ: &Type<'_>, array: &dyn Array, out: &mut Vec<u8>) -> Result<()> {
+    fn le<T: Copy, const N: usize>(out: &mut Vec<u8>, values: &[T], bytes: fn(T) -> [u8; N]) {
+        for &value in values {
+            out.extend_from_slice(&bytes(value));
+        }
+    }
+
+    if !matches!(typ, Type::Nullable(_) | Type::LowCardinality(_)) && array.null_count() > 0 {
+        return Err(unexpected_null(typ));
+    }
+
+    match typ {
+        Type::Bool => out.extend(array.as_boolean().values().iter().map(u8::from)),
+        Type::Int8 => le(
+            out,
+            array.as_primitive::<Int8Type>().values(),
+            i8::to_le_bytes,
+        ),
+        Type::Int16 => le(
+            out,
+            array.as_primitive::<Int16Type>().values(),
+            i16::to_le_bytes,
+        ),
+        Type::Int32 => le(
+            out,
+            array.as_primitive::<Int32Type>().values(),
+            i32::to_le_bytes,
+        ),
+        Type::Int64 => le(
+            out,
+            array.as_primitive::<Int64Type>().values(),
+            i64::to_le_bytes,
+        ),
+        Type::UInt8 => le(
+            out,
+            array.as_primitive::<UInt8Type>().values(),
+            u8::to_le_bytes,
+        ),
+        Type::UInt16 => le(
+            out,
+            array.as_primitive::<UInt16Type>().values(),
+            u16::to_le_bytes,
+        ),
+        Type::UInt32 | Type::Ipv4 => {
+            le(
+                out,
+                array.as_primitive::<UInt32Type>().values(),
+                u32::to_le_bytes,
+            );
+        }
+        Type::UInt64 => le(
+            out,
+            array.as_primitive::<UInt64Type>().values(),
+            u64::to_le_bytes,
+        ),
+        Type::Float32 => le(
+            out,
+            array.as_primitive::<Float32Type>().values(),
+            f32::to_le_bytes,
+        ),
+        Type::Float64 => le(
+            out,
+            array.as_primitive::<Float64Type>().values(),
+            f64::to_le_bytes,
+        ),
+        Type::BFloat16 => {
+            for &value in array.as_primitive::<Float32Type>().values() {
+                out.extend_from_slice(&half::bf16::from_f32(value).to_le_bytes());
+            }
+        }
+        Type::Int128
+        | Type::UInt128
+        | Type::Int256
+        | Type::UInt256
+        | Type::Ipv6
+        | Type::FixedString(_) => {
+            let array = array.as_fixed_size_binary();
+            for index in 0..array.len() {
+                out.extend_from_slice(array.value(index));
+            }
+        }
+        Type::Uuid => {
+            let array = array.as_fixed_size_binary();
+            for index in 0..array.len() {
+                let uuid = Uuid::from_slice(array.value(index))
+                    .map_err(|err| Error::CorruptedData(err.to_string()))?;
+                let (hi, lo) = uuid.as_u64_pair();
+                out.extend_from_slice(&hi.to_le_bytes());
+                out.extend_from_slice(&lo.to_le_bytes());
+            }
+        }
+        Type::Decimal32(..) => {
+            for &value in array.as_primitive::<Decimal128Type>().values() {
+                out.extend_from_slice(&i32::try_from(value)?.to_le_bytes());
+            }
+        }
+        Type::Decimal64(..) => {
+            for &value in array.as_primitive::<Decimal128Type>().values() {
+                out.extend_from_slice(&i64::try_from(value)?.to_le_bytes());
+            }
+        }
+        Type::Decimal128(..) => {
+            le(
+                out,
+                array.as_primitive::<Decimal128Type>().values(),
+                i128::to_le_bytes,
+            );
+        }
+        Type::Decimal256(..) => {
+            le(
+                out,
+                array.as_primitive::<Decimal256Type>().values(),
+                i256::to_le_bytes,
+            );
+        }
+        Type::String => {
+            let array = array.as_string::<i32>();
+            for index in 0..array.len() {
+                write::var_str(out, array.value(index).as_bytes());
+            }
+        }
+        Type::Enum8(variants) => {
+            let array = array.as_string::<i32>();
+            for index in 0..array.len() {
+                let value = enum_value(variants, array.value(index), "Enum8")?;
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        Type::Enum16(variants) => {
+            let array = array.as_string::<i32>();
+            for index in 0..array.len() {
+                let value = enum_value(variants, array.value(index), "Enum16")?;
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        Type::Date => {
+            for &days in array.as_primitive::<Date32Type>().values() {
+                let days = u16::try_from(days)
+                    .map_err(|_| Error::ValueOutOfRange("u16", "Date", days.to_string()))?;
+                out.extend_from_slice(&days.to_le_bytes());
+            }
+        }
+        Type::Date32 => le(
+            out,
+            array.as_primitive::<Date32Type>().values(),
+            i32::to_le_bytes,
+        ),
+        Type::DateTime(_) => {
+            for &seconds in array.as_primitive::<TimestampSecondType>().values() {
+                let seconds = u32::try_from(seconds)
+                    .map_err(|_| Error::ValueOutOfRange("u32", "DateTime", seconds.to_string()))?;
+                out.extend_from_slice(&seconds.to_le_bytes());
+            }
+        }
+        Type::DateTime64(precision, _) => {
+            let (_, factor) = time_unit(*precision)?;
+            // every timestamp unit is an i64 count
+            let values = cast(array, &DataType::Int64)?;
+            for &value in values.as_primitive::<Int64Type>().values() {
+                out.extend_from_slice(&value.div_euclid(factor).to_le_bytes());
+            }
+        }
+        Type::Nullable(inner) => {
+            match array.logical_nulls() {
+                Some(nulls) => out.extend(nulls.iter().map(|valid| u8::from(!valid))),
+                None => out.resize(out.len() + array.len(), 0),
+            }
+            encode(inner, &without_nulls(array)?, out)?;
+        }
+        Type::LowCardinality(inner) => low_cardinality(inner, array, out)?,
+        Type::Array(inner) => {
+            let list = array.as_list::<i32>();
+            let (start, len) = ends(list.offsets(), out)?;
+            encode(inner, &list.values().slice(start, len), out)?;
+        }
+        Type::Map(key, value) => {
+            let map = array.as_map();
+            let (start, len) = ends(map.offsets(), out)?;
+            let entries = map.entries();
+            encode(key, &entries.column(0).slice(start, len), out)?;
+            encode(value, &entries.column(1).slice(start, len), out)?;
+        }
+        Type::Tuple(types, _) => {
+            for (typ, column) in types.iter().zip(array.as_struct().columns()) {
+                encode(typ, column, out)?;
+            }
+        }
+        // stored as an array of tuples of the fields
+        Type::Nested(fields) => {
+            let types = fields.iter().map(|field| field.typ.clone()).collect();
+            encode(
+                &Type::Array(Box::new(Type::Tuple(types, Box::default()))),
+                array,
+                out,
+            )?;
+        }
+        Type::Point
+        | Type::Ring
+        | Type::Polygon
+        | Type::MultiPolygon
+        | Type::LineString
+        | Type::MultiLineString => encode(&geo(typ)?, array, out)?,
+        Type::Variant(_) | Type::Dynamic | Type::Json | Type::SharedVariant => {
+            return Err(Error::NotImplemented(format!(
+                "Native encoding of {typ} from Arrow"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn unexpected_null(typ: &Type<'_>) -> Error {
+    Error::ValueOutOfRange("NULL", "a non-Nullable column", typ.to_string())
+}
+
+fn enum_value<T: Copy>(variants: &[(Cow<'_, str>, T)], name: &str, typ: &'static str) -> Result<T> {
+    variants
+        .iter()
+        .find(|(variant, _)| variant == name)
+        .map(|&(_, value)| value)
+        .ok_or_else(|| Error::ValueOutOfRange("enum value", typ, name.to_owned()))
+}
+
+/// The array with its validity bitmap dropped, leaving whatever values are at NULL slots.
+fn without_nulls(array: &dyn Array) -> Result<ArrayRef> {
+    Ok(make_array(
+        array.to_data().into_builder().nulls(None).build()?,
+    ))
+}
+
+/// Writes the ClickHouse end offsets of a possibly sliced Arrow list, returning the start and
+/// length of the values it covers.
+fn ends(offsets: &OffsetBuffer<i32>, out: &mut Vec<u8>) -> Result<(usize, usize)> {
+    let first = offsets.first().copied().unwrap_or_default();
+    let last = offsets.last().copied().unwrap_or_default();
+    for &end in offsets.iter().skip(1) {
+        out.extend_from_slice(&u64::try_from(end - first)?.to_le_bytes());
+    }
+    Ok((usize::try_from(first)?, usize::try_from(last - first)?))
+}
+
+/// Writes a dictionary column as a `LowCardinality` one with all its keys in the block.
+fn low_cardinality(inner: &Type<'_>, array: &dyn Array, out: &mut Vec<u8>) -> Result<()> {
+    // a column without rows has no LowCardinality data at all
+    if array.is_empty() {
+        return Ok(());
+    }
+
+    let dictionary = array.as_dictionary::<UInt32Type>();
+    let values = dictionary.values();
+    // ClickHouse keeps the default value first, which also stands for NULL in a nullable column
+    let mut indices = Vec::with_capacity(dictionary.len());
+    for key in dictionary.keys() {
+        let key = key.map(usize::try_from).transpose()?;
+        let index = match key {
+            Some(key) if values.is_valid(key) => u64::try_from(key)? + 1,
+            _ if inner.is_nullable() => 0,
+            _ => return Err(unexpected_null(inner)),
+        };
+        indices.push(index);
+    }
+
+    let size = u64::try_from(values.len())? + 1;
+    let (index_type, width) = match size {
+        ..=0x100 => (TUINT8, 1),
+        0x101..=0x1_0000 => (TUINT16, 2),
+        0x1_0001..=0x1_0000_0000 => (TUINT32, 4),
+        _ => (TUINT64, 8),
+    };
+    out.extend_from_slice(&(index_type | HAS_ADDITIONAL_KEYS_BIT).to_le_bytes());
+    out.extend_from_slice(&size.to_le_bytes());
+    let inner = inner.strip_null();
+    encode(
+        inner,
+        &without_nulls(&new_null_array(values.data_type(), 1))?,
+        out,
+    )?;
+    encode(inner, &without_nulls(values)?, out)?;
+
+    out.extend_from_slice(&u64::try_from(indices.len())?.to_le_bytes());
+    for index in indices {
+        out.extend_from_slice(&index.to_le_bytes()[..width]);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::Int32Array;
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{
+        common::{load, load_testdata},
+        iter_blocks,
+        parse::block::parse_many,
+    };
+
+    /// A single-column batch, `None` if the column has no Arrow counterpart.
+    fn column_batch(name: &str, typ: &Type<'_>, mark: &Mark<'_>) -> Result<Option<RecordBatch>> {
+        let column = match array(typ, mark) {
+            Err(Error::NotImplemented(_)) => return Ok(None),
+            column => column?,
+        };
+        let field = Field::new(name, column.data_type().clone(), is_nullable(typ));
+        let options = RecordBatchOptions::new().with_row_count(Some(column.len()));
+        Ok(Some(RecordBatch::try_new_with_options(
+            Arc::new(Schema::new(vec![field])),
+            vec![column],
+            &options,
+        )?))
+    }
+
+    #[test]
+    fn converts_testdata() -> TestResult {
+        let mut unsupported = Vec::new();
+        for (path, buf) in load_testdata()? {
+            for block in parse_many(&buf)? {
+                for ((name, typ), mark) in
+                    block.col_names.iter().zip(&block.types).zip(&block.markers)
+                {
+                    let array = match array(typ, mark) {
+                        Err(Error::NotImplemented(_)) => {
+                            unsupported.push(format!("{}: {name}", path.display()));
+                            continue;
+                        }
+                        array => array
+                            .map_err(|err| format!("{}: {name} {typ}: {err}", path.display()))?,
+                    };
+                    assert_eq!(array.len(), block.num_rows, "{}: {name}", path.display());
+                    if !matches!(typ, Type::Dynamic) {
+                        assert_eq!(array.data_type(), &data_type(typ)?, "{name} {typ}");
+                    }
+                }
+            }
+        }
+
+        // JSON, also inside Dynamic and Variant columns, has no Arrow counterpart yet
+        unsupported.sort();
+        assert_eq!(
+            unsupported,
+            [
+                "./testdata/dynamic_arr.native: arr",
+                "./testdata/json.native: json",
+                "./testdata/json_arr.native: json_arr",
+                "./testdata/sample.native: json_doc",
+                "./testdata/variant_arr.native: variant",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn record_batch_values() -> TestResult {
+        let buf = load("./testdata/nullable_string.native")?;
+        let blocks = parse_many(&buf)?;
+        let batch = record_batch(&blocks[0])?;
+        assert_eq!(batch.num_rows(), blocks[0].num_rows);
+        assert!(batch.schema().field_with_name("nstr")?.is_nullable());
+        let Mark::Nullable(nullable) = &blocks[0].markers[1] else {
+            panic!("expected a nullable column");
+        };
+        let nulls = nullable.mask.iter().filter(|&&null| null == 1).count();
+        assert_eq!(batch.column(1).null_count(), nulls);
+
+        let buf = load("./testdata/decimal_sample.native")?;
+        let blocks = parse_many(&buf)?;
+        let batch = record_batch(&blocks[0])?;
+        let d32 = batch
+            .column_by_name("d32")
+            .unwrap()
+            .as_primitive::<Decimal128Type>();
+        let mut values = (0..d32.len())
+            .map(|index| d32.value_as_string(index))
+            .collect::<Vec<_>>();
+        values.sort();
+        assert_eq!(values, ["1.234", "2.345", "3.456"]);
+
+        let buf = load("./testdata/array_nullable_int64.native")?;
+        let blocks = parse_many(&buf)?;
+        let batch = record_batch(&blocks[0])?;
+        let list = batch.column_by_name("arr").unwrap().as_list::<i32>();
+        let total = list.values().len();
+        let nulls = list.values().null_count();
+        assert_eq!((total, nulls), (16, 6));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_shared_variant_rows() -> TestResult {
+        let types = vec![Type::Int32, Type::SharedVariant];
+        let one = 1_i32.to_le_bytes();
+        let columns = vec![Mark::Int32(one[..].try_into()?), Mark::Empty];
+        let dynamic = |discriminators: Vec<usize>| {
+            Mark::Dynamic(Box::new(Dynamic {
+                offsets: vec![0; discriminators.len()],
+                discriminators,
+                types: types.clone(),
+                columns: columns.clone(),
+            }))
+        };
+
+        assert_eq!(array(&Type::Dynamic, &dynamic(vec![0]))?.len(), 1);
+
+        let Err(Error::NotImplemented(_)) = array(&Type::Dynamic, &dynamic(vec![0, 1])) else {
+            panic!("expected the shared variant row to be rejected");
+        };
+
+        Ok(())
+    }
+
+    #[test]
+    fn native_round_trip() -> TestResult {
+        for (path, buf) in load_testdata()? {
+            for block in parse_many(&buf)? {
+                for ((name, typ), mark) in
+                    block.col_names.iter().zip(&block.types).zip(&block.markers)
+                {
+                    let Some(batch) = column_batch(name, typ, mark)? else {
+                        continue;
+                    };
+                    let mut out = Vec::new();
+                    // Variant and Dynamic columns are not encoded yet
+                    match native_block(&batch, std::slice::from_ref(typ), &mut out) {
+                        Err(Error::NotImplemented(_)) => {
+                            assert!(out.is_empty());
+                            continue;
+                        }
+                        result => result
+                            .map_err(|err| format!("{}: {name} {typ}: {err}", path.display()))?,
+                    }
+
+                    let decoded = parse_many(&out)?;
+                    assert_eq!(decoded.len(), 1);
+                    assert_eq!(decoded[0].types, std::slice::from_ref(typ));
+                    let round_trip = column_batch(name, typ, &decoded[0].markers[0])?.unwrap();
+                    assert_eq!(round_trip, batch, "{}: {name} {typ}", path.display());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn native_block_casts() -> TestResult {
+        let tags = Field::new("item", DataType::Utf8, true);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new(
+                "name",
+                DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8)),
+                true,
+            ),
+            Field::new(
+                "at",
+                DataType::Timestamp(TimeUnit::Nanosecond, Some("Europe/Berlin".into())),
+                false,
+            ),
+            Field::new("tags", DataType::List(Arc::new(tags.clone())), false),
+        ]));
+        let mut types = schema
+            .fields()
+            .iter()
+            .map(|field| native_type(field.data_type(), field.is_nullable()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            types.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "Nullable(Int32)",
+                "LowCardinality(Nullable(String))",
+                "DateTime64(9, 'Europe/Berlin')",
+                "Array(Nullable(String))"
+            ]
+        );
+        // a wider column, and a coarser precision in another timezone
+        types[0] = Type::Int64;
+        types[2] = Type::DateTime64(3, Tz::UTC);
+
+        let names = [Some("a"), None, Some("a")]
+            .into_iter()
+            .collect::<DictionaryArray<Int8Type>>();
+        let at = 1_700_000_000_123_456_789;
+        // a sliced list, starting at a non-zero offset
+        let tags = ListArray::try_new(
+            Arc::new(tags),
+            OffsetBuffer::new(ScalarBuffer::from(vec![0, 1, 3, 3, 4])),
+            Arc::new(StringArray::from(vec![
+                Some("x"),
+                Some("y"),
+                None,
+                Some("z"),
+            ])),
+            None,
+        )?
+        .slice(1, 3);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            Arc::new(names),
+            Arc::new(TimestampNanosecondArray::from(vec![at; 3]).with_timezone("Europe/Berlin")),
+            Arc::new(tags),
+        ];
+        let batch = RecordBatch::try_new(Arc::clone(&schema), columns.clone())?;
+
+        let mut out = Vec::new();
+        native_block(&batch, &types, &mut out)?;
+        let blocks = parse_many(&out)?;
+        assert_eq!(blocks[0].types, types);
+        let rows = iter_blocks(&blocks)
+            .map(|row| {
+                let at = row.get("at").unwrap().into::<chrono::DateTime<Tz>>()?;
+                Ok((
+                    row.get("id").unwrap().into::<i64>()?,
+                    row.get("name").unwrap().into::<Option<&str>>()?,
+                    at.timestamp_millis(),
+                    row.get("tags").unwrap().into::<Vec<Option<&str>>>()?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let at = 1_700_000_000_123;
+        assert_eq!(
+            rows,
+            [
+                (1, Some("a"), at, vec![Some("y"), None]),
+                (2, None, at, vec![]),
+                (3, Some("a"), at, vec![Some("z")]),
+            ]
+        );
+
+        // NULLs do not fit a non-Nullable column, and nothing is written on errors
+        let mut columns = columns;
+        columns[0] = Arc::new(Int32Array::from(vec![Some(1), None, Some(3)]));
+        let batch = RecordBatch::try_new(schema, columns)?;
+        let mut out = vec![1];
+        assert!(matches!(
+            native_block(&batch, &types, &mut out),
+            Err(Error::ValueOutOfRange("NULL", ..))
+        ));
+        assert_eq!(out, [1]);
+
+        Ok(())
+    }
+}
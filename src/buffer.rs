@@ -0,0 +1,208 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::{
+    BlocksIterator, ParsedBlock, Result,
+    error::Error,
+    parse::block::{parse_many, parse_single},
+    value::Value,
+};
+
+/// A parsed block that keeps its input buffer alive, so it is `Send + 'static` and can be moved
+/// to another thread or kept in a cache.
+///
+/// The markers borrow from the buffer, so the block is only handed out bound to `&self`.
+pub struct OwnedBlock {
+    // must be declared before the buffer: the block borrows from it
+    block: ParsedBlock<'static>,
+    buffer: Arc<[u8]>,
+}
+
+impl OwnedBlock {
+    /// Parses exactly one block occupying the whole buffer.
+    pub fn parse(buffer: impl Into<Arc<[u8]>>) -> Result<Self> {
+        let buffer = buffer.into();
+        let len = buffer.len();
+        Self::parse_range(buffer, 0..len)
+    }
+
+    /// Parses exactly one block occupying `range` of a shared buffer.
+    pub fn parse_range(buffer: Arc<[u8]>, range: Range<usize>) -> Result<Self> {
+        let Some(input) = buffer.get(range.clone()) else {
+            return Err(Error::IndexOutOfBounds(range.end, "OwnedBlock buffer"));
+        };
+        let (remainder, block) = parse_single(input)?;
+        if !remainder.is_empty() {
+            return Err(Error::Parse(format!(
+                "{} trailing bytes after the block",
+                remainder.len()
+            )));
+        }
+
+        // SAFETY: the block borrows from the heap allocation behind `buffer`, which does not
+        // move and lives as long as `self`. The `'static` lifetime never escapes: the block is
+        // only exposed through `&self`, shortened to the lifetime of that borrow.
+        let block = unsafe { std::mem::transmute::<ParsedBlock<'_>, ParsedBlock<'static>>(block) };
+
+        Ok(Self { block, buffer })
+    }
+
+    pub fn block(&self) -> &ParsedBlock<'_> {
+        &self.block
+    }
+
+    pub fn buffer(&self) -> &Arc<[u8]> {
+        &self.buffer
+    }
+
+    /// The value in row `index` of the column at position `column`.
+    pub fn get(&self, column: usize, index: usize) -> Option<Value<'_>> {
+        self.block.markers.get(column)?.get(index)
+    }
+
+    /// The values in `range` of rows of the column at position `column`, `None` if the range
+    /// does not fit the block.
+    pub fn slice(&self, column: usize, range: Range<usize>) -> Option<Value<'_>> {
+        if range.start > range.end || range.end > self.block.num_rows {
+            return None;
+        }
+        Some(self.block.markers.get(column)?.slice(range))
+    }
+
+    pub fn iter(&self) -> BlocksIterator<'_> {
+        BlocksIterator::new(std::slice::from_ref(&self.block))
+    }
+}
+
+/// All blocks of a buffer, owning that buffer. See [`OwnedBlock`].
+pub struct OwnedBlocks {
+    // must be declared before the buffer: the blocks borrow from it
+    blocks: Vec<ParsedBlock<'static>>,
+    buffer: Arc<[u8]>,
+}
+
+impl OwnedBlocks {
+    pub fn parse(buffer: impl Into<Arc<[u8]>>) -> Result<Self> {
+        let buffer = buffer.into();
+        let blocks = parse_many(&buffer)?;
+
+        // SAFETY: see `OwnedBlock::parse_range`
+        let blocks = unsafe {
+            std::mem::transmute::<Vec<ParsedBlock<'_>>, Vec<ParsedBlock<'static>>>(blocks)
+        };
+
+        Ok(Self { blocks, buffer })
+    }
+
+    pub fn blocks(&self) -> &[ParsedBlock<'_>] {
+        &self.blocks
+    }
+
+    pub fn buffer(&self) -> &Arc<[u8]> {
+        &self.buffer
+    }
+
+    pub fn iter(&self) -> BlocksIterator<'_> {
+        BlocksIterator::new(&self.blocks)
+    }
+}
+
+impl IntoIterator for OwnedBlocks {
+    type Item = OwnedBlock;
+    type IntoIter = std::iter::Map<
+        std::iter::Zip<std::vec::IntoIter<ParsedBlock<'static>>, std::iter::Repeat<Arc<[u8]>>>,
+        fn((ParsedBlock<'static>, Arc<[u8]>)) -> OwnedBlock,
+    >;
+
+    /// Splits into separate blocks, each sharing the same buffer.
+    fn into_iter(self) -> Self::IntoIter {
+        self.blocks
+            .into_iter()
+            .zip(std::iter::repeat(self.buffer))
+            .map(|(block, buffer)| OwnedBlock { block, buffer })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::common::load;
+
+    fn assert_send_static<T: Send + 'static>() {}
+
+    #[test]
+    fn owned_blocks_outlive_input() -> TestResult {
+        assert_send_static::<OwnedBlock>();
+        assert_send_static::<OwnedBlocks>();
+
+        let buf = load("./testdata/plain_strings.native")?;
+        let expected = parse_many(&buf)?
+            .iter()
+            .flat_map(|block| {
+                (0..block.num_rows).map(|row| format!("{:?}", block.markers[0].get(row)))
+            })
+            .collect::<Vec<_>>();
+
+        let blocks = OwnedBlocks::parse(buf)?;
+        let handle = std::thread::spawn(move || {
+            blocks
+                .iter()
+                .map(|mut row| format!("{:?}", row.next().map(|(_, col)| col.get())))
+                .collect::<Vec<_>>()
+        });
+        let actual = handle.join().unwrap();
+
+        assert_eq!(expected, actual);
+
+        Ok(())
+    }
+
+    #[test]
+    fn split_into_single_blocks() -> TestResult {
+        let mut buf = load("./testdata/plain_strings.native")?;
+        buf.extend(load("./testdata/array.native")?);
+
+        let num_blocks = parse_many(&buf)?.len();
+
+        let blocks = OwnedBlocks::parse(buf)?.into_iter().collect::<Vec<_>>();
+        assert_eq!(blocks.len(), num_blocks);
+        assert!(Arc::ptr_eq(blocks[0].buffer(), blocks[1].buffer()));
+
+        let Some(Value::String("hello")) = blocks[0].block().markers[1].get(0) else {
+            panic!("expected a string");
+        };
+        for block in &blocks {
+            assert_eq!(block.iter().count(), block.block().num_rows);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn values_by_position() -> TestResult {
+        let buf = load("./testdata/plain_strings.native")?;
+        let expected = parse_many(&buf)?
+            .first()
+            .map(|block| format!("{:?}", block.markers[1].slice(1..3)))
+            .unwrap();
+
+        let block = OwnedBlocks::parse(buf)?.into_iter().next().unwrap();
+        let num_rows = block.block().num_rows;
+
+        let Some(Value::String("hello")) = block.get(1, 0) else {
+            panic!("expected a string");
+        };
+        assert!(block.get(1, num_rows).is_none());
+        assert!(block.get(2, 0).is_none());
+
+        assert_eq!(
+            format!("{:?}", block.slice(1, 1..3)),
+            format!("Some({expected})")
+        );
+        assert!(block.slice(1, 0..num_rows + 1).is_none());
+        assert!(block.slice(2, 0..1).is_none());
+
+        Ok(())
+    }
+}
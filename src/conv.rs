@@ -25,7 +25,7 @@ pub fn datetime32_tz(secs: u32, tz: Tz) -> DateTime<Tz> {
 }
 #[inline(always)]
 pub fn datetime64(timestamp: i64, precision: u8) -> Option<DateTime<Utc>> {
-    let pow = 10i64.pow(u32::from(precision));
+    let pow = 10i64.checked_pow(u32::from(precision))?;
     let secs = timestamp / pow;
     let rem_ms = (timestamp % pow).abs();
     let nsec = rem_ms.checked_mul(1_000_000)?;
@@ -38,3 +38,15 @@ pub fn datetime64_tz(timestamp: i64, precision: u8, tz: Tz) -> Option<DateTime<T
     let dt_utc = datetime64(timestamp, precision)?;
     Some(dt_utc.with_timezone(&tz))
 }
+
+/// Splits ticks of the precision since the epoch into seconds and nanoseconds. `None` for
+/// precisions finer than nanoseconds, which `DateTime64` does not have.
+#[inline(always)]
+pub fn split_ticks(ticks: i64, precision: u8) -> Option<(i64, u32)> {
+    if precision > 9 {
+        return None;
+    }
+    let scale = 10_i64.pow(u32::from(precision));
+    let nanos = ticks.rem_euclid(scale) * 10_i64.pow(9 - u32::from(precision));
+    Some((ticks.div_euclid(scale), u32::try_from(nanos).ok()?))
+}
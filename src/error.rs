@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -49,6 +49,231 @@ pub enum Error {
 
     #[error("Invalid column order: {0}")]
     InvalidColumnOrder(String),
+
+    #[error("Cannot encode {0} as {1}")]
+    Encode(String, String),
+
+    #[error("Missing column: {0}")]
+    MissingColumn(String),
+
+    #[error("Schema mismatch: {0}")]
+    SchemaMismatch(crate::schema::Diff),
+
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    #[error("Server exception: {0}")]
+    ServerException(Box<ServerException>),
+
+    #[error("{context}: {source}")]
+    Decode {
+        context: Box<Context>,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Whether the error was caused by the input ending too early, i.e. it may go away once
+    /// more bytes are available.
+    pub fn is_incomplete(&self) -> bool {
+        matches!(
+            self.root_cause(),
+            Self::Length(_) | Self::UnexpectedEndOfInput
+        )
+    }
+
+    /// Where the error occurred, if it was raised while decoding a block.
+    pub fn context(&self) -> Option<&Context> {
+        match self {
+            Self::Decode { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The error without the decoding context.
+    pub fn root_cause(&self) -> &Self {
+        match self {
+            Self::Decode { source, .. } => source.root_cause(),
+            err => err,
+        }
+    }
+
+    /// Prepends `segment` to the type path of the error; `offset` is where the nested stream
+    /// starts, relative to the block, and is only kept if no deeper stream recorded one.
+    pub(crate) fn within(self, segment: &str, offset: usize) -> Self {
+        self.with_context(offset, |context| {
+            context.path = if context.path.is_empty() {
+                segment.to_owned()
+            } else {
+                format!("{segment}.{}", context.path)
+            };
+        })
+    }
+
+    pub(crate) fn in_column(self, name: &str, offset: usize) -> Self {
+        self.within(name, offset).with_context(offset, |context| {
+            context.column = Some(name.to_owned());
+        })
+    }
+
+    /// Records the index of the block the error occurred in, making the offset absolute by
+    /// adding `start`, the position of the block in the input.
+    pub(crate) fn in_block(self, index: usize, start: usize) -> Self {
+        self.with_context(0, |context| {
+            context.block = Some(index);
+            context.offset += start;
+        })
+    }
+
+    fn with_context(self, offset: usize, update: impl FnOnce(&mut Context)) -> Self {
+        let (mut context, source) = match self {
+            Self::Decode { context, source } => (context, source),
+            // the exception replaces the block, there is nothing to attribute it to
+            Self::ServerException(_) => return self,
+            err => (
+                Box::new(Context {
+                    block: None,
+                    column: None,
+                    path: String::new(),
+                    offset,
+                }),
+                Box::new(err),
+            ),
+        };
+        update(&mut context);
+        Self::Decode { context, source }
+    }
+}
+
+/// Position of a decoding error inside the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    /// Index of the block, if the error was raised while parsing a sequence of blocks.
+    pub block: Option<usize>,
+
+    /// Name of the column being decoded, if any.
+    pub column: Option<String>,
+
+    /// Position inside the column type, starting with the column name, e.g.
+    /// `value.element.null_map` for the null map of the array values of a
+    /// `Map(String, Array(Nullable(Int64)))` column.
+    pub path: String,
+
+    /// Byte offset of the innermost stream being decoded: relative to the start of the input
+    /// when the block index is known, relative to the block otherwise.
+    pub offset: usize,
+}
+
+impl Display for Context {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(block) = self.block {
+            write!(f, "block {block}, ")?;
+        }
+        if !self.path.is_empty() {
+            write!(f, "column `{}`, ", self.path)?;
+        }
+        write!(f, "byte {}", self.offset)
+    }
+}
+
+/// An exception that ClickHouse appended as plain text to a Native body after it had already
+/// started sending blocks, e.g. `Code: 395. DB::Exception: ... (FUNCTION_THROW_IF_VALUE_IS_NON_ZERO)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerException {
+    pub code: i32,
+
+    /// Symbolic error name, e.g. `MEMORY_LIMIT_EXCEEDED`, if the server included it.
+    pub name: Option<String>,
+    pub message: String,
+    pub stack_trace: Option<String>,
+}
+
+impl ServerException {
+    const PREFIX: &'static [u8] = b"Code: ";
+    const TAG: &'static str = "__exception__";
+
+    /// Parses `input` as an exception trailer, if it is one. Expected to be called at a block
+    /// boundary. Both the classic `Code: ...` text and the newer `__exception__`-framed form
+    /// are recognized.
+    pub fn detect(input: &[u8]) -> Option<Self> {
+        let mut text = input.trim_ascii_start();
+        if let Some(framed) = text.strip_prefix(Self::TAG.as_bytes()) {
+            // `__exception__\r\n<tag>\r\nCode: ...`
+            let mut lines = framed.trim_ascii_start().splitn(2, |&b| b == b'\n');
+            lines.next()?;
+            text = lines.next()?.trim_ascii_start();
+        }
+
+        if !text.starts_with(Self::PREFIX) {
+            return None;
+        }
+        Self::parse(text)
+    }
+
+    /// Whether `input` starts like an exception trailer, which may not be fully received yet.
+    pub fn is_start(input: &[u8]) -> bool {
+        let text = input.trim_ascii_start();
+        text.starts_with(Self::PREFIX) || text.starts_with(Self::TAG.as_bytes())
+    }
+
+    fn parse(text: &[u8]) -> Option<Self> {
+        let rest = text.strip_prefix(Self::PREFIX)?;
+        let digits = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+        let code = std::str::from_utf8(&rest[..digits]).ok()?.parse().ok()?;
+        let rest = rest[digits..].strip_prefix(b". ")?;
+
+        let text = String::from_utf8_lossy(rest);
+        let mut text = text.as_ref();
+        if let Some(end) = text.find(Self::TAG) {
+            // the framed form ends with a `<length> <tag>` line before the closing marker
+            text = text[..end].trim_end();
+            text = text.rsplit_once('\n').map_or(text, |(text, _)| text);
+        }
+
+        let (message, stack_trace) = match text.split_once("Stack trace:") {
+            Some((message, stack_trace)) => (message, Some(stack_trace.trim().to_owned())),
+            None => (text, None),
+        };
+        let message = message.trim();
+        let message = message.strip_prefix("DB::Exception: ").unwrap_or(message);
+
+        Some(Self {
+            code,
+            name: error_name(message).map(str::to_owned),
+            message: message.to_owned(),
+            stack_trace,
+        })
+    }
+}
+
+/// Finds the last `(UPPER_SNAKE_CASE)` group in the message.
+fn error_name(message: &str) -> Option<&str> {
+    message.rmatch_indices('(').find_map(|(pos, _)| {
+        let rest = &message[pos + 1..];
+        let name = &rest[..rest.find(')')?];
+        let valid = !name.is_empty()
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_');
+        valid.then_some(name)
+    })
+}
+
+impl Display for ServerException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Code: {}. DB::Exception: {}", self.code, self.message)
+    }
+}
+
+impl From<ServerException> for Error {
+    fn from(value: ServerException) -> Self {
+        Self::ServerException(Box::new(value))
+    }
 }
 
 impl<T> From<nom::Err<T>> for Error
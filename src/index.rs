@@ -38,7 +38,7 @@ impl<'a> Mark<'a> {
             Mark::Decimal64(d) => d.get(index),
             Mark::Decimal128(d) => d.get(index),
             Mark::Decimal256(d) => d.get(index),
-            Mark::String(strings) => Some(Value::String(strings.get(index)?)),
+            Mark::String(strings) => Some(Value::string(strings.get(index)?)),
             Mark::FixedString(fs) => fs.get(index),
             Mark::Uuid(bv) => {
                 let value = bv.get(index)?;
@@ -202,12 +202,13 @@ impl<'a> Mark<'a> {
     #[inline]
     pub fn get_str(&'a self, index: usize) -> crate::Result<Option<&'a str>> {
         match self {
-            Mark::String(strings) => Ok(strings.get(index).copied()),
+            Mark::String(strings) => strings.get(index).map(|s| s.to_utf8()).transpose(),
             Mark::FixedString(fs) => {
                 let offset = fs.size * index;
-                let slice = fs.data[offset..offset + fs.size].rtrim_zeros();
-                let slice = unsafe { std::str::from_utf8_unchecked(slice) };
-                Ok(Some(slice))
+                let Some(slice) = fs.data.get(offset..offset + fs.size) else {
+                    return Ok(None);
+                };
+                Ok(Some(slice.rtrim_zeros().to_utf8()?))
             }
             Mark::LowCardinality(lc) => {
                 let Some(keys) = &lc.additional_keys else {
@@ -228,7 +229,7 @@ impl<'a> Mark<'a> {
                     return Err(crate::Error::MismatchedType(keys.as_str(), "&str"));
                 };
 
-                Ok(keys.get(value_index).copied())
+                keys.get(value_index).map(|s| s.to_utf8()).transpose()
             }
             mark => Err(crate::Error::MismatchedType(mark.as_str(), "&str")),
         }
@@ -407,7 +408,11 @@ impl<'a> Mark<'a> {
             _ => unreachable!("must never have any other type"),
         };
 
-        Ok(index_it.map(move |idx| keys.get(idx).copied().unwrap()))
+        // checked for UTF-8 up front, so the strings come out infallibly
+        let strings = index_it
+            .map(|idx| keys[idx].to_utf8())
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(strings.into_iter())
     }
 
     #[inline]
@@ -492,7 +497,7 @@ impl<'a> Mark<'a> {
         (Float32, F32),
         (Float64, F64),
         (BFloat16, Bf16Data),
-        (String, &'a str),
+        (String, &'a [u8]),
         (Uuid, UuidData),
         (Date, Date16Data),
         (Date32, Date32Data),
@@ -630,8 +635,11 @@ mod tests {
         let strings_marker = &block.markers[1];
 
         for (i, expected) in expected_arrays.iter().enumerate() {
-            let slice: &[&str] = strings_marker.get(i).unwrap().try_into()?;
-            let actual = slice.iter().copied().collect::<Vec<_>>();
+            let slice: &[&[u8]] = strings_marker.get(i).unwrap().try_into()?;
+            let actual = slice
+                .iter()
+                .map(|s| std::str::from_utf8(s))
+                .collect::<Result<Vec<_>, _>>()?;
 
             assert_eq!(actual, *expected, "Mismatch at index {i}");
         }
@@ -0,0 +1,443 @@
+//! ClickHouse JSON output: rows as `JSONEachRow` objects or `JSONCompactEachRow` arrays, one
+//! per line.
+//!
+//! Values follow the ClickHouse defaults: 64-bit and wider integers are quoted unless
+//! [`Options::quote_64bit_integers`] is off, decimals are numbers, non-finite floats are
+//! `null`, dates, UUIDs and IPs are strings, arrays and unnamed tuples are arrays, named tuples
+//! are objects of their elements, maps are objects with their keys as strings, `Nested` rows
+//! are objects of their fields and `JSON` columns are nested objects.
+
+use std::{
+    fmt::{self, Write as _},
+    io,
+};
+
+use crate::{
+    BlockRow, Result,
+    error::Error,
+    text::{self, Escaping, Item},
+    types::OffsetIndexPair as _,
+    value::{JsonIterator, NestedIterator, Value},
+};
+
+/// The shape of a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// An object keyed by column names, as `JSONEachRow`.
+    EachRow,
+
+    /// An array of the values, as `JSONCompactEachRow`.
+    CompactEachRow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// Whether `Int64`, `UInt64` and wider integers are written as strings, which JavaScript
+    /// reads without losing precision. On by default, as in ClickHouse.
+    pub quote_64bit_integers: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            quote_64bit_integers: true,
+        }
+    }
+}
+
+/// Writes every row followed by a newline, i.e. NDJSON.
+pub fn write_rows<'a>(
+    mut out: impl io::Write,
+    rows: impl IntoIterator<Item = BlockRow<'a>>,
+    format: Format,
+    options: Options,
+) -> Result<()> {
+    let mut line = String::new();
+    for row in rows {
+        line.clear();
+        write_row(&mut line, row, format, options)?;
+        line.push('\n');
+        out.write_all(line.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes a single row, without a trailing newline. The columns are those the row iterates,
+/// in its order. A failure of `out` itself is reported as [`Error::Io`].
+pub fn write_row<W: fmt::Write>(
+    out: &mut W,
+    row: BlockRow<'_>,
+    format: Format,
+    options: Options,
+) -> Result<()> {
+    let row_index = row.row_index();
+    let (open, close) = match format {
+        Format::EachRow => ('{', '}'),
+        Format::CompactEachRow => ('[', ']'),
+    };
+
+    let out = &mut Tracked { out, failed: false };
+    out.write_char(open).map_err(|_| out.error())?;
+    for (position, (name, accessor)) in row.enumerate() {
+        let value = text::column_value(accessor.marker, row_index)?;
+        if position > 0 {
+            out.write_char(',').map_err(|_| out.error())?;
+        }
+        if format == Format::EachRow {
+            write_string(out, name).map_err(|_| out.error())?;
+            out.write_char(':').map_err(|_| out.error())?;
+        }
+        write_item(out, Item::from(value), options).map_err(|_| out.error())?;
+    }
+    out.write_char(close).map_err(|_| out.error())?;
+    Ok(())
+}
+
+/// A writer that remembers whether the one it wraps failed, which tells a failing writer
+/// apart from a value that cannot be written, as both end in a `fmt::Error`.
+struct Tracked<'w, W> {
+    out: &'w mut W,
+    failed: bool,
+}
+
+impl<W> Tracked<'_, W> {
+    fn error(&self) -> Error {
+        if self.failed {
+            Error::Io(io::Error::other(fmt::Error))
+        } else {
+            text::outside_column()
+        }
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for Tracked<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_str(s).inspect_err(|_| self.failed = true)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.out.write_char(c).inspect_err(|_| self.failed = true)
+    }
+}
+
+/// Writes the paths of a `JSON` value as an object, nesting the dotted ones.
+pub(crate) fn write_object<W: fmt::Write>(
+    out: &mut W,
+    paths: JsonIterator<'_>,
+    options: Options,
+) -> fmt::Result {
+    fn object<W: fmt::Write>(
+        out: &mut W,
+        entries: &[(Vec<&str>, Value<'_>)],
+        depth: usize,
+        options: Options,
+    ) -> fmt::Result {
+        out.write_char('{')?;
+        let mut start = 0;
+        while start < entries.len() {
+            let key = entries[start].0[depth];
+            let end = start
+                + entries[start..]
+                    .iter()
+                    .take_while(|(path, _)| path[depth] == key)
+                    .count();
+            if start > 0 {
+                out.write_char(',')?;
+            }
+            write_string(out, key)?;
+            out.write_char(':')?;
+            match &entries[start..end] {
+                [(path, value)] if path.len() == depth + 1 => {
+                    write_item(out, Item::from(value.clone()), options)?;
+                }
+                group => {
+                    let nested = group
+                        .iter()
+                        .position(|(path, _)| path.len() > depth + 1)
+                        .unwrap_or(group.len());
+                    object(out, &group[nested..], depth + 1, options)?;
+                }
+            }
+            start = end;
+        }
+        out.write_char('}')
+    }
+
+    let mut entries = paths
+        .map(|(path, value)| (path.split('.').collect::<Vec<_>>(), value))
+        .collect::<Vec<_>>();
+    entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+    object(out, &entries, 0, options)
+}
+
+fn write_item<W: fmt::Write>(out: &mut W, item: Item<'_>, options: Options) -> fmt::Result {
+    let value = match item {
+        Item::Decimal(unscaled, scale) => {
+            return text::decimal(out, &unscaled.digits(), scale);
+        }
+        item @ Item::DateTime { .. } => return quoted(out, item),
+        Item::Value(value) => value,
+    };
+
+    match value {
+        Value::Empty => out.write_str("null"),
+        Value::Bool(_)
+        | Value::Int8(_)
+        | Value::Int16(_)
+        | Value::Int32(_)
+        | Value::UInt8(_)
+        | Value::UInt16(_)
+        | Value::UInt32(_) => text::write_item(out, Item::Value(value), Escaping::Raw),
+        Value::Int64(_)
+        | Value::Int128(_)
+        | Value::Int256(_)
+        | Value::UInt64(_)
+        | Value::UInt128(_)
+        | Value::UInt256(_) => {
+            if options.quote_64bit_integers {
+                quoted(out, Item::Value(value))
+            } else {
+                text::write_item(out, Item::Value(value), Escaping::Raw)
+            }
+        }
+        Value::Float32(_) | Value::Float64(_) | Value::BFloat16(_) => {
+            let mut text = String::new();
+            text::write_item(&mut text, Item::Value(value), Escaping::Raw)?;
+            let finite = !matches!(text.as_str(), "nan" | "inf" | "-inf");
+            out.write_str(if finite { &text } else { "null" })
+        }
+        Value::String(value) | Value::Enum8(value, _) | Value::Enum16(value, _) => {
+            write_string(out, value)
+        }
+        Value::Bytes(value) => write_string(out, &String::from_utf8_lossy(value)),
+        Value::Uuid(_) | Value::Date(_) | Value::Date32(_) | Value::Ipv4(_) | Value::Ipv6(_) => {
+            quoted(out, Item::Value(value))
+        }
+        Value::Decimal32(..)
+        | Value::Decimal64(..)
+        | Value::Decimal128(..)
+        | Value::Decimal256(..)
+        | Value::DateTime(..)
+        | Value::DateTime64(..) => write_item(out, Item::from(value), options),
+        Value::Json { .. } => write_object(
+            out,
+            JsonIterator::try_from(value).map_err(|_| fmt::Error)?,
+            options,
+        ),
+        // named tuples are objects of their elements, unnamed ones arrays
+        Value::Tuple { index, mark } => {
+            let named = !mark.names.is_empty();
+            out.write_char(if named { '{' } else { '[' })?;
+            for (position, column) in mark.values.iter().enumerate() {
+                if position > 0 {
+                    out.write_char(',')?;
+                }
+                if named {
+                    write_string(out, mark.names.get(position).ok_or(fmt::Error)?)?;
+                    out.write_char(':')?;
+                }
+                let value = column.get(index).ok_or(fmt::Error)?;
+                write_item(out, Item::from(value), options)?;
+            }
+            out.write_char(if named { '}' } else { ']' })
+        }
+        Value::Map { mark, index } => {
+            let (start, end) = mark
+                .offsets
+                .offset_indices(index)
+                .ok()
+                .flatten()
+                .ok_or(fmt::Error)?;
+            out.write_char('{')?;
+            for position in start..end {
+                if position > start {
+                    out.write_char(',')?;
+                }
+                match mark.keys.get(position).ok_or(fmt::Error)? {
+                    Value::String(key) => write_string(out, key)?,
+                    key => quoted(out, Item::from(key))?,
+                }
+                out.write_char(':')?;
+                let value = mark.values.get(position).ok_or(fmt::Error)?;
+                write_item(out, Item::from(value), options)?;
+            }
+            out.write_char('}')
+        }
+        // the fields of `Nested` are named, so its rows are objects
+        Value::Nested { .. } => {
+            out.write_char('[')?;
+            let rows = NestedIterator::try_from(value).map_err(|_| fmt::Error)?;
+            for (position, row) in rows.enumerate() {
+                if position > 0 {
+                    out.write_char(',')?;
+                }
+                out.write_char('{')?;
+                for (field, (name, value)) in row.enumerate() {
+                    if field > 0 {
+                        out.write_char(',')?;
+                    }
+                    write_string(out, name)?;
+                    out.write_char(':')?;
+                    write_item(out, Item::from(value), options)?;
+                }
+                out.write_char('}')?;
+            }
+            out.write_char(']')
+        }
+        // every other value is an array
+        value => {
+            out.write_char('[')?;
+            let mut first = true;
+            text::for_each_element(&value, &mut |item| {
+                if !first {
+                    out.write_char(',')?;
+                }
+                first = false;
+                write_item(out, item, options)
+            })?;
+            out.write_char(']')
+        }
+    }
+}
+
+/// Writes the text form of a value as a JSON string.
+fn quoted<W: fmt::Write>(out: &mut W, item: Item<'_>) -> fmt::Result {
+    let mut text = String::new();
+    text::write_item(&mut text, item, Escaping::Raw)?;
+    write_string(out, &text)
+}
+
+fn write_string<W: fmt::Write>(out: &mut W, value: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\x08' => out.write_str("\\b")?,
+            '\x0c' => out.write_str("\\f")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", u32::from(c))?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{common::load, iter_blocks, parse::block::parse_many};
+
+    fn export(name: &str, format: Format, options: Options) -> TestResult<Vec<String>> {
+        let data = load(format!("./testdata/{name}.native"))?;
+        let blocks = parse_many(&data)?;
+        let mut out = Vec::new();
+        write_rows(&mut out, iter_blocks(&blocks), format, options)?;
+        Ok(String::from_utf8(out)?.lines().map(str::to_owned).collect())
+    }
+
+    #[test]
+    fn each_row() -> TestResult {
+        let lines = export("uuid_and_dates", Format::EachRow, Options::default())?;
+        assert_eq!(
+            lines[0],
+            r#"{"id":"00000000-0000-0000-0000-000000000001","date":"2023-01-01","date32":"2023-01-01","datetime":"2023-01-01 12:00:00","datetime64":"2023-01-01 12:00:00.123"}"#
+        );
+
+        let lines = export("decimal_sample", Format::EachRow, Options::default())?;
+        assert!(lines[0].starts_with(r#"{"id":"0","d32":1.234,"d64":1.234567,"#));
+
+        let lines = export(
+            "array_lc_nullable_string",
+            Format::EachRow,
+            Options::default(),
+        )?;
+        assert_eq!(lines[0], r#"{"id":"0","arr":["apple","banana",null]}"#);
+        assert_eq!(lines[4], r#"{"id":"4","arr":[]}"#);
+
+        let lines = export("ip_sample", Format::EachRow, Options::default())?;
+        assert_eq!(
+            lines[0],
+            r#"{"id":"0","ip4":"100.64.0.2","ip6":"2001:db8::ff00:42:8329"}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn composites() -> TestResult {
+        let lines = export("map_in_map", Format::EachRow, Options::default())?;
+        assert_eq!(
+            lines[0],
+            r#"{"id":"0","m":{"a":{"x":"apple","y":"banana"},"b":{"z":"cherry"}}}"#
+        );
+
+        let lines = export("simple_nested", Format::EachRow, Options::default())?;
+        assert_eq!(
+            lines[0],
+            r#"{"id":"0","nes":[{"child_id":"1","child_name":"Alice"},{"child_id":"2","child_name":"Bob"}]}"#
+        );
+
+        let lines = export("json", Format::EachRow, Options::default())?;
+        assert_eq!(
+            lines[2],
+            r#"{"id":"2","json":{"nested":{"a":"1","b":"2"}}}"#
+        );
+
+        let typ = b"Tuple(a UInt8, b String)";
+        let mut data = vec![1, 2, 1, b't', u8::try_from(typ.len())?];
+        data.extend(typ);
+        data.extend([1, 2, 1, b'x', 1, b'y']);
+        let blocks = parse_many(&data)?;
+        let mut out = Vec::new();
+        write_rows(
+            &mut out,
+            iter_blocks(&blocks),
+            Format::EachRow,
+            Options::default(),
+        )?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "{\"t\":{\"a\":1,\"b\":\"x\"}}\n{\"t\":{\"a\":2,\"b\":\"y\"}}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compact_unquoted() -> TestResult {
+        let options = Options {
+            quote_64bit_integers: false,
+        };
+        let lines = export("tuple", Format::CompactEachRow, options)?;
+        assert_eq!(lines[0], r#"[0,[1,"a"]]"#);
+
+        let lines = export("sample_128", Format::CompactEachRow, options)?;
+        assert!(lines[0].starts_with("[0,12345678901234567890123456789012,["));
+
+        let lines = export("variant", Format::CompactEachRow, options)?;
+        assert_eq!(lines[..3], ["[0,1]", r#"[1,"a"]"#, "[2,[1,2,3]]"]);
+        Ok(())
+    }
+
+    #[test]
+    fn reports_failing_writers() -> TestResult {
+        struct Failing;
+        impl fmt::Write for Failing {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        let data = load("./testdata/tuple.native")?;
+        let blocks = parse_many(&data)?;
+        let row = iter_blocks(&blocks).next().unwrap();
+        let Err(Error::Io(_)) = write_row(&mut Failing, row, Format::EachRow, Options::default())
+        else {
+            panic!("expected the writer's failure");
+        };
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     iter::Peekable,
     net::{Ipv4Addr, Ipv6Addr},
     ops::Range,
+    sync::OnceLock,
 };
 
 use chrono::{NaiveDate, TimeZone};
@@ -13,25 +14,48 @@ use zerocopy::little_endian::{I32, I64, I128, U16, U32, U64};
 use crate::{
     conv::{date16, date32, datetime32, datetime32_tz, datetime64_tz},
     mark::Mark,
+    parse::block::ParseContext,
+    typed::{Chunks, FromColumn, Primitive},
+    types::{Type, TypeHeader},
     value::Value,
 };
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod buffer;
 pub mod conv;
 pub mod error;
 pub mod index;
+pub mod json;
 mod macros;
 pub mod mark;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod parse;
+pub mod rowbinary;
+pub mod schema;
 pub mod slice;
+#[cfg(feature = "tokio")]
+pub mod streaming;
+pub mod text;
+pub mod typed;
 pub mod types;
+pub mod validate;
 pub mod value;
+mod write;
 
+pub use buffer::{OwnedBlock, OwnedBlocks};
 pub use error::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 pub(crate) trait ByteExt {
     fn rtrim_zeros(&self) -> &[u8];
+
+    /// The bytes as a string, an error with the bytes if they are not UTF-8.
+    fn to_utf8(&self) -> Result<&str>;
 }
 
 impl ByteExt for [u8] {
@@ -43,6 +67,11 @@ impl ByteExt for [u8] {
         }
         &self[..end]
     }
+
+    #[inline(always)]
+    fn to_utf8(&self) -> Result<&str> {
+        std::str::from_utf8(self).map_err(|err| Error::Utf8Decode(err, self.to_vec()))
+    }
 }
 
 /// This range represents a starting offset and a length, as opposed to the
@@ -185,21 +214,88 @@ impl Decimal128Data {
     }
 }
 
+#[derive(Default)]
 pub struct ParsedBlock<'a> {
     pub markers: Vec<Mark<'a>>,
     pub col_names: Vec<&'a str>,
+    pub types: Vec<Type<'a>>,
     pub num_rows: usize,
+
+    /// Column positions by name, built on the first lookup. A block put together by hand can
+    /// leave it to `Default::default()`.
+    pub name_index: NameIndex,
 }
 
-impl ParsedBlock<'_> {
+/// Column positions by name of a [`ParsedBlock`]. Every position found is checked against
+/// `col_names`, so lookups stay correct after the columns were edited by hand.
+#[derive(Debug, Default)]
+pub struct NameIndex(OnceLock<HashMap<Box<str>, usize>>);
+
+impl<'a> ParsedBlock<'a> {
+    pub fn new(
+        col_names: Vec<&'a str>,
+        types: Vec<Type<'a>>,
+        markers: Vec<Mark<'a>>,
+        num_rows: usize,
+    ) -> Self {
+        Self {
+            markers,
+            col_names,
+            types,
+            num_rows,
+            name_index: NameIndex::default(),
+        }
+    }
+
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        let index = self.name_index.0.get_or_init(|| {
+            // the first column wins if a name repeats
+            self.col_names
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(index, name)| (Box::from(*name), index))
+                .collect()
+        });
+        match index.get(name) {
+            Some(&index) if self.col_names.get(index) == Some(&name) => Some(index),
+            _ => self.col_names.iter().position(|column| *column == name),
+        }
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Mark<'a>> {
+        self.markers.get(self.column_index(name)?)
+    }
+
+    /// Looks the column up once, producing a handle that finds it in this and any other block
+    /// with the same layout without hashing the name again.
+    pub fn resolve<'n>(&self, name: &'n str) -> Option<ColumnHandle<'n>> {
+        let index = self.column_index(name)?;
+        Some(ColumnHandle { name, index })
+    }
+
+    /// Checks the invariants the accessors rely on: offsets, LowCardinality indices, Enum
+    /// values, Variant/Dynamic discriminators, Nullable masks and Bool bytes. Returns every
+    /// violation found, so an empty vector means the block is sound.
+    pub fn validate(&self) -> Vec<validate::Violation<'a>> {
+        self.col_names
+            .iter()
+            .zip(&self.markers)
+            .flat_map(|(name, mark)| validate::column(name, mark, self.num_rows))
+            .collect()
+    }
+
     fn reorder(&mut self, order: &HashMap<&str, usize>) -> Result<()> {
         let num_cols = self.col_names.len();
         let col_names = std::mem::replace(&mut self.col_names, Vec::with_capacity(num_cols));
         let markers = std::mem::replace(&mut self.markers, Vec::with_capacity(num_cols));
+        let types = std::mem::replace(&mut self.types, Vec::with_capacity(num_cols));
 
-        let mut triples = Vec::with_capacity(num_cols);
+        let mut columns = Vec::with_capacity(num_cols);
         let mut num_used = 0;
-        for (index, (col_name, marker)) in col_names.into_iter().zip(markers).enumerate() {
+        for (index, ((col_name, marker), typ)) in
+            col_names.into_iter().zip(markers).zip(types).enumerate()
+        {
             let sort_key = if let Some(key) = order.get(col_name).copied() {
                 num_used += 1;
                 key
@@ -207,13 +303,13 @@ impl ParsedBlock<'_> {
                 // if the column is not in the order, we put it at the end
                 num_cols + index
             };
-            triples.push((col_name, marker, sort_key));
+            columns.push((col_name, marker, typ, sort_key));
         }
 
         if num_used < order.len() {
-            let present_columns = triples
+            let present_columns = columns
                 .iter()
-                .map(|(name, _, _)| *name)
+                .map(|(name, _, _, _)| *name)
                 .collect::<HashSet<_>>();
             let mut missing = order.keys().copied().collect::<HashSet<_>>();
             missing.retain(|name| !present_columns.contains(name));
@@ -223,20 +319,125 @@ impl ParsedBlock<'_> {
             )));
         }
 
-        triples.sort_unstable_by_key(|(_, _, sort_key)| *sort_key);
+        columns.sort_unstable_by_key(|(_, _, _, sort_key)| *sort_key);
 
-        for (col_name, marker, _) in triples {
+        for (col_name, marker, typ, _) in columns {
             self.col_names.push(col_name);
             self.markers.push(marker);
+            self.types.push(typ);
         }
+        self.name_index = NameIndex::default();
 
         Ok(())
     }
 }
 
+/// A block that has only been scanned: every column knows its name, type, header and byte
+/// range, but its [`Mark`] is decoded on first access. Produced by
+/// [`parse::block::scan_single`] and [`parse::block::scan_many`].
+#[derive(Debug)]
+pub struct LazyBlock<'a> {
+    pub columns: Vec<LazyColumn<'a>>,
+    pub num_rows: usize,
+}
+
+impl<'a> LazyBlock<'a> {
+    pub fn col_names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.columns.iter().map(|column| column.name)
+    }
+
+    /// Decodes (once) and returns the column at `index`.
+    pub fn mark(&self, index: usize) -> Result<&Mark<'a>> {
+        let Some(column) = self.columns.get(index) else {
+            return Err(Error::IndexOutOfBounds(index, "LazyBlock columns"));
+        };
+        column.mark()
+    }
+
+    /// Decodes every column that has not been accessed yet.
+    pub fn into_parsed(self) -> Result<ParsedBlock<'a>> {
+        let mut markers = Vec::with_capacity(self.columns.len());
+        let mut col_names = Vec::with_capacity(self.columns.len());
+        let mut types = Vec::with_capacity(self.columns.len());
+        for column in self.columns {
+            col_names.push(column.name);
+            types.push(column.typ.clone());
+            markers.push(column.into_mark()?);
+        }
+
+        Ok(ParsedBlock::new(col_names, types, markers, self.num_rows))
+    }
+}
+
+#[derive(Debug)]
+pub struct LazyColumn<'a> {
+    pub name: &'a str,
+    pub typ: Type<'a>,
+    pub header: TypeHeader<'a>,
+
+    /// Byte range of the column (serialization prefix and data) relative to the block start.
+    pub range: Range<usize>,
+
+    /// The column data following the serialization prefix `header` was read from.
+    data: ParseContext<'a>,
+    mark: OnceLock<Mark<'a>>,
+}
+
+impl<'a> LazyColumn<'a> {
+    pub(crate) fn new(
+        name: &'a str,
+        typ: Type<'a>,
+        header: TypeHeader<'a>,
+        range: Range<usize>,
+        data: ParseContext<'a>,
+    ) -> Self {
+        Self {
+            name,
+            typ,
+            header,
+            range,
+            data,
+            mark: OnceLock::new(),
+        }
+    }
+
+    pub fn is_decoded(&self) -> bool {
+        self.mark.get().is_some()
+    }
+
+    pub fn mark(&self) -> Result<&Mark<'a>> {
+        if let Some(mark) = self.mark.get() {
+            return Ok(mark);
+        }
+        let mark = self.decode()?;
+        Ok(self.mark.get_or_init(|| mark))
+    }
+
+    fn into_mark(mut self) -> Result<Mark<'a>> {
+        match self.mark.take() {
+            Some(mark) => Ok(mark),
+            None => self.decode(),
+        }
+    }
+
+    fn decode(&self) -> Result<Mark<'a>> {
+        // the decoder consumes the header and fills in the JSON ones, so it gets a copy
+        let (_, mark) = self
+            .typ
+            .clone()
+            .decode(self.data.clone(), self.header.clone())
+            .map_err(|err| err.in_column(self.name, self.range.start))?;
+        Ok(mark)
+    }
+}
+
 pub struct BlocksIterator<'a> {
     blocks: Peekable<std::slice::Iter<'a, ParsedBlock<'a>>>,
     block_row: usize,
+    block_index: usize,
+
+    /// Column positions of each block in iteration order, empty to iterate in block order.
+    columns: &'a [Vec<usize>],
 }
 
 impl<'a> BlocksIterator<'a> {
@@ -245,6 +446,8 @@ impl<'a> BlocksIterator<'a> {
         Self {
             blocks: blocks.iter().peekable(),
             block_row: 0,
+            block_index: 0,
+            columns: &[],
         }
     }
 
@@ -258,27 +461,179 @@ impl<'a> BlocksIterator<'a> {
             block.reorder(&order_map)?;
         }
 
-        Ok(Self {
-            blocks: blocks.iter().peekable(),
-            block_row: 0,
-        })
+        Ok(Self::new(blocks))
+    }
+}
+
+/// What an [`OrderedBlocks`] view does with the columns of a block that were not requested.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Unrequested {
+    /// Keep them after the requested columns, in block order, like
+    /// [`BlocksIterator::new_ordered`] does.
+    #[default]
+    Append,
+
+    /// Leave them out of the row iteration.
+    Drop,
+
+    /// Fail with [`Error::InvalidColumnOrder`].
+    Error,
+}
+
+/// Blocks whose rows iterate their columns in a requested order. Unlike
+/// [`BlocksIterator::new_ordered`], the blocks are not modified: the order is kept as a list of
+/// column positions for every block.
+pub struct OrderedBlocks<'a> {
+    blocks: &'a [ParsedBlock<'a>],
+    columns: Vec<Vec<usize>>,
+}
+
+impl<'a> OrderedBlocks<'a> {
+    /// Fails with [`Error::InvalidColumnOrder`] if a block is missing one of the requested
+    /// columns, or has a column that was not requested and `unrequested` is
+    /// [`Unrequested::Error`].
+    pub fn new(
+        blocks: &'a [ParsedBlock<'a>],
+        order: &[&str],
+        unrequested: Unrequested,
+    ) -> Result<Self> {
+        let columns = blocks
+            .iter()
+            .map(|block| Self::positions(block, order, unrequested))
+            .collect::<Result<_>>()?;
+
+        Ok(Self { blocks, columns })
+    }
+
+    fn positions(
+        block: &ParsedBlock<'_>,
+        order: &[&str],
+        unrequested: Unrequested,
+    ) -> Result<Vec<usize>> {
+        let mut missing = Vec::new();
+        let mut positions = Vec::with_capacity(block.col_names.len());
+        for name in order {
+            match block.column_index(name) {
+                Some(index) => positions.push(index),
+                None => missing.push(*name),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(Error::InvalidColumnOrder(format!(
+                "Missing columns: {missing:?}"
+            )));
+        }
+
+        if unrequested == Unrequested::Drop || positions.len() == block.col_names.len() {
+            return Ok(positions);
+        }
+        let extra = (0..block.col_names.len())
+            .filter(|index| !positions.contains(index))
+            .collect::<Vec<_>>();
+        if unrequested == Unrequested::Error {
+            let names = extra
+                .iter()
+                .map(|&index| block.col_names[index])
+                .collect::<Vec<_>>();
+            return Err(Error::InvalidColumnOrder(format!(
+                "Unrequested columns: {names:?}"
+            )));
+        }
+        positions.extend(extra);
+
+        Ok(positions)
+    }
+
+    pub fn blocks(&self) -> &'a [ParsedBlock<'a>] {
+        self.blocks
+    }
+
+    /// Positions of the columns of the block at `block`, in iteration order.
+    pub fn columns(&self, block: usize) -> Option<&[usize]> {
+        self.columns.get(block).map(Vec::as_slice)
+    }
+
+    pub fn iter(&self) -> BlocksIterator<'_> {
+        BlocksIterator {
+            columns: &self.columns,
+            ..BlocksIterator::new(self.blocks)
+        }
+    }
+}
+
+impl<'v> IntoIterator for &'v OrderedBlocks<'_> {
+    type Item = BlockRow<'v>;
+    type IntoIter = BlocksIterator<'v>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A column of a [`ParsedBlock`] found by name, see [`ParsedBlock::resolve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnHandle<'n> {
+    name: &'n str,
+    index: usize,
+}
+
+impl ColumnHandle<'_> {
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Position of the column in `block`: the resolved position if the block has the column
+    /// there, otherwise whatever the block's name index says.
+    #[inline]
+    pub fn index_in(&self, block: &ParsedBlock<'_>) -> Option<usize> {
+        if block.col_names.get(self.index) == Some(&self.name) {
+            return Some(self.index);
+        }
+        block.column_index(self.name)
     }
 }
 
 pub struct BlockRow<'a> {
-    col_names: &'a [&'a str],
-    cols: &'a [Mark<'a>],
+    block: &'a ParsedBlock<'a>,
     col_index: usize,
     row_index: usize,
+
+    /// Column positions to iterate, set for rows of an [`OrderedBlocks`] view.
+    columns: Option<&'a [usize]>,
 }
 
 impl<'a> BlockRow<'a> {
+    /// All columns of the block, in block order, even for rows of an [`OrderedBlocks`] view.
     pub fn cols(&self) -> &'a [Mark<'a>] {
-        self.cols
+        &self.block.markers
     }
 
     pub fn col_names(&self) -> &'a [&'a str] {
-        self.col_names
+        &self.block.col_names
+    }
+
+    pub fn block(&self) -> &'a ParsedBlock<'a> {
+        self.block
+    }
+
+    /// The value of the column named `name` in this row.
+    pub fn get(&self, name: &str) -> Option<ColumnAccessor<'a>> {
+        self.accessor(self.block.column_index(name)?)
+    }
+
+    /// Same as [`BlockRow::get`], for a column resolved up front.
+    #[inline]
+    pub fn column(&self, handle: &ColumnHandle<'_>) -> Option<ColumnAccessor<'a>> {
+        self.accessor(handle.index_in(self.block)?)
+    }
+
+    #[inline]
+    fn accessor(&self, index: usize) -> Option<ColumnAccessor<'a>> {
+        Some(ColumnAccessor {
+            col_name: self.block.col_names.get(index)?,
+            marker: self.block.markers.get(index)?,
+            row_index: self.row_index,
+        })
     }
 
     pub fn row_index(&self) -> usize {
@@ -295,19 +650,14 @@ impl<'a> Iterator for BlockRow<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let col_name = self.col_names.get(self.col_index)?;
-        let marker = self.cols.get(self.col_index)?;
-
+        let index = match self.columns {
+            Some(columns) => *columns.get(self.col_index)?,
+            None => self.col_index,
+        };
+        let accessor = self.accessor(index)?;
         self.col_index += 1;
 
-        Some((
-            col_name,
-            ColumnAccessor {
-                col_name,
-                marker,
-                row_index: self.row_index,
-            },
-        ))
+        Some((accessor.col_name, accessor))
     }
 }
 
@@ -320,14 +670,15 @@ impl<'a> Iterator for BlocksIterator<'a> {
             if self.block_row >= block.num_rows {
                 self.blocks.next();
                 self.block_row = 0;
+                self.block_index += 1;
                 continue;
             }
 
             let block_row = BlockRow {
-                col_names: &block.col_names,
-                cols: &block.markers,
+                block,
                 col_index: 0,
                 row_index: self.block_row,
+                columns: self.columns.get(self.block_index).map(Vec::as_slice),
             };
             self.block_row += 1;
 
@@ -336,6 +687,7 @@ impl<'a> Iterator for BlocksIterator<'a> {
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct ColumnAccessor<'a> {
     pub col_name: &'a str,
     pub marker: &'a Mark<'a>,
@@ -352,63 +704,73 @@ impl<'a> ColumnAccessor<'a> {
         self.marker.get(self.row_index).unwrap()
     }
 
+    /// Reads the value as `T`, failing if the column holds a different type, e.g. a null for
+    /// a `T` that is not an `Option`.
+    #[inline]
+    pub fn into<T: FromColumn<'a>>(self) -> Result<T> {
+        T::from_column(self.marker, self.row_index)
+    }
+
     #[inline]
     pub fn into_str(self) -> Result<&'a str> {
         let str = self.marker.get_str(self.row_index)?;
-        Ok(str.unwrap())
+        str.ok_or(Error::IndexOutOfBounds(self.row_index, "column"))
     }
 
     #[inline]
     pub fn into_opt_str(self) -> Result<Option<&'a str>> {
         let str = self.marker.get_opt_str(self.row_index)?;
-        Ok(str.unwrap())
+        str.ok_or(Error::IndexOutOfBounds(self.row_index, "column"))
     }
 
     #[inline]
     pub fn into_datetime<T: TimeZone>(self, tz: T) -> Result<chrono::DateTime<T>> {
         let dt = self.marker.get_datetime(self.row_index, tz)?;
-        Ok(dt.unwrap())
+        dt.ok_or(Error::IndexOutOfBounds(self.row_index, "column"))
     }
 
     #[inline]
     pub fn into_uuid(self) -> Result<Uuid> {
         let uuid = self.marker.get_uuid(self.row_index)?;
-        Ok(uuid.unwrap())
+        uuid.ok_or(Error::IndexOutOfBounds(self.row_index, "column"))
     }
 
     #[inline]
     pub fn into_ipv4(self) -> Result<Ipv4Addr> {
         let ipv4 = self.marker.get_ipv4(self.row_index)?;
-        Ok(ipv4.unwrap())
+        ipv4.ok_or(Error::IndexOutOfBounds(self.row_index, "column"))
     }
 
     #[inline]
     pub fn into_ipv6(self) -> Result<Ipv6Addr> {
         let ipv6 = self.marker.get_ipv6(self.row_index)?;
-        Ok(ipv6.unwrap())
+        ipv6.ok_or(Error::IndexOutOfBounds(self.row_index, "column"))
     }
 
     #[inline]
     pub fn into_opt_ipv6(self) -> Result<Option<Ipv6Addr>> {
         let ipv6 = self.marker.get_opt_ipv6(self.row_index)?;
-        Ok(ipv6.unwrap())
+        ipv6.ok_or(Error::IndexOutOfBounds(self.row_index, "column"))
     }
 
     #[inline]
     pub fn into_bool(self) -> Result<bool> {
         let value = self.marker.get_bool(self.row_index)?;
-        Ok(value.unwrap())
+        value.ok_or(Error::IndexOutOfBounds(self.row_index, "column"))
     }
 
     #[inline]
     pub fn into_f64(self) -> Result<f64> {
         let value = self.marker.get_f64(self.row_index)?;
-        Ok(value.unwrap())
+        value.ok_or(Error::IndexOutOfBounds(self.row_index, "column"))
     }
 
     #[inline]
     pub fn into_array_lc_strs(self) -> Result<impl Iterator<Item = &'a str>> {
-        let it = self.marker.get_array_lc_strs(self.row_index)?.unwrap();
+        let it = self
+            .marker
+            .get_array_lc_strs(self.row_index)?
+            .ok_or(Error::IndexOutOfBounds(self.row_index, "column"))?;
         Ok(it.into_iter())
     }
 }
@@ -417,6 +779,16 @@ pub fn iter_blocks<'a>(blocks: &'a [ParsedBlock]) -> BlocksIterator<'a> {
     BlocksIterator::new(blocks)
 }
 
+/// The column named `name` of every block, borrowed as typed slices, e.g.
+/// `iter_column::<f64>(&blocks, "count")` yields the `Float64` data of each block. A block
+/// without the column, or with a column of a different type, yields an error.
+pub fn iter_column<'a, 'n, T: Primitive>(
+    blocks: &'a [ParsedBlock<'a>],
+    name: &'n str,
+) -> Chunks<'a, 'n, T> {
+    Chunks::new(blocks, name)
+}
+
 pub fn iter_blocks_ordered<'a>(
     blocks: &'a mut [ParsedBlock<'a>],
     order: &[&str],
@@ -426,7 +798,11 @@ pub fn iter_blocks_ordered<'a>(
 
 #[cfg(test)]
 pub(crate) mod common {
-    use std::{io::Read as _, path::Path, sync::Once};
+    use std::{
+        io::Read as _,
+        path::{Path, PathBuf},
+        sync::Once,
+    };
 
     use log::LevelFilter;
 
@@ -460,4 +836,20 @@ pub(crate) mod common {
         file.read_to_end(&mut buf)?;
         Ok(buf)
     }
+
+    /// Every `.native` file in `testdata`, sorted by path, with its contents.
+    pub fn load_testdata() -> std::io::Result<Vec<(PathBuf, Vec<u8>)>> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir("./testdata")? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "native") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| load(&path).map(|data| (path, data)))
+            .collect()
+    }
 }
@@ -1,5 +1,5 @@
 use core::fmt;
-use std::fmt::Debug;
+use std::{borrow::Cow, fmt::Debug};
 
 use chrono_tz::Tz;
 use zerocopy::{
@@ -11,7 +11,7 @@ use crate::{
     Bf16Data, ByteExt as _, Date16Data, Date32Data, DateTime32Data, DateTime64Data, Decimal32Data,
     Decimal64Data, Decimal128Data, Decimal256Data, I256, Ipv4Data, Ipv6Data, U256, UuidData,
     slice::ByteView,
-    types::{JsonColumnHeader, OffsetIndexPair as _, Offsets},
+    types::{JsonColumnHeader, OffsetIndexPair as _, Offsets, Type},
     value::Value,
 };
 
@@ -36,14 +36,14 @@ macro_rules! impl_get_many {
     };
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Map<'a> {
     pub offsets: Offsets<'a>,
     pub keys: Box<Mark<'a>>,
     pub values: Box<Mark<'a>>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Variant<'a> {
     pub offsets: Vec<usize>,
     pub discriminators: &'a [u8],
@@ -61,7 +61,7 @@ impl Variant<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct LowCardinality<'a> {
     pub is_nullable: bool,
     pub indices: Box<Mark<'a>>,
@@ -76,8 +76,8 @@ impl LowCardinality<'_> {
             Mark::UInt8(indices) => indices.get(index).copied()? as usize,
             Mark::UInt16(indices) => indices.get(index)?.get() as usize,
             Mark::UInt32(indices) => indices.get(index)?.get() as usize,
-            Mark::UInt64(indices) => usize::try_from(indices.get(index)?.get()).unwrap(),
-            _ => unreachable!("unexpected indices type in LowCardinality "),
+            Mark::UInt64(indices) => usize::try_from(indices.get(index)?.get()).ok()?,
+            _ => return None,
         };
 
         Some(value_index)
@@ -98,14 +98,14 @@ impl LowCardinality<'_> {
         // fast path for LowCardinality with String keys
         if let Mark::String(keys) = keys.as_ref() {
             let value = keys.get(value_index).copied()?;
-            return Some(Value::String(value));
+            return Some(Value::string(value));
         }
 
         keys.get(value_index)
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Nested<'a> {
     pub col_names: Vec<&'a str>,
     pub array_of_tuples: Box<Mark<'a>>,
@@ -120,13 +120,13 @@ impl Nested<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Json<'a> {
     pub paths: Vec<&'a str>,
     pub headers: Vec<JsonColumnHeader<'a>>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Array<'a> {
     pub offsets: Offsets<'a>,
     pub values: Box<Mark<'a>>,
@@ -140,31 +140,31 @@ impl Array<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Decimal32<'a> {
     pub precision: u8,
     pub data: ByteView<'a, Decimal32Data>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Decimal64<'a> {
     pub precision: u8,
     pub data: ByteView<'a, Decimal64Data>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Decimal128<'a> {
     pub precision: u8,
     pub data: ByteView<'a, Decimal128Data>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Decimal256<'a> {
     pub precision: u8,
     pub data: ByteView<'a, Decimal256Data>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct FixedString<'a> {
     pub size: usize,
     pub data: &'a [u8],
@@ -174,21 +174,19 @@ impl FixedString<'_> {
     #[inline]
     pub fn get(&self, index: usize) -> Option<Value> {
         let offset = self.size * index;
-        let slice = self.data[offset..offset + self.size].rtrim_zeros();
+        let slice = self.data.get(offset..offset + self.size)?.rtrim_zeros();
 
-        Some(Value::String(unsafe {
-            std::str::from_utf8_unchecked(slice)
-        }))
+        Some(Value::string(slice))
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct DateTime<'a> {
     pub tz: Tz,
     pub data: ByteView<'a, DateTime32Data>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct DateTime64<'a> {
     pub precision: u8,
     pub tz: Tz,
@@ -199,9 +197,9 @@ impl_get_many!(
     Decimal32, Decimal64, Decimal128, Decimal256, DateTime, DateTime64
 );
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Enum8<'a> {
-    pub variants: Vec<(&'a str, i8)>,
+    pub variants: Vec<(Cow<'a, str>, i8)>,
     pub data: ByteView<'a, i8>,
 }
 
@@ -210,16 +208,16 @@ impl Enum8<'_> {
     pub fn get(&self, index: usize) -> Option<Value> {
         let variant = *self.data.get(index)?;
         if let Ok(index) = self.variants.binary_search_by_key(&variant, |(_, id)| *id) {
-            return Some(Value::String(self.variants[index].0));
+            return Some(Value::Enum8(&self.variants[index].0, variant));
         }
         // actually, at this point it's broken, but we trust clickhouse!
         None
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Enum16<'a> {
-    pub variants: Vec<(&'a str, i16)>,
+    pub variants: Vec<(Cow<'a, str>, i16)>,
     pub data: ByteView<'a, I16>,
 }
 
@@ -228,16 +226,20 @@ impl Enum16<'_> {
     pub fn get(&self, index: usize) -> Option<Value> {
         let variant = self.data.get(index)?.get();
         if let Ok(index) = self.variants.binary_search_by_key(&variant, |(_, id)| *id) {
-            return Some(Value::String(self.variants[index].0));
+            return Some(Value::Enum16(&self.variants[index].0, variant));
         }
         None
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Dynamic<'a> {
     pub offsets: Vec<usize>,
     pub discriminators: Vec<usize>,
+
+    /// The type of every column, `SharedVariant` for the column of values stored without a
+    /// type of their own, which is not decoded.
+    pub types: Vec<Type<'a>>,
     pub columns: Vec<Mark<'a>>,
 }
 
@@ -252,7 +254,7 @@ impl Dynamic<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Nullable<'a> {
     pub mask: &'a [u8],
     pub data: Box<Mark<'a>>,
@@ -269,11 +271,14 @@ impl Nullable<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Tuple<'a> {
     pub values: Vec<Mark<'a>>,
+    /// The element names of a named tuple, empty otherwise.
+    pub names: Vec<&'a str>,
 }
 
+#[derive(Clone)]
 pub enum Mark<'a> {
     Empty,
     Bool(&'a [u8]),
@@ -296,7 +301,8 @@ pub enum Mark<'a> {
     Decimal64(Decimal64<'a>),
     Decimal128(Decimal128<'a>),
     Decimal256(Decimal256<'a>),
-    String(Vec<&'a str>),
+    /// The strings of the rows, which are not necessarily UTF-8.
+    String(Vec<&'a [u8]>),
     FixedString(FixedString<'a>),
     Uuid(ByteView<'a, UuidData>),
     Date(ByteView<'a, Date16Data>),
@@ -322,7 +328,7 @@ pub enum Mark<'a> {
     Map(Map<'a>),
     Variant(Variant<'a>),
     Nested(Nested<'a>),
-    Dynamic(Dynamic<'a>),
+    Dynamic(Box<Dynamic<'a>>),
 
     Json(Json<'a>),
 }
@@ -394,6 +400,61 @@ impl Mark<'_> {
             Self::Empty => None,
         }
     }
+
+    /// Number of rows stored in the marker, if the marker knows it: an empty tuple or a JSON
+    /// column without paths does not.
+    pub fn num_rows(&self) -> Option<usize> {
+        let num_rows = match self {
+            Self::Empty => 0,
+            Self::Bool(bytes) => bytes.len(),
+            Self::Point(bytes) => bytes.len() / 16,
+            Self::Int8(bv) => bv.len(),
+            Self::Int16(bv) | Self::Enum16(Enum16 { data: bv, .. }) => bv.len(),
+            Self::Int32(bv) => bv.len(),
+            Self::Int64(bv) => bv.len(),
+            Self::Int128(bv) => bv.len(),
+            Self::Int256(bv) => bv.len(),
+            Self::UInt256(bv) => bv.len(),
+            Self::UInt8(bv) => bv.len(),
+            Self::UInt16(bv) => bv.len(),
+            Self::UInt32(bv) => bv.len(),
+            Self::UInt64(bv)
+            | Self::Array(Array { offsets: bv, .. })
+            | Self::Map(Map { offsets: bv, .. }) => bv.len(),
+            Self::UInt128(bv) => bv.len(),
+            Self::Float32(bv) => bv.len(),
+            Self::Float64(bv) => bv.len(),
+            Self::BFloat16(bv) => bv.len(),
+            Self::Decimal32(d) => d.data.len(),
+            Self::Decimal64(d) => d.data.len(),
+            Self::Decimal128(d) => d.data.len(),
+            Self::Decimal256(d) => d.data.len(),
+            Self::String(strings) => strings.len(),
+            Self::FixedString(fs) => fs.data.len().checked_div(fs.size)?,
+            Self::Uuid(bv) => bv.len(),
+            Self::Date(bv) => bv.len(),
+            Self::Date32(bv) => bv.len(),
+            Self::DateTime(d) => d.data.len(),
+            Self::DateTime64(d) => d.data.len(),
+            Self::Ipv4(bv) => bv.len(),
+            Self::Ipv6(bv) => bv.len(),
+            Self::Enum8(e) => e.data.len(),
+            Self::Ring(inner)
+            | Self::Polygon(inner)
+            | Self::MultiPolygon(inner)
+            | Self::LineString(inner)
+            | Self::MultiLineString(inner) => inner.num_rows()?,
+            Self::LowCardinality(lc) => lc.indices.num_rows()?,
+            Self::Tuple(tuple) => tuple.values.first()?.num_rows()?,
+            Self::Nullable(n) => n.mask.len(),
+            Self::Variant(v) => v.discriminators.len(),
+            Self::Nested(n) => n.array_of_tuples.num_rows()?,
+            Self::Dynamic(d) => d.discriminators.len(),
+            Self::Json(j) => j.headers.first()?.discriminators.len(),
+        };
+
+        Some(num_rows)
+    }
 }
 
 impl Debug for Mark<'_> {
@@ -473,7 +534,13 @@ impl Debug for Mark<'_> {
                 .field("data", &d.data.as_slice())
                 .finish(),
 
-            String(data) => f.debug_struct("String").field("data", data).finish(),
+            String(data) => {
+                let data = data
+                    .iter()
+                    .map(|s| std::string::String::from_utf8_lossy(s))
+                    .collect::<Vec<_>>();
+                f.debug_struct("String").field("data", &data).finish()
+            }
             FixedString(ff) => f
                 .debug_struct("FixedString")
                 .field("fixed_len", &ff.size)
@@ -0,0 +1,83 @@
+//! Memory-mapped access to Native files, so large dumps can be scanned block by block without
+//! reading them into memory.
+
+use std::{fs::File, path::Path};
+
+use memmap2::Mmap;
+
+use crate::{
+    Result,
+    parse::block::{Blocks, parse_iter},
+};
+
+pub struct MappedFile {
+    mmap: Mmap,
+}
+
+impl MappedFile {
+    /// Maps the whole file into memory.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped: all parsed blocks borrow
+    /// directly from the mapping, and a concurrent change is undefined behaviour.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: forwarded to the caller
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        // blocks are read front to back: only a hint to read ahead more aggressively and that
+        // pages already read may be reclaimed sooner, nothing is unmapped or freed
+        #[cfg(unix)]
+        if let Err(err) = mmap.advise(memmap2::Advice::Sequential) {
+            log::debug!("madvise(MADV_SEQUENTIAL) failed: {err}");
+        }
+
+        Ok(Self { mmap })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Lazily parses the blocks of the file. The heap only ever holds the markers of the block
+    /// being handed out; the data itself stays in the page cache.
+    pub fn blocks(&self) -> Blocks<'_> {
+        parse_iter(&self.mmap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{common::load, parse::block::parse_many};
+
+    #[test]
+    fn blocks_match_read_to_end() -> TestResult {
+        for path in [
+            "./testdata/sample.native",
+            "./testdata/json_arr.native",
+            "./testdata/array_of_nested.native",
+        ] {
+            let buf = load(path)?;
+            let expected = parse_many(&buf)?;
+
+            // SAFETY: test data is not modified while the tests run
+            let file = unsafe { MappedFile::open(path)? };
+            assert_eq!(file.as_bytes(), buf.as_slice());
+
+            let mut num_blocks = 0;
+            for (expected, block) in expected.iter().zip(file.blocks()) {
+                let block = block?;
+                assert_eq!(expected.col_names, block.col_names);
+                assert_eq!(expected.num_rows, block.num_rows);
+                num_blocks += 1;
+            }
+            assert_eq!(num_blocks, expected.len());
+        }
+
+        Ok(())
+    }
+}
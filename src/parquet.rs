@@ -0,0 +1,262 @@
+//! Writing parsed blocks into Parquet files.
+//!
+//! Blocks go through their Arrow form, see [`crate::arrow`], so the Parquet schema follows the
+//! Arrow one: `Array`, `Map` and `Tuple` become lists, maps and groups, decimals keep their
+//! precision and scale, and `DateTime64` becomes a timestamp in the unit its precision needs.
+//! `Variant`, `Dynamic` and `JSON` columns have no Parquet counterpart.
+
+use std::io;
+
+use arrow_schema::{Field, Schema, SchemaRef};
+use parquet::{
+    arrow::ArrowWriter,
+    file::properties::{WriterProperties, WriterPropertiesBuilder},
+    format::FileMetaData,
+};
+
+use crate::{
+    ParsedBlock, Result,
+    arrow::{data_type, is_nullable, record_batch},
+    error::Error,
+    types::Type,
+};
+
+/// A Parquet file being written, a row group per `blocks_per_row_group` blocks.
+pub struct Writer<W: io::Write + Send> {
+    inner: ArrowWriter<W>,
+    blocks_per_row_group: usize,
+    pending_blocks: usize,
+}
+
+impl<W: io::Write + Send> Writer<W> {
+    /// Starts a file with the schema of `block`, which is not written yet. Every block written
+    /// later must have the same columns.
+    pub fn try_new(out: W, block: &ParsedBlock<'_>, blocks_per_row_group: usize) -> Result<Self> {
+        Self::with_properties(
+            out,
+            block,
+            blocks_per_row_group,
+            WriterProperties::builder(),
+        )
+    }
+
+    /// Same as [`Writer::try_new`], with the given compression, encodings, etc. The maximum
+    /// row group size is overridden: row groups are cut by blocks only.
+    pub fn with_properties(
+        out: W,
+        block: &ParsedBlock<'_>,
+        blocks_per_row_group: usize,
+        properties: WriterPropertiesBuilder,
+    ) -> Result<Self> {
+        if blocks_per_row_group == 0 {
+            return Err(Error::ProgrammingError(
+                "A row group needs at least one block".to_owned(),
+            ));
+        }
+
+        let schema = schema(&block.col_names, &block.types)?;
+        let properties = properties.set_max_row_group_size(usize::MAX).build();
+        Ok(Self {
+            inner: ArrowWriter::try_new(out, SchemaRef::new(schema), Some(properties))?,
+            blocks_per_row_group,
+            pending_blocks: 0,
+        })
+    }
+
+    pub fn write(&mut self, block: &ParsedBlock<'_>) -> Result<()> {
+        self.inner.write(&record_batch(block)?)?;
+        self.pending_blocks += 1;
+        if self.pending_blocks == self.blocks_per_row_group {
+            self.inner.flush()?;
+            self.pending_blocks = 0;
+        }
+        Ok(())
+    }
+
+    /// Writes the last row group and the footer.
+    pub fn close(self) -> Result<FileMetaData> {
+        Ok(self.inner.close()?)
+    }
+}
+
+/// Writes all blocks as a single Parquet file. Nothing is written without blocks, as there is
+/// no schema to write.
+pub fn write_blocks<W: io::Write + Send>(
+    out: W,
+    blocks: &[ParsedBlock<'_>],
+    blocks_per_row_group: usize,
+) -> Result<Option<FileMetaData>> {
+    let Some(first) = blocks.first() else {
+        return Ok(None);
+    };
+
+    let mut writer = Writer::try_new(out, first, blocks_per_row_group)?;
+    for block in blocks {
+        writer.write(block)?;
+    }
+    Ok(Some(writer.close()?))
+}
+
+/// The Arrow schema of the Parquet file for columns of the given names and types.
+pub fn schema(names: &[&str], types: &[Type<'_>]) -> Result<Schema> {
+    let fields = names
+        .iter()
+        .zip(types)
+        .map(|(name, typ)| {
+            if let Some(variant) = find_variant(typ) {
+                return Err(Error::NotImplemented(format!("Parquet type for {variant}")));
+            }
+            Ok(Field::new(*name, data_type(typ)?, is_nullable(typ)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Schema::new(fields))
+}
+
+/// The first `Variant` in `typ`: unions, which it becomes in Arrow, are not written by the
+/// Parquet writer.
+fn find_variant<'t, 'a>(typ: &'t Type<'a>) -> Option<&'t Type<'a>> {
+    match typ {
+        Type::Variant(_) => Some(typ),
+        Type::Nullable(inner) | Type::LowCardinality(inner) | Type::Array(inner) => {
+            find_variant(inner)
+        }
+        Type::Map(key, value) => find_variant(key).or_else(|| find_variant(value)),
+        Type::Tuple(types, _) => types.iter().find_map(find_variant),
+        Type::Nested(fields) => fields.iter().find_map(|field| find_variant(&field.typ)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::RecordBatch;
+    use bytes::Bytes;
+    use parquet::{
+        arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
+        basic::{Compression, ZstdLevel},
+    };
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{
+        common::{load, load_testdata},
+        parse::block::parse_many,
+    };
+
+    fn read(file: Vec<u8>) -> TestResult<(usize, Vec<RecordBatch>)> {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(file))?;
+        let row_groups = reader.metadata().num_row_groups();
+        let batches = reader.build()?.collect::<std::result::Result<_, _>>()?;
+        Ok((row_groups, batches))
+    }
+
+    #[test]
+    fn writes_testdata() -> TestResult {
+        for (path, data) in load_testdata()? {
+            let blocks = parse_many(&data)?;
+            let mut file = Vec::new();
+            match write_blocks(&mut file, &blocks, 1) {
+                Err(Error::NotImplemented(_)) => continue,
+                result => result?,
+            };
+
+            let (row_groups, batches) = read(file)?;
+            assert_eq!(row_groups, blocks.len(), "{}", path.display());
+            let rows = batches.iter().map(RecordBatch::num_rows).sum::<usize>();
+            let expected = blocks.iter().map(|block| block.num_rows).sum::<usize>();
+            assert_eq!(rows, expected, "{}", path.display());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn groups_blocks() -> TestResult {
+        let data = load("./testdata/decimal_sample.native")?;
+        let blocks = parse_many(&data)?;
+        let block = &blocks[0];
+
+        let mut file = Vec::new();
+        let mut writer = Writer::try_new(&mut file, block, 2)?;
+        for _ in 0..5 {
+            writer.write(block)?;
+        }
+        let metadata = writer.close()?;
+        assert_eq!(metadata.row_groups.len(), 3);
+
+        let (_, batches) = read(file)?;
+        let schema = batches[0].schema();
+        assert_eq!(
+            schema.field_with_name("d64")?.data_type(),
+            &arrow_schema::DataType::Decimal128(18, 6)
+        );
+        let written = batches[0].column(1).slice(0, block.num_rows);
+        assert_eq!(written.to_data(), record_batch(block)?.column(1).to_data());
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_decimal_precision() -> TestResult {
+        let typ = b"Decimal(5, 2)";
+        let mut data = vec![1, 1, 1, b'd', u8::try_from(typ.len())?];
+        data.extend(typ);
+        data.extend(12345_i32.to_le_bytes());
+        let blocks = parse_many(&data)?;
+
+        let mut file = Vec::new();
+        write_blocks(&mut file, &blocks, 1)?;
+        let (_, batches) = read(file)?;
+        let column = batches[0].column(0);
+        assert_eq!(
+            column.data_type(),
+            &arrow_schema::DataType::Decimal128(5, 2)
+        );
+        assert_eq!(
+            column.to_data(),
+            record_batch(&blocks[0])?.column(0).to_data()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compresses() -> TestResult {
+        for name in [
+            "sample_128",
+            "map_in_map",
+            "nullable_string",
+            "uuid_and_dates",
+        ] {
+            let data = load(format!("./testdata/{name}.native"))?;
+            let blocks = parse_many(&data)?;
+            for compression in [Compression::SNAPPY, Compression::ZSTD(ZstdLevel::default())] {
+                let mut file = Vec::new();
+                let properties = WriterProperties::builder().set_compression(compression);
+                let mut writer = Writer::with_properties(&mut file, &blocks[0], 1, properties)?;
+                for block in &blocks {
+                    writer.write(block)?;
+                }
+                let metadata = writer.close()?;
+                let codec = metadata.row_groups[0].columns[0]
+                    .meta_data
+                    .as_ref()
+                    .map(|meta| meta.codec);
+                assert_eq!(codec, Some(compression.into()), "{name}");
+
+                let (_, batches) = read(file)?;
+                let columns = batches.iter().map(RecordBatch::columns);
+                for (columns, block) in columns.zip(&blocks) {
+                    assert_eq!(columns, record_batch(block)?.columns(), "{name}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_variants() -> TestResult {
+        let data = load("./testdata/variant.native")?;
+        let blocks = parse_many(&data)?;
+        let result = write_blocks(Vec::new(), &blocks, 1);
+        assert!(matches!(result, Err(Error::NotImplemented(_))));
+        Ok(())
+    }
+}
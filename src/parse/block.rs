@@ -1,9 +1,16 @@
-use std::ops::Deref;
+use std::{
+    collections::HashSet,
+    num::NonZeroUsize,
+    ops::{Deref, Range},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
 
 use log::debug;
 
 use crate::{
-    ParsedBlock,
+    LazyBlock, LazyColumn, ParsedBlock,
+    error::ServerException,
     parse::{IResult, parse_var_str, parse_varuint, typ::parse_type},
 };
 
@@ -17,8 +24,15 @@ pub struct ParseContext<'a> {
     pub col_id: usize,
 
     pub column_name: &'a str,
+
+    /// How many types deep the decoder is, the types of `Dynamic` and JSON columns included.
+    pub depth: usize,
 }
 
+/// Types decoded deeper than this are rejected: a `Dynamic` or JSON column names its types in
+/// the data, so nesting is not bounded by the column type, and must not overflow the stack.
+const MAX_DEPTH: usize = 128;
+
 impl Deref for ParseContext<'_> {
     type Target = [u8];
 
@@ -36,8 +50,22 @@ impl<'a> ParseContext<'a> {
             num_rows: self.num_rows,
             col_id: self.col_id,
             column_name: self.column_name,
+            depth: self.depth,
         }
     }
+
+    /// The context of a type nested in the current one.
+    pub(crate) fn deeper(self) -> crate::Result<ParseContext<'a>> {
+        if self.depth >= MAX_DEPTH {
+            return Err(crate::Error::Parse(format!(
+                "Types nested deeper than {MAX_DEPTH} levels"
+            )));
+        }
+        Ok(ParseContext {
+            depth: self.depth + 1,
+            ..self
+        })
+    }
     pub fn with_column_name(self, column_name: &'a str) -> ParseContext<'a> {
         ParseContext {
             column_name,
@@ -48,17 +76,51 @@ impl<'a> ParseContext<'a> {
     pub fn with_num_rows(self, num_rows: usize) -> ParseContext<'a> {
         ParseContext { num_rows, ..self }
     }
+
+    /// Position of the input relative to the start of the block.
+    pub fn offset(&self) -> usize {
+        offset_in(self.initial, self.input)
+    }
+
+    /// Decodes the nested stream starting at `input`, attributing its errors to `segment` of
+    /// the type path.
+    pub(crate) fn within<T>(
+        &self,
+        segment: &str,
+        input: &'a [u8],
+        decode: impl FnOnce(ParseContext<'a>) -> crate::Result<T>,
+    ) -> crate::Result<T> {
+        let offset = offset_in(self.initial, input);
+        decode(self.fork(input)).map_err(|err| err.within(segment, offset))
+    }
+}
+
+fn offset_in(initial: &[u8], input: &[u8]) -> usize {
+    input
+        .as_ptr()
+        .addr()
+        .saturating_sub(initial.as_ptr().addr())
 }
 
 pub fn parse_single(input: &[u8]) -> IResult<&[u8], ParsedBlock> {
+    parse_block(input, |_| true)
+}
+
+/// Parses a single block, decoding only the columns listed in `columns`.
+/// The remaining columns are skipped by computing their byte length, without producing a
+/// [`crate::mark::Mark`] for them. The resulting block keeps the original column order.
+pub fn parse_single_projected<'a>(
+    input: &'a [u8],
+    columns: &HashSet<&str>,
+) -> IResult<&'a [u8], ParsedBlock<'a>> {
+    parse_block(input, |name| columns.contains(name))
+}
+
+fn parse_block(input: &[u8], wanted: impl Fn(&str) -> bool) -> IResult<&[u8], ParsedBlock<'_>> {
     if input.is_empty() {
         return Ok((
             input,
-            ParsedBlock {
-                markers: Vec::new(),
-                col_names: Vec::new(),
-                num_rows: 0,
-            },
+            ParsedBlock::new(Vec::new(), Vec::new(), Vec::new(), 0),
         ));
     }
 
@@ -69,6 +131,7 @@ pub fn parse_single(input: &[u8]) -> IResult<&[u8], ParsedBlock> {
         num_rows: 0,
         col_id: 0,
         column_name: "",
+        depth: 0,
     };
 
     let (input, num_columns) = parse_varuint(input)?;
@@ -79,8 +142,10 @@ pub fn parse_single(input: &[u8]) -> IResult<&[u8], ParsedBlock> {
     parse_context.num_columns = num_columns;
     parse_context.num_rows = num_rows;
 
-    let mut markers = Vec::with_capacity(num_columns);
-    let mut col_names = Vec::with_capacity(num_columns);
+    // every column takes at least a couple of bytes, don't trust the count for allocation
+    let mut markers = Vec::with_capacity(num_columns.min(input.len()));
+    let mut col_names = Vec::with_capacity(num_columns.min(input.len()));
+    let mut types = Vec::with_capacity(num_columns.min(input.len()));
 
     for index in 0..num_columns {
         debug!("Parsing column {} of {num_columns}", index + 1);
@@ -90,44 +155,304 @@ pub fn parse_single(input: &[u8]) -> IResult<&[u8], ParsedBlock> {
         (input, column_name) = parse_var_str(input)?;
         debug!("column name: {column_name}");
         parse_context.column_name = column_name;
-        col_names.push(column_name);
 
         let column_type;
         (input, column_type) = parse_var_str(input)?;
         debug!("{column_name}: column type: {column_type}");
 
+        let ctx = parse_context.fork(input);
+        let in_column = |err: crate::Error| err.in_column(column_name, ctx.offset());
+
         // convert back to bytes, converting to string needed to ensure encoding
         // and fail earlier, can be removed later
-        let (_, typ) = parse_type(column_type.as_bytes())?;
+        let (_, typ) = parse_type(column_type.as_bytes()).map_err(|err| in_column(err.into()))?;
         debug!("column type parsed: {:?}", typ);
 
-        let ctx = parse_context.fork(input);
         let header;
-        (input, header) = typ.decode_header(&ctx)?;
+        (input, header) = typ.decode_header(&ctx).map_err(in_column)?;
         debug!("Decoded header: `{header:?}` for column `{column_name}`");
 
+        if !wanted(column_name) {
+            input = typ.skip(&ctx.fork(input), &header).map_err(in_column)?;
+            debug!(
+                "Skipped column `{column_name}`, remaining bytes: {}",
+                input.len()
+            );
+            continue;
+        }
+
         let marker;
-        (input, marker) = typ.decode(ctx.fork(input), header)?;
+        (input, marker) = typ
+            .clone()
+            .decode(ctx.fork(input), header)
+            .map_err(in_column)?;
         debug!("Decoded, remaining bytes: {}", input.len());
 
+        col_names.push(column_name);
         markers.push(marker);
+        types.push(typ);
     }
 
-    Ok((
+    Ok((input, ParsedBlock::new(col_names, types, markers, num_rows)))
+}
+
+pub fn parse_many(input: &[u8]) -> Result<Vec<ParsedBlock>, crate::parse::Error> {
+    match parse_many_partial(input) {
+        (blocks, None) => Ok(blocks),
+        (_, Some(err)) => Err(err),
+    }
+}
+
+/// Parses as many blocks as possible, returning them together with the error that stopped the
+/// parsing, if any. When ClickHouse fails a query after it has already sent some blocks, it
+/// appends the exception text to the body; in that case the error is
+/// [`crate::Error::ServerException`] and the blocks are the ones received before it.
+pub fn parse_many_partial(input: &[u8]) -> (Vec<ParsedBlock<'_>>, Option<crate::parse::Error>) {
+    let mut blocks = Vec::new();
+    for block in parse_iter(input) {
+        match block {
+            Ok(block) => blocks.push(block),
+            Err(err) => return (blocks, Some(err)),
+        }
+    }
+
+    (blocks, None)
+}
+
+/// Parses the next block with `parse`, recognizing a server exception written instead of it.
+/// `index` and `start` locate the block in the whole input and are attached to the error.
+fn next_block<'a, T>(
+    input: &'a [u8],
+    index: usize,
+    start: usize,
+    parse: impl FnOnce(&'a [u8]) -> IResult<&'a [u8], T>,
+) -> IResult<&'a [u8], T> {
+    parse(input).map_err(|err| {
+        ServerException::detect(input).map_or_else(|| err.in_block(index, start), Into::into)
+    })
+}
+
+/// Lazily parses blocks one at a time. Unlike [`parse_many`], only the block being handed out
+/// is held in memory. Iteration stops after the first error.
+pub fn parse_iter(input: &[u8]) -> Blocks<'_> {
+    Blocks {
         input,
-        ParsedBlock {
-            markers,
-            col_names,
-            num_rows,
-        },
-    ))
+        index: 0,
+        start: 0,
+    }
+}
+
+pub struct Blocks<'a> {
+    input: &'a [u8],
+    index: usize,
+    start: usize,
+}
+
+impl<'a> Blocks<'a> {
+    /// The bytes that have not been parsed yet.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.input
+    }
+}
+
+impl<'a> Iterator for Blocks<'a> {
+    type Item = Result<ParsedBlock<'a>, crate::parse::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.is_empty() {
+            return None;
+        }
+
+        match next_block(self.input, self.index, self.start, parse_single) {
+            Ok((input, block)) => {
+                self.index += 1;
+                self.start += self.input.len() - input.len();
+                self.input = input;
+                Some(Ok(block))
+            }
+            Err(err) => {
+                self.input = &[];
+                Some(Err(err))
+            }
+        }
+    }
 }
 
-pub fn parse_many(mut input: &[u8]) -> Result<Vec<ParsedBlock>, crate::parse::Error> {
+/// Same as [`parse_many`], but only the columns listed in `columns` are decoded in every block.
+pub fn parse_many_projected<'a>(
+    mut input: &'a [u8],
+    columns: &HashSet<&str>,
+) -> Result<Vec<ParsedBlock<'a>>, crate::parse::Error> {
     let mut blocks = Vec::new();
+    let mut start = 0;
     while !input.is_empty() {
-        let block;
-        (input, block) = parse_single(input)?;
+        let (remainder, block) = next_block(input, blocks.len(), start, |input| {
+            parse_single_projected(input, columns)
+        })?;
+        start += input.len() - remainder.len();
+        input = remainder;
+        blocks.push(block);
+    }
+
+    Ok(blocks)
+}
+
+/// Finds the byte range of every block in `input` without decoding any column: the column data
+/// is only skipped over, which is much cheaper than building the markers.
+pub fn scan_boundaries(input: &[u8]) -> Result<Vec<Range<usize>>, crate::parse::Error> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < input.len() {
+        let (_, len) = next_block(&input[start..], ranges.len(), start, |input| {
+            Ok((input, block_len(input)?))
+        })?;
+        let end = start + len;
+        ranges.push(start..end);
+        start = end;
+    }
+
+    Ok(ranges)
+}
+
+/// Byte length of the first block in `input`, found by skipping over all of its columns.
+pub(crate) fn block_len(input: &[u8]) -> Result<usize, crate::parse::Error> {
+    let (remainder, _) = parse_block(input, |_| false)?;
+    Ok(input.len() - remainder.len())
+}
+
+/// Parses all blocks using up to `num_threads` std threads. Block boundaries are found first
+/// with [`scan_boundaries`], then the blocks are decoded in parallel. The result is in input order,
+/// and the first failing block (in input order) determines the error.
+pub fn parse_many_parallel(
+    input: &[u8],
+    num_threads: NonZeroUsize,
+) -> Result<Vec<ParsedBlock<'_>>, crate::parse::Error> {
+    let ranges = scan_boundaries(input)?;
+    let num_threads = num_threads.get().min(ranges.len());
+    if num_threads <= 1 {
+        return parse_many(input);
+    }
+
+    debug!("Decoding {} blocks on {num_threads} threads", ranges.len());
+
+    let next = AtomicUsize::new(0);
+    let worker = || {
+        let mut decoded = Vec::new();
+        loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(range) = ranges.get(index) else {
+                break decoded;
+            };
+            let block = parse_single(&input[range.clone()])
+                .map(|(_, block)| block)
+                .map_err(|err| err.in_block(index, range.start));
+            decoded.push((index, block));
+        }
+    };
+
+    let mut slots = Vec::with_capacity(ranges.len());
+    slots.resize_with(ranges.len(), || None);
+
+    thread::scope(|scope| {
+        let handles = (0..num_threads)
+            .map(|_| scope.spawn(worker))
+            .collect::<Vec<_>>();
+        for handle in handles {
+            let decoded = handle
+                .join()
+                .map_err(|_| crate::Error::ProgrammingError("decoder thread panicked".into()))?;
+            for (index, block) in decoded {
+                slots[index] = Some(block);
+            }
+        }
+        Ok::<_, crate::Error>(())
+    })?;
+
+    slots
+        .into_iter()
+        .map(|slot| {
+            slot.unwrap_or_else(|| {
+                Err(crate::Error::ProgrammingError(
+                    "block was not decoded".into(),
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Scans a single block without decoding it: every column's header is read and its data is
+/// skipped, so the result only records where each column lives. Columns are decoded on first
+/// access through [`LazyBlock::mark`].
+pub fn scan_single(input: &[u8]) -> IResult<&[u8], LazyBlock<'_>> {
+    if input.is_empty() {
+        return Ok((
+            input,
+            LazyBlock {
+                columns: Vec::new(),
+                num_rows: 0,
+            },
+        ));
+    }
+
+    let mut parse_context = ParseContext {
+        initial: input,
+        input,
+        num_columns: 0,
+        num_rows: 0,
+        col_id: 0,
+        column_name: "",
+        depth: 0,
+    };
+
+    let (input, num_columns) = parse_varuint(input)?;
+    let (mut input, num_rows) = parse_varuint(input)?;
+
+    debug!("num_columns={} num_rows={}", num_columns, num_rows);
+
+    parse_context.num_columns = num_columns;
+    parse_context.num_rows = num_rows;
+
+    let mut columns = Vec::with_capacity(num_columns.min(input.len()));
+
+    for index in 0..num_columns {
+        parse_context.col_id = index;
+
+        let column_name;
+        (input, column_name) = parse_var_str(input)?;
+        parse_context.column_name = column_name;
+
+        let column_type;
+        (input, column_type) = parse_var_str(input)?;
+        let ctx = parse_context.fork(input);
+        let in_column = |err: crate::Error| err.in_column(column_name, ctx.offset());
+
+        let (_, typ) = parse_type(column_type.as_bytes()).map_err(|err| in_column(err.into()))?;
+        let (data, header) = typ.decode_header(&ctx).map_err(in_column)?;
+        input = typ.skip(&ctx.fork(data), &header).map_err(in_column)?;
+        debug!("Scanned column `{column_name}`: {typ:?}");
+
+        let start = ctx.offset();
+        let column_len = ctx.input.len() - input.len();
+        let data = ctx.fork(&data[..data.len() - input.len()]);
+        columns.push(LazyColumn::new(
+            column_name,
+            typ,
+            header,
+            start..start + column_len,
+            data,
+        ));
+    }
+
+    Ok((input, LazyBlock { columns, num_rows }))
+}
+
+pub fn scan_many(mut input: &[u8]) -> Result<Vec<LazyBlock<'_>>, crate::parse::Error> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    while !input.is_empty() {
+        let (remainder, block) = next_block(input, blocks.len(), start, scan_single)?;
+        start += input.len() - remainder.len();
+        input = remainder;
         blocks.push(block);
     }
 
@@ -139,7 +464,9 @@ mod tests {
     use testresult::TestResult;
 
     use super::*;
-    use crate::common::load;
+    use crate::{
+        ColumnAccessor, OrderedBlocks, Unrequested, common::load, mark::Mark, value::Value,
+    };
 
     macro_rules! test_file {
         (
@@ -151,7 +478,26 @@ mod tests {
                 #[test]
                 fn $name() -> TestResult {
                     let buf = load($file)?;
-                    parse_many(&buf)?;
+                    let blocks = parse_many(&buf)?;
+
+                    // skipping every column must consume exactly the same bytes
+                    let skipped = parse_many_projected(&buf, &HashSet::new())?;
+                    assert_eq!(blocks.len(), skipped.len());
+                    for (block, skipped) in blocks.iter().zip(&skipped) {
+                        assert_eq!(block.num_rows, skipped.num_rows);
+                        assert!(skipped.markers.is_empty());
+                        assert!(skipped.col_names.is_empty());
+                    }
+
+                    let scanned = scan_many(&buf)?;
+                    assert_eq!(blocks.len(), scanned.len());
+                    for (block, scanned) in blocks.iter().zip(&scanned) {
+                        assert_eq!(block.num_rows, scanned.num_rows);
+                        assert_eq!(block.col_names, scanned.col_names().collect::<Vec<_>>());
+                        for (index, marker) in block.markers.iter().enumerate() {
+                            assert_eq!(format!("{marker:?}"), format!("{:?}", scanned.mark(index)?));
+                        }
+                    }
                     Ok(())
                 }
             )*
@@ -180,4 +526,401 @@ mod tests {
         variant_arr => "./testdata/variant_arr.native",
         dynamic_arr => "./testdata/dynamic_arr.native",
     }
+
+    #[test]
+    fn projection_keeps_requested_columns() -> TestResult {
+        let buf = load("./testdata/sample.native")?;
+        let blocks = parse_many(&buf)?;
+        let full = &blocks[0];
+
+        let wanted = full
+            .col_names
+            .iter()
+            .copied()
+            .step_by(3)
+            .collect::<HashSet<_>>();
+        let projected = parse_many_projected(&buf, &wanted)?;
+        let projected = &projected[0];
+
+        let expected_names = full
+            .col_names
+            .iter()
+            .copied()
+            .filter(|name| wanted.contains(name))
+            .collect::<Vec<_>>();
+        assert_eq!(projected.col_names, expected_names);
+        assert_eq!(projected.num_rows, full.num_rows);
+
+        for (name, marker) in projected.col_names.iter().zip(&projected.markers) {
+            let index = full.col_names.iter().position(|n| n == name).unwrap();
+            assert_eq!(
+                format!("{marker:?}"),
+                format!("{:?}", full.markers[index]),
+                "column {name}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn scan_decodes_on_access() -> TestResult {
+        let buf = load("./testdata/sample.native")?;
+        let (_, block) = scan_single(&buf)?;
+
+        assert!(block.columns.iter().all(|column| !column.is_decoded()));
+        block.mark(1)?;
+        assert!(block.columns[1].is_decoded());
+        assert!(!block.columns[0].is_decoded());
+
+        for pair in block.columns.windows(2) {
+            assert!(pair[0].range.end < pair[1].range.start);
+        }
+        let (remainder, _) = parse_single(&buf)?;
+        let last = block.columns.last().unwrap();
+        assert_eq!(last.range.end, buf.len() - remainder.len());
+
+        let (_, parsed) = parse_single(&buf)?;
+        let lazy = block.into_parsed()?;
+        assert_eq!(parsed.col_names, lazy.col_names);
+        assert_eq!(
+            format!("{:?}", parsed.markers),
+            format!("{:?}", lazy.markers)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_matches_sequential() -> TestResult {
+        let mut buf = Vec::new();
+        for file in [
+            "./testdata/sample.native",
+            "./testdata/json.native",
+            "./testdata/array_lc_string.native",
+            "./testdata/dynamic_arr.native",
+            "./testdata/geo_sample.native",
+            "./testdata/variant.native",
+            "./testdata/map_nullable_lc_string.native",
+        ] {
+            buf.extend(load(file)?);
+        }
+
+        let sequential = parse_many(&buf)?;
+        let ranges = scan_boundaries(&buf)?;
+        assert_eq!(ranges.len(), sequential.len());
+        assert_eq!(ranges.last().map(|r| r.end), Some(buf.len()));
+
+        let parallel = parse_many_parallel(&buf, NonZeroUsize::new(3).unwrap())?;
+        assert_eq!(sequential.len(), parallel.len());
+        for (left, right) in sequential.iter().zip(&parallel) {
+            assert_eq!(left.col_names, right.col_names);
+            assert_eq!(
+                format!("{:?}", left.markers),
+                format!("{:?}", right.markers)
+            );
+        }
+
+        Ok(())
+    }
+
+    const EXCEPTION: &str = "Code: 395. DB::Exception: Value passed to 'throwIf' function is \
+                             non-zero: while executing 'FUNCTION throwIf(greater(number, 3)) :: \
+                             2'. (FUNCTION_THROW_IF_VALUE_IS_NON_ZERO) (version 24.8.4.13 \
+                             (official build))\n";
+
+    #[test]
+    fn server_exception_after_blocks() -> TestResult {
+        let mut buf = load("./testdata/plain_strings.native")?;
+        let num_blocks = parse_many(&buf)?.len();
+        buf.extend_from_slice(EXCEPTION.as_bytes());
+
+        let Err(crate::Error::ServerException(exception)) = parse_many(&buf) else {
+            panic!("expected a server exception");
+        };
+        assert_eq!(exception.code, 395);
+        assert_eq!(
+            exception.name.as_deref(),
+            Some("FUNCTION_THROW_IF_VALUE_IS_NON_ZERO")
+        );
+        assert!(exception.message.starts_with("Value passed to 'throwIf'"));
+        assert!(exception.message.ends_with("(official build))"));
+        assert_eq!(exception.stack_trace, None);
+
+        let (blocks, err) = parse_many_partial(&buf);
+        assert_eq!(blocks.len(), num_blocks);
+        assert!(matches!(err, Some(crate::Error::ServerException(_))));
+
+        let Err(crate::Error::ServerException(_)) = scan_boundaries(&buf) else {
+            panic!("expected a server exception");
+        };
+
+        Ok(())
+    }
+
+    #[test]
+    fn framed_server_exception_with_stack_trace() -> TestResult {
+        let mut buf = load("./testdata/array.native")?;
+        buf.extend_from_slice(
+            b"\r\n__exception__\r\nPU1FNUFH98\r\nCode: 241. DB::Exception: Memory limit \
+              (total) exceeded. (MEMORY_LIMIT_EXCEEDED) (version 25.1.1.1)\nStack trace:\n\n0. \
+              DB::Exception::Exception()\n1. DB::MemoryTracker::allocImpl()\r\n143 \
+              PU1FNUFH98\r\n__exception__\r\n",
+        );
+
+        let Err(crate::Error::ServerException(exception)) = parse_many(&buf) else {
+            panic!("expected a server exception");
+        };
+        assert_eq!(exception.code, 241);
+        assert_eq!(exception.name.as_deref(), Some("MEMORY_LIMIT_EXCEEDED"));
+        assert_eq!(
+            exception.message,
+            "Memory limit (total) exceeded. (MEMORY_LIMIT_EXCEEDED) (version 25.1.1.1)"
+        );
+        assert_eq!(
+            exception.stack_trace.as_deref(),
+            Some("0. DB::Exception::Exception()\n1. DB::MemoryTracker::allocImpl()")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn exception_text_inside_a_block() -> TestResult {
+        let mut buf = load("./testdata/plain_strings.native")?;
+        let num_blocks = parse_many(&buf)?.len();
+        let start = buf.len();
+
+        buf.extend([2, 1]);
+        buf.extend([1, b's', 6]);
+        buf.extend(b"String");
+        buf.push(10);
+        buf.extend(b"Code: 1. x");
+        buf.extend([1, b'n', 6]);
+        buf.extend(b"UInt64");
+        let data = buf.len();
+        // only half of the value
+        buf.extend([0; 4]);
+
+        let mut blocks = parse_iter(&buf);
+        for _ in 0..num_blocks {
+            blocks.next().transpose()?;
+        }
+        let Some(Err(err)) = blocks.next() else {
+            panic!("expected an error");
+        };
+        assert!(matches!(err.root_cause(), crate::Error::Length(8)));
+        let context = err.context().unwrap();
+        assert_eq!(context.block, Some(num_blocks));
+        assert_eq!(context.column.as_deref(), Some("n"));
+        assert_eq!(context.offset, data);
+
+        // the same value in a complete block is just a string
+        buf.extend([0; 4]);
+        let blocks = parse_many(&buf)?;
+        assert_eq!(blocks.len(), num_blocks + 1);
+        let Some(Value::String("Code: 1. x")) = blocks[num_blocks].markers[0].get(0) else {
+            panic!("expected the string");
+        };
+
+        let Err(err) = scan_boundaries(&buf[..buf.len() - 1]) else {
+            panic!("expected an error");
+        };
+        assert_eq!(
+            err.context().and_then(|context| context.block),
+            Some(num_blocks)
+        );
+        assert_eq!(
+            scan_boundaries(&buf)?.last().map(|range| range.start),
+            Some(start)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_utf8_is_kept_as_bytes() -> TestResult {
+        for (typ, value) in [
+            ("String", &[2, b'a', 0xff][..]),
+            ("FixedString(2)", b"a\xff"),
+        ] {
+            let mut buf = vec![1, 1, 1, b's', u8::try_from(typ.len())?];
+            buf.extend(typ.as_bytes());
+            buf.extend(value);
+
+            let blocks = parse_many(&buf)?;
+            let marker = &blocks[0].markers[0];
+            assert_eq!(marker.get(0), Some(Value::Bytes(b"a\xff")), "{typ}");
+
+            let Err(err) = marker.get_str(0) else {
+                panic!("expected an error for {typ}");
+            };
+            assert!(
+                matches!(&err, crate::Error::Utf8Decode(_, bytes) if bytes == b"a\xff"),
+                "{err}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn nested_dynamic_headers_are_rejected() -> TestResult {
+        let mut buf = vec![1, 1, 1, b'd', 7];
+        buf.extend(b"Dynamic");
+        for _ in 0..100_000 {
+            // version 2 with one type, `Dynamic` again, and the variant mode
+            buf.extend(2u64.to_le_bytes());
+            buf.extend([1, 7]);
+            buf.extend(b"Dynamic");
+            buf.extend(0u64.to_le_bytes());
+        }
+
+        let Err(err) = parse_many(&buf) else {
+            panic!("expected an error");
+        };
+        assert!(
+            matches!(err.root_cause(), crate::Error::Parse(message) if message.contains("deeper")),
+            "{err}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_error_has_context() -> TestResult {
+        let mut buf = load("./testdata/plain_strings.native")?;
+        let num_blocks = parse_many(&buf)?.len();
+
+        let typ = "Map(String, Array(Nullable(Int64)))";
+        buf.extend([1, 1, 5]);
+        buf.extend(b"value");
+        buf.push(u8::try_from(typ.len())?);
+        buf.extend(typ.as_bytes());
+        // one key `k` with two array elements
+        buf.extend(1u64.to_le_bytes());
+        buf.extend([1, b'k']);
+        buf.extend(2u64.to_le_bytes());
+        let null_map = buf.len();
+        // only half of the null map
+        buf.push(0);
+
+        let Err(err) = parse_many(&buf) else {
+            panic!("expected an error");
+        };
+        assert!(matches!(err.root_cause(), crate::Error::Length(2)));
+        assert!(err.is_incomplete());
+
+        let context = err.context().unwrap();
+        assert_eq!(context.block, Some(num_blocks));
+        assert_eq!(context.column.as_deref(), Some("value"));
+        assert_eq!(context.path, "value.values.element.null_map");
+        assert_eq!(context.offset, null_map);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "block {num_blocks}, column `value.values.element.null_map`, byte {null_map}: \
+                 Length error: 2"
+            )
+        );
+
+        let Err(err) = scan_boundaries(&buf) else {
+            panic!("expected an error");
+        };
+        assert_eq!(err.context(), Some(context));
+
+        Ok(())
+    }
+
+    #[test]
+    fn column_lookup_by_name() -> TestResult {
+        let buf = load("./testdata/nullable_string.native")?;
+        let mut blocks = parse_many(&buf)?;
+        assert_eq!(blocks[0].col_names, ["id", "nstr"]);
+
+        assert!(matches!(blocks[0].column("nstr"), Some(Mark::Nullable(_))));
+        assert!(blocks[0].column("missing").is_none());
+        assert!(blocks[0].resolve("missing").is_none());
+
+        let expected = crate::iter_blocks(&blocks)
+            .map(|row| format!("{:?}", row.get("nstr").map(ColumnAccessor::get)))
+            .collect::<Vec<_>>();
+
+        let handle = blocks[0].resolve("nstr").unwrap();
+        let by_handle = crate::iter_blocks(&blocks)
+            .map(|row| format!("{:?}", row.column(&handle).map(ColumnAccessor::get)))
+            .collect::<Vec<_>>();
+        assert_eq!(expected, by_handle);
+
+        // the handle still finds the column after the blocks were reordered
+        let reordered = crate::iter_blocks_ordered(&mut blocks, &["nstr", "id"])?
+            .map(|row| {
+                assert_eq!(handle.index_in(row.block()), Some(0));
+                format!("{:?}", row.column(&handle).map(ColumnAccessor::get))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, reordered);
+
+        Ok(())
+    }
+
+    #[test]
+    fn column_lookup_after_edits() -> TestResult {
+        let buf = load("./testdata/nullable_string.native")?;
+        let mut block = parse_many(&buf)?.swap_remove(0);
+        assert_eq!(block.column_index("nstr"), Some(1));
+
+        block.col_names.swap(0, 1);
+        block.markers.swap(0, 1);
+        block.types.swap(0, 1);
+        assert_eq!(block.column_index("nstr"), Some(0));
+        assert_eq!(block.column_index("id"), Some(1));
+
+        block.col_names[1] = "renamed";
+        assert_eq!(block.column_index("id"), None);
+        assert_eq!(block.column_index("renamed"), Some(1));
+
+        let block = ParsedBlock {
+            markers: block.markers,
+            col_names: vec!["a", "b"],
+            types: block.types,
+            num_rows: block.num_rows,
+            ..Default::default()
+        };
+        assert_eq!(block.column_index("b"), Some(1));
+        assert!(matches!(block.column("a"), Some(Mark::Nullable(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn ordered_view() -> TestResult {
+        fn names(view: &OrderedBlocks<'_>) -> Vec<Vec<String>> {
+            view.iter()
+                .map(|row| row.map(|(name, _)| name.to_owned()).collect())
+                .collect()
+        }
+
+        let buf = load("./testdata/nullable_string.native")?;
+        let blocks = parse_many(&buf)?;
+        let view = OrderedBlocks::new(&blocks, &["nstr"], Unrequested::Append)?;
+        assert_eq!(view.columns(0), Some(&[1, 0][..]));
+        assert!(names(&view).iter().all(|row| row == &["nstr", "id"]));
+
+        let view = OrderedBlocks::new(&blocks, &["nstr"], Unrequested::Drop)?;
+        assert!(names(&view).iter().all(|row| row == &["nstr"]));
+        assert_eq!(view.iter().count(), crate::iter_blocks(&blocks).count());
+
+        let Err(crate::error::Error::InvalidColumnOrder(message)) =
+            OrderedBlocks::new(&blocks, &["nstr"], Unrequested::Error)
+        else {
+            panic!("expected an unrequested column error");
+        };
+        assert!(message.contains("id"), "{message}");
+        assert!(OrderedBlocks::new(&blocks, &["nstr", "missing"], Unrequested::Drop).is_err());
+
+        // the blocks are left as they were
+        assert_eq!(blocks[0].col_names, ["id", "nstr"]);
+
+        Ok(())
+    }
 }
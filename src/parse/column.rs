@@ -11,7 +11,7 @@ use crate::{
             HAS_ADDITIONAL_KEYS_BIT, NEED_GLOBAL_DICTIONARY_BIT, NEED_UPDATE_DICTIONARY_BIT,
             TUINT8, TUINT16, TUINT32, TUINT64,
         },
-        header, parse_offsets, parse_u64, parse_var_str_bytes, parse_varuint,
+        header, parse_offsets, parse_u64, parse_var_str_bytes, parse_varuint, take,
     },
     types::{DynamicHeader, Field, JsonHeader, MapHeader, OffsetIndexPair as _, Type, TypeHeader},
 };
@@ -22,12 +22,13 @@ impl<'a> Type<'a> {
         ctx: &ParseContext<'a>,
     ) -> IResult<&'a [u8], TypeHeader<'a>> {
         debug!("Decoding header for type: {self:?}");
+        let ctx = &ctx.clone().deeper()?;
         match self {
             Type::Nullable(inner) => {
                 let (input, th) = inner.decode_header(ctx)?;
                 Ok((input, th))
             }
-            Type::Tuple(inner) => {
+            Type::Tuple(inner, _) => {
                 let (input, headers) = header::tuple(ctx, inner)?;
                 Ok((input, TypeHeader::Tuple(headers)))
             }
@@ -76,32 +77,267 @@ impl<'a> Type<'a> {
         header: TypeHeader<'a>,
     ) -> IResult<&'a [u8], Mark<'a>> {
         debug!("Decoding type: {self:?} with header: {header:?}");
+        let ctx = ctx.deeper()?;
 
         if let Some(size) = self.size() {
-            let (data, input) = ctx.input.split_at(size * ctx.num_rows);
+            let len = size
+                .checked_mul(ctx.num_rows)
+                .ok_or_else(|| Error::Overflow(format!("{} rows of {self:?}", ctx.num_rows)))?;
+            let (input, data) = take(ctx.input, len)?;
             let marker = self.into_fixed_size_marker(data)?;
             return Ok((input, marker));
         }
 
         match self {
             Type::String => string(&ctx),
-            Type::Array(inner) => array(*inner, &ctx, header.into_array()),
-            Type::Point => t!(Tuple(vec![t!(Float64), t!(Float64)])).decode(ctx, header),
+            Type::Array(inner) => array(*inner, &ctx, header.into_array()?),
+            Type::Point => {
+                t!(Tuple(vec![t!(Float64), t!(Float64)], Box::default())).decode(ctx, header)
+            }
             Type::Ring | Type::LineString => t!(Array(bt!(Point))).decode(ctx, header),
             Type::Polygon | Type::MultiLineString => t!(Array(bt!(Ring))).decode(ctx, header),
             Type::MultiPolygon => t!(Array(bt!(Polygon))).decode(ctx, header),
-            Type::Tuple(inner) => tuple(inner, &ctx, header.into_tuple()),
-            Type::Map(key, value) => map(*key, *value, &ctx, header.into_map()),
-            Type::Variant(inner) => variant(inner, &ctx, header.into_variant()),
+            Type::Tuple(inner, names) => tuple(inner, names.into_vec(), &ctx, header.into_tuple()?),
+            Type::Map(key, value) => map(*key, *value, &ctx, header.into_map()?),
+            Type::Variant(inner) => variant(inner, &ctx, header.into_variant()?),
             Type::LowCardinality(inner) => lc(inner.as_ref(), &ctx),
-            Type::Nullable(inner) => nullable(*inner, &ctx, header.into_nullable()),
-            Type::Dynamic => dynamic(&ctx, header.into_dynamic()),
-            Type::Json => json(&ctx, header.into_json()),
-            Type::Nested(fields) => nested(fields, ctx, header.into_nested()),
-            _ => {
-                unimplemented!("decode is not implemented for {self:?}")
+            Type::Nullable(inner) => nullable(*inner, &ctx, header.into_nullable()?),
+            Type::Dynamic => dynamic(&ctx, header.into_dynamic()?),
+            Type::Json => json(&ctx, header.into_json()?),
+            Type::Nested(fields) => nested(fields, ctx, header.into_nested()?),
+            _ => Err(Error::NotImplemented(format!(
+                "decode is not implemented for {self:?}"
+            ))),
+        }
+    }
+
+    /// Walks over the column data the same way [`Type::decode`] does, but only computes the
+    /// byte length, so no [`Mark`] (and none of its offset vectors) is built. Returns the input
+    /// right after the column.
+    pub(crate) fn skip(
+        &self,
+        ctx: &ParseContext<'a>,
+        header: &TypeHeader<'a>,
+    ) -> crate::Result<&'a [u8]> {
+        let ctx = &ctx.clone().deeper()?;
+        if let Some(size) = self.size() {
+            let len = size
+                .checked_mul(ctx.num_rows)
+                .ok_or_else(|| Error::Overflow(format!("{} rows of {self:?}", ctx.num_rows)))?;
+            let (input, _) = take(ctx.input, len)?;
+            return Ok(input);
+        }
+
+        match (self, header) {
+            (Type::String, _) => skip_strings(ctx.input, ctx.num_rows),
+            (Type::Array(inner), TypeHeader::Array(header)) => {
+                let (input, offsets) = parse_offsets(ctx.input, ctx.num_rows)?;
+                let num_rows = offsets.last_or_default()?;
+                ctx.within("element", input, |ctx| {
+                    inner.skip(&ctx.with_num_rows(num_rows), header)
+                })
+            }
+            (Type::Point, _) => {
+                t!(Tuple(vec![t!(Float64), t!(Float64)], Box::default())).skip(ctx, header)
+            }
+            (Type::Ring | Type::LineString, _) => t!(Array(bt!(Point))).skip(ctx, header),
+            (Type::Polygon | Type::MultiLineString, _) => t!(Array(bt!(Ring))).skip(ctx, header),
+            (Type::MultiPolygon, _) => t!(Array(bt!(Polygon))).skip(ctx, header),
+            (Type::Tuple(inner, _), TypeHeader::Tuple(headers)) => {
+                let mut input = ctx.input;
+                for (index, (typ, header)) in inner.iter().zip(headers).enumerate() {
+                    input = ctx.within(&(index + 1).to_string(), input, |ctx| {
+                        typ.skip(&ctx, header)
+                    })?;
+                }
+                Ok(input)
+            }
+            (Type::Map(key, value), TypeHeader::Map(header)) => {
+                let (input, offsets) = parse_offsets(ctx.input, ctx.num_rows)?;
+                let n = offsets.last_or_default()?;
+                let input = ctx.within("keys", input, |ctx| {
+                    key.skip(&ctx.with_num_rows(n), &header.key)
+                })?;
+                ctx.within("values", input, |ctx| {
+                    value.skip(&ctx.with_num_rows(n), &header.value)
+                })
             }
+            (Type::Variant(inner), TypeHeader::Variant(headers)) => {
+                skip_variant(inner, ctx, headers)
+            }
+            (Type::LowCardinality(inner), _) => skip_lc(inner, ctx),
+            (Type::Nullable(inner), _) => {
+                let (input, _mask) =
+                    ctx.within("null_map", ctx.input, |ctx| take(ctx.input, ctx.num_rows))?;
+                inner.skip(&ctx.fork(input), header)
+            }
+            (Type::Dynamic, TypeHeader::Dynamic(header)) => skip_dynamic(ctx, header),
+            (Type::Json, TypeHeader::Json(header)) => skip_json(ctx, header),
+            (Type::Nested(fields), TypeHeader::Nested(headers)) => {
+                // Nested is stored as Array(Tuple(...)): one offsets column, then every field
+                let (mut input, offsets) = parse_offsets(ctx.input, ctx.num_rows)?;
+                let num_rows = offsets.last_or_default()?;
+                for (index, (field, header)) in fields.iter().zip(headers).enumerate() {
+                    let segment = format!("element.{}", index + 1);
+                    input = ctx.within(&segment, input, |ctx| {
+                        field.typ.skip(&ctx.with_num_rows(num_rows), header)
+                    })?;
+                }
+                Ok(input)
+            }
+            _ => Err(Error::Parse(format!(
+                "skip: unexpected header {header:?} for type {self:?}"
+            ))),
+        }
+    }
+}
+
+fn skip_strings(mut input: &[u8], num_rows: usize) -> crate::Result<&[u8]> {
+    for _ in 0..num_rows {
+        (input, _) = parse_var_str_bytes(input)?;
+    }
+    Ok(input)
+}
+
+fn skip_json<'a>(ctx: &ParseContext<'a>, header: &JsonHeader<'a>) -> crate::Result<&'a [u8]> {
+    let mut input = ctx.input;
+    let num_rows = ctx.num_rows;
+
+    for ((path, col_header), type_header) in header
+        .paths
+        .iter()
+        .zip(&header.col_headers)
+        .zip(&header.type_headers)
+    {
+        input = ctx.within(path, input, |ctx| {
+            let (input, discriminators) = take(ctx.input, num_rows)?;
+            let count = discriminators.iter().filter(|&&d| d != 255).count();
+            col_header
+                .typ
+                .skip(&ctx.fork(input).with_num_rows(count), type_header)
+        })?;
+    }
+
+    let shared_data_size = num_rows
+        .checked_mul(8)
+        .ok_or_else(|| Error::Overflow(format!("JSON shared data for {num_rows} rows")))?;
+    let (input, _shared_data) = take(input, shared_data_size)?;
+
+    Ok(input)
+}
+
+fn skip_dynamic<'a>(ctx: &ParseContext<'a>, header: &DynamicHeader<'a>) -> crate::Result<&'a [u8]> {
+    let mut row_counts = vec![0usize; header.types.len()];
+    let mut input = ctx.input;
+
+    for _ in 0..ctx.num_rows {
+        let disc: usize;
+        (input, disc) = parse_varuint(input)?;
+        let Some(count) = row_counts.get_mut(disc) else {
+            return Err(Error::Parse(format!(
+                "Dynamic: discriminator {disc} out of bounds for {} types",
+                header.types.len()
+            )));
+        };
+        *count += 1;
+    }
+
+    for (index, ((typ, header), read_rows)) in header
+        .types
+        .iter()
+        .zip(&header.headers)
+        .zip(row_counts)
+        .enumerate()
+    {
+        if matches!(typ, Type::SharedVariant) {
+            continue;
+        }
+        input = ctx.within(&index.to_string(), input, |ctx| {
+            typ.skip(&ctx.with_num_rows(read_rows), header)
+        })?;
+    }
+
+    Ok(input)
+}
+
+fn skip_variant<'a>(
+    inner: &[Type<'a>],
+    ctx: &ParseContext<'a>,
+    headers: &[TypeHeader<'a>],
+) -> crate::Result<&'a [u8]> {
+    const NULL_DISCR: u8 = 255;
+
+    let (mut input, discriminators) = take(ctx.input, ctx.num_rows)?;
+    let mut row_counts = vec![0; inner.len()];
+    for discriminator in discriminators.iter().copied() {
+        if discriminator == NULL_DISCR {
+            continue;
         }
+        let Some(count) = row_counts.get_mut(discriminator as usize) else {
+            return Err(Error::Parse(format!(
+                "Variant: discriminator {discriminator} out of bounds for inner types length {}",
+                inner.len()
+            )));
+        };
+        *count += 1;
+    }
+
+    for (index, ((typ, header), read_rows)) in inner.iter().zip(headers).zip(row_counts).enumerate()
+    {
+        input = ctx.within(&index.to_string(), input, |ctx| {
+            typ.skip(&ctx.with_num_rows(read_rows), header)
+        })?;
+    }
+
+    Ok(input)
+}
+
+fn skip_lc<'a>(inner: &Type<'a>, ctx: &ParseContext<'a>) -> crate::Result<&'a [u8]> {
+    if ctx.num_rows == 0 {
+        return Ok(ctx.input);
+    }
+
+    let (mut input, flags) = parse_u64::<u64>(ctx.input)?;
+    let index_type = lc_index_type(flags)?;
+    let base_inner = inner.strip_null();
+
+    if flags & NEED_GLOBAL_DICTIONARY_BIT != 0 {
+        let cnt: usize;
+        (input, cnt) = parse_u64(input)?;
+        input = ctx.within("dictionary", input, |ctx| {
+            base_inner.skip(&ctx.with_num_rows(cnt), &TypeHeader::Empty)
+        })?;
+    }
+
+    if flags & HAS_ADDITIONAL_KEYS_BIT != 0 {
+        let cnt: usize;
+        (input, cnt) = parse_u64(input)?;
+        input = ctx.within("dictionary", input, |ctx| {
+            base_inner.skip(&ctx.with_num_rows(cnt), &TypeHeader::Empty)
+        })?;
+    }
+
+    let rows_here: usize;
+    (input, rows_here) = parse_u64(input)?;
+    if rows_here != ctx.num_rows {
+        return Err(Error::Parse(format!(
+            "LowCardinality: expected {} rows, got {rows_here}",
+            ctx.num_rows
+        )));
+    }
+
+    ctx.within("indices", input, |ctx| {
+        index_type.skip(&ctx, &TypeHeader::Empty)
+    })
+}
+
+fn lc_index_type(flags: u64) -> crate::Result<Type<'static>> {
+    match flags & 0xff {
+        TUINT8 => Ok(Type::UInt8),
+        TUINT16 => Ok(Type::UInt16),
+        TUINT32 => Ok(Type::UInt32),
+        TUINT64 => Ok(Type::UInt64),
+        x => Err(Error::Parse(format!("LowCardinality: bad index type: {x}"))),
     }
 }
 
@@ -116,29 +352,30 @@ fn json<'a>(
     let mut input = ctx.input;
     let num_rows = ctx.num_rows;
 
-    for (col_header, type_header) in col_headers.iter_mut().zip(type_headers) {
-        let discriminators;
-        (discriminators, input) = input.split_at(num_rows);
+    for ((path, col_header), type_header) in paths.iter().zip(&mut col_headers).zip(type_headers) {
+        input = ctx.within(path, input, |ctx| {
+            let (input, discriminators) = take(ctx.input, num_rows)?;
 
-        let offsets = &mut col_header.offsets;
+            let offsets = &mut col_header.offsets;
 
-        offsets.resize(num_rows, 0);
-        let mut counter = 0usize;
+            offsets.resize(num_rows, 0);
+            let mut counter = 0usize;
 
-        for (discriminator, offset) in discriminators.iter().copied().zip(offsets.iter_mut()) {
-            *offset = counter;
-            if discriminator != 255 {
-                counter += 1;
+            for (discriminator, offset) in discriminators.iter().copied().zip(offsets.iter_mut()) {
+                *offset = counter;
+                if discriminator != 255 {
+                    counter += 1;
+                }
             }
-        }
 
-        let marker;
-        (input, marker) = col_header
-            .typ
-            .clone()
-            .decode(ctx.fork(input).with_num_rows(counter), type_header)?;
-        col_header.mark = marker;
-        col_header.discriminators = discriminators;
+            let (input, marker) = col_header
+                .typ
+                .clone()
+                .decode(ctx.fork(input).with_num_rows(counter), type_header)?;
+            col_header.mark = marker;
+            col_header.discriminators = discriminators;
+            Ok(input)
+        })?;
     }
 
     let marker = Mark::Json(Json {
@@ -147,15 +384,21 @@ fn json<'a>(
     });
 
     // https://github.com/ClickHouse/clickhouse-go/blob/71a2b475e899afe9626f40af513bcf25aa3098a2/lib/column/json.go#L569-L572
-    let shared_data_size = num_rows * 8;
+    let shared_data_size = num_rows
+        .checked_mul(8)
+        .ok_or_else(|| Error::Overflow(format!("JSON shared data for {num_rows} rows")))?;
     let _shared_data;
-    (_shared_data, input) = input.split_at(shared_data_size);
+    (input, _shared_data) = take(input, shared_data_size)?;
 
     Ok((input, marker))
 }
 
 fn dynamic<'a>(ctx: &ParseContext<'a>, header: DynamicHeader<'a>) -> IResult<&'a [u8], Mark<'a>> {
     let types = header.types;
+    // every discriminator takes at least one byte
+    if ctx.num_rows > ctx.input.len() {
+        return Err(Error::Length(ctx.num_rows));
+    }
     let mut discriminators = Vec::with_capacity(ctx.num_rows);
     let mut offsets = vec![0usize; ctx.num_rows];
     let mut row_counts = vec![0usize; types.len()];
@@ -166,14 +409,20 @@ fn dynamic<'a>(ctx: &ParseContext<'a>, header: DynamicHeader<'a>) -> IResult<&'a
         let disc;
         (input, disc) = parse_varuint(input)?;
 
-        *offset = row_counts[disc];
-        row_counts[disc] += 1;
+        let Some(count) = row_counts.get_mut(disc) else {
+            return Err(Error::Parse(format!(
+                "Dynamic: discriminator {disc} out of bounds for {} types",
+                types.len()
+            )));
+        };
+        *offset = *count;
+        *count += 1;
 
         discriminators.push(disc);
     }
 
     let mut columns = Vec::with_capacity(types.len());
-    for ((i, typ), header) in types.into_iter().enumerate().zip(header.headers) {
+    for ((i, typ), header) in types.iter().cloned().enumerate().zip(header.headers) {
         if matches!(typ, Type::SharedVariant) {
             columns.push(Mark::Empty);
             continue;
@@ -186,15 +435,18 @@ fn dynamic<'a>(ctx: &ParseContext<'a>, header: DynamicHeader<'a>) -> IResult<&'a
             input.len()
         );
         let marker;
-        (input, marker) = typ.decode(ctx.fork(input).with_num_rows(read_rows), header)?;
+        (input, marker) = ctx.within(&i.to_string(), input, |ctx| {
+            typ.decode(ctx.with_num_rows(read_rows), header)
+        })?;
         columns.push(marker);
     }
 
-    let marker = Mark::Dynamic(Dynamic {
+    let marker = Mark::Dynamic(Box::new(Dynamic {
         offsets,
         discriminators,
+        types,
         columns,
-    });
+    }));
 
     Ok((input, marker))
 }
@@ -204,7 +456,7 @@ fn nullable<'a>(
     ctx: &ParseContext<'a>,
     header: TypeHeader<'a>,
 ) -> IResult<&'a [u8], Mark<'a>> {
-    let (mask, input) = ctx.input.split_at(ctx.num_rows);
+    let (input, mask) = ctx.within("null_map", ctx.input, |ctx| take(ctx.input, ctx.num_rows))?;
     // here we pass through the header
     let (input, marker) = inner.decode(ctx.fork(input), header)?;
     let mark_nullable = Nullable {
@@ -242,15 +494,7 @@ fn lc<'a>(inner: &Type<'a>, ctx: &ParseContext<'a>) -> IResult<&'a [u8], Mark<'a
         ctx.num_rows
     );
 
-    let index_type = match flags & 0xff {
-        TUINT8 => Type::UInt8,
-        TUINT16 => Type::UInt16,
-        TUINT32 => Type::UInt32,
-        TUINT64 => Type::UInt64,
-        x => {
-            return Err(Error::Parse(format!("LowCardinality: bad index type: {x}")));
-        }
-    };
+    let index_type = lc_index_type(flags)?;
 
     let base_inner = inner.strip_null().clone();
 
@@ -260,9 +504,11 @@ fn lc<'a>(inner: &Type<'a>, ctx: &ParseContext<'a>) -> IResult<&'a [u8], Mark<'a
         (input, cnt) = parse_u64(input)?;
 
         let dict_marker;
-        (input, dict_marker) = base_inner
-            .clone()
-            .decode(ctx.fork(input).with_num_rows(cnt), TypeHeader::Empty)?;
+        (input, dict_marker) = ctx.within("dictionary", input, |ctx| {
+            base_inner
+                .clone()
+                .decode(ctx.with_num_rows(cnt), TypeHeader::Empty)
+        })?;
         global_dictionary = Some(Box::new(dict_marker));
     }
 
@@ -272,8 +518,9 @@ fn lc<'a>(inner: &Type<'a>, ctx: &ParseContext<'a>) -> IResult<&'a [u8], Mark<'a
         (input, cnt) = parse_u64(input)?;
 
         let dict_marker;
-        (input, dict_marker) =
-            base_inner.decode(ctx.fork(input).with_num_rows(cnt), TypeHeader::Empty)?;
+        (input, dict_marker) = ctx.within("dictionary", input, |ctx| {
+            base_inner.decode(ctx.with_num_rows(cnt), TypeHeader::Empty)
+        })?;
         additional_keys = Some(Box::new(dict_marker));
     }
 
@@ -286,7 +533,9 @@ fn lc<'a>(inner: &Type<'a>, ctx: &ParseContext<'a>) -> IResult<&'a [u8], Mark<'a
         )));
     }
 
-    let (input, indices_marker) = index_type.decode(ctx.fork(input), TypeHeader::Empty)?;
+    let (input, indices_marker) = ctx.within("indices", input, |ctx| {
+        index_type.decode(ctx, TypeHeader::Empty)
+    })?;
     let marker = Mark::LowCardinality(LowCardinality {
         is_nullable: inner.is_nullable(),
         indices: Box::new(indices_marker),
@@ -306,29 +555,30 @@ fn variant<'a>(
 
     let input = ctx.input;
 
-    let (discriminators, mut input) = input.split_at(ctx.num_rows);
+    let (mut input, discriminators) = take(input, ctx.num_rows)?;
     let mut offsets = vec![0; ctx.num_rows];
     let mut row_counts = vec![0; inner.len()];
     for (discriminator, offset) in discriminators.iter().copied().zip(offsets.iter_mut()) {
         if discriminator == NULL_DISCR {
             continue;
         }
-        *offset = row_counts[discriminator as usize];
-        if let Some(count) = row_counts.get_mut(discriminator as usize) {
-            *count += 1;
-        } else {
+        let Some(count) = row_counts.get_mut(discriminator as usize) else {
             return Err(Error::Parse(format!(
                 "Variant: discriminator {discriminator} out of bounds for inner types length {}",
                 inner.len()
             )));
-        }
+        };
+        *offset = *count;
+        *count += 1;
     }
 
     let mut markers = Vec::with_capacity(inner.len());
 
     for ((idx, typ), header) in inner.into_iter().enumerate().zip(headers) {
         let marker;
-        (input, marker) = typ.decode(ctx.fork(input).with_num_rows(row_counts[idx]), header)?;
+        (input, marker) = ctx.within(&idx.to_string(), input, |ctx| {
+            typ.decode(ctx.with_num_rows(row_counts[idx]), header)
+        })?;
         markers.push(marker);
     }
 
@@ -352,8 +602,12 @@ fn map<'a>(
 
     debug!("Map got {n} rows");
 
-    let (input, keys) = key.decode(ctx.fork(input).with_num_rows(n), header.key)?;
-    let (input, values) = value.decode(ctx.fork(input).with_num_rows(n), header.value)?;
+    let (input, keys) = ctx.within("keys", input, |ctx| {
+        key.decode(ctx.with_num_rows(n), header.key)
+    })?;
+    let (input, values) = ctx.within("values", input, |ctx| {
+        value.decode(ctx.with_num_rows(n), header.value)
+    })?;
 
     let marker = Mark::Map(Map {
         offsets,
@@ -366,18 +620,24 @@ fn map<'a>(
 
 fn tuple<'a>(
     inner: Vec<Type<'a>>,
+    names: Vec<&'a str>,
     ctx: &ParseContext<'a>,
     headers: Vec<TypeHeader<'a>>,
 ) -> IResult<&'a [u8], Mark<'a>> {
     let mut markers = Vec::with_capacity(inner.len());
     let mut input = ctx.input;
-    for (typ, header) in inner.into_iter().zip(headers) {
+    for (index, (typ, header)) in inner.into_iter().zip(headers).enumerate() {
         let marker;
-        (input, marker) = typ.decode(ctx.fork(input), header)?;
+        (input, marker) = ctx.within(&(index + 1).to_string(), input, |ctx| {
+            typ.decode(ctx, header)
+        })?;
         markers.push(marker);
     }
 
-    let marker = Tuple { values: markers };
+    let marker = Tuple {
+        values: markers,
+        names,
+    };
     Ok((input, Mark::Tuple(marker)))
 }
 
@@ -401,7 +661,9 @@ fn array<'a>(
         ));
     }
 
-    let (input, inner_block) = inner.decode(ctx.fork(input).with_num_rows(num_rows), header)?;
+    let (input, inner_block) = ctx.within("element", input, |ctx| {
+        inner.decode(ctx.with_num_rows(num_rows), header)
+    })?;
     Ok((
         input,
         Mark::Array(Array {
@@ -411,16 +673,24 @@ fn array<'a>(
     ))
 }
 
-pub(super) fn string<'a>(ctx: &ParseContext<'a>) -> IResult<&'a [u8], Mark<'a>> {
+fn string<'a>(ctx: &ParseContext<'a>) -> IResult<&'a [u8], Mark<'a>> {
+    let (input, strings) = strings(ctx)?;
+    Ok((input, Mark::String(strings)))
+}
+
+/// Reads the strings of the rows as they are: ClickHouse strings are arbitrary bytes, and are
+/// only checked for UTF-8 when read as `&str`.
+pub(super) fn strings<'a>(ctx: &ParseContext<'a>) -> IResult<&'a [u8], Vec<&'a [u8]>> {
     let mut input = ctx.input;
-    let mut strings = Vec::with_capacity(ctx.num_rows);
+    // every string takes at least one byte, so a bogus row count can't blow up the allocation
+    let mut strings = Vec::with_capacity(ctx.num_rows.min(input.len()));
     for _ in 0..ctx.num_rows {
         let s;
         (input, s) = parse_var_str_bytes(input)?;
-        strings.push(unsafe { std::str::from_utf8_unchecked(s) });
+        strings.push(s);
     }
 
-    Ok((input, Mark::String(strings)))
+    Ok((input, strings))
 }
 
 fn nested<'a>(
@@ -437,7 +707,7 @@ fn nested<'a>(
         col_names.push(f.name);
     }
 
-    let tuple_type = bt!(Tuple(inner_types));
+    let tuple_type = bt!(Tuple(inner_types, Box::default()));
     let array_of_tuples = t!(Array(tuple_type));
     let header = TypeHeader::Array(Box::new(TypeHeader::Tuple(headers)));
 
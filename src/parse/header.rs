@@ -1,12 +1,10 @@
-use std::hint::unreachable_unchecked;
-
 use log::debug;
 
 use crate::{
-    Error,
+    ByteExt as _, Error,
     mark::Mark,
     parse::{
-        IResult, block::ParseContext, column::string, consts::LOW_CARDINALITY_VERSION, parse_u64,
+        IResult, block::ParseContext, column::strings, consts::LOW_CARDINALITY_VERSION, parse_u64,
         parse_var_str, parse_var_str_type, parse_varuint, typ::parse_type,
     },
     types::{DynamicHeader, Field, JsonColumnHeader, JsonHeader, MapHeader, Type, TypeHeader},
@@ -34,7 +32,8 @@ pub fn dynamic<'a>(ctx: &ParseContext<'a>) -> IResult<&'a [u8], DynamicHeader<'a
     }
 
     let (mut input, num_types) = parse_varuint::<usize>(input)?;
-    let mut type_names = Vec::with_capacity(num_types + 1);
+    // every type name takes at least one byte
+    let mut type_names = Vec::with_capacity(num_types.min(input.len()) + 1);
     for _ in 0..num_types {
         let t;
         (input, t) = parse_var_str(input)?;
@@ -46,7 +45,7 @@ pub fn dynamic<'a>(ctx: &ParseContext<'a>) -> IResult<&'a [u8], DynamicHeader<'a
 
     debug!("Dynamic type names (sorted): {type_names:?}");
 
-    let mut types = Vec::with_capacity(num_types + 1);
+    let mut types = Vec::with_capacity(type_names.len());
     for name in type_names {
         let typ;
         (_, typ) = parse_type(name.as_bytes())?;
@@ -127,12 +126,13 @@ pub fn json<'a>(ctx: &ParseContext<'a>) -> IResult<&'a [u8], JsonHeader<'a>> {
     debug!("num_paths_old: {num_paths_old}");
 
     let (input, num_paths) = parse_varuint(input)?;
-    let (mut input, paths) = string(&ctx.fork(input).with_num_rows(num_paths))?;
-    let Mark::String(paths) = paths else {
-        unsafe { unreachable_unchecked() };
-    };
+    let (mut input, paths) = strings(&ctx.fork(input).with_num_rows(num_paths))?;
+    let paths = paths
+        .into_iter()
+        .map(|path| path.to_utf8())
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let mut col_headers = Vec::with_capacity(num_paths);
+    let mut col_headers = Vec::with_capacity(paths.len());
 
     for _ in 0..num_paths {
         let header;
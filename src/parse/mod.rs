@@ -9,8 +9,9 @@ use crate::{
 
 pub mod block;
 pub mod column;
-mod consts;
+pub(crate) mod consts;
 pub mod header;
+pub mod rowbinary;
 pub mod typ;
 
 pub type IResult<I, O, E = Error> = Result<(I, O), E>;
@@ -123,8 +124,21 @@ fn parse_var_str_type(input: &[u8]) -> IResult<&[u8], Type> {
     Ok((input, typ))
 }
 
+/// Splits off exactly `len` bytes, failing instead of panicking on a short input.
+#[inline(always)]
+fn take(input: &[u8], len: usize) -> IResult<&[u8], &[u8]> {
+    if input.len() < len {
+        return Err(Error::Length(len));
+    }
+    let (data, rest) = input.split_at(len);
+    Ok((rest, data))
+}
+
 fn parse_offsets(input: &[u8], num_rows: usize) -> IResult<&[u8], Offsets> {
-    let (offsets, input) = input.split_at(num_rows * size_of::<u64>());
+    let len = num_rows
+        .checked_mul(size_of::<u64>())
+        .ok_or_else(|| Error::Overflow(format!("{num_rows} offsets")))?;
+    let (input, offsets) = take(input, len)?;
     let offsets = ByteView::<U64<LittleEndian>>::try_from(offsets)?;
 
     Ok((input, offsets))
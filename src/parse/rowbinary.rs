@@ -0,0 +1,579 @@
+//! Decoding of `RowBinary`, `RowBinaryWithNames` and `RowBinaryWithNamesAndTypes`.
+//!
+//! The rows are transposed into a single Native block, so the result is read like any other
+//! block: parse it with [`crate::parse::block::parse_many`] and use the same rows, values and
+//! typed accessors. Fixed-width values and strings are encoded the same way in both formats and
+//! are copied as they are; `Nullable`, `Array`, `Map`, `Variant` and `LowCardinality` are
+//! rebuilt as null maps, offsets, discriminators and dictionaries.
+
+use std::collections::HashMap;
+
+use crate::{
+    Result,
+    error::Error,
+    parse::{
+        consts::{HAS_ADDITIONAL_KEYS_BIT, TUINT8, TUINT16, TUINT32, TUINT64},
+        parse_var_str, parse_var_str_type, parse_varuint, take,
+    },
+    types::Type,
+    write,
+};
+
+/// The columns of the data: in the header of the `WithNames` variants, or given by the caller.
+#[derive(Debug, Clone, Copy)]
+pub enum Header<'t> {
+    /// Plain `RowBinary`, with no header at all.
+    None {
+        names: &'t [&'t str],
+        types: &'t [Type<'t>],
+    },
+
+    /// `RowBinaryWithNames`: the names are read from the data, the types are given.
+    Names(&'t [Type<'t>]),
+
+    /// `RowBinaryWithNamesAndTypes`, which describes itself.
+    NamesAndTypes,
+}
+
+/// Transposes all rows of `input` into a Native block.
+pub fn to_native(input: &[u8], header: Header<'_>) -> Result<Vec<u8>> {
+    let (mut input, names, types) = match header {
+        Header::None { names, types } => (input, names.to_vec(), types.to_vec()),
+        Header::Names(types) => {
+            let (input, names) = names(input)?;
+            (input, names, types.to_vec())
+        }
+        Header::NamesAndTypes => {
+            let (mut input, names) = names(input)?;
+            let mut types = Vec::with_capacity(names.len());
+            for _ in 0..names.len() {
+                let typ;
+                (input, typ) = parse_var_str_type(input)?;
+                types.push(typ);
+            }
+            (input, names, types)
+        }
+    };
+    if names.len() != types.len() {
+        return Err(Error::ProgrammingError(format!(
+            "{} column names for {} types",
+            names.len(),
+            types.len()
+        )));
+    }
+    if types.is_empty() && !input.is_empty() {
+        return Err(Error::CorruptedData(
+            "RowBinary rows without columns".to_owned(),
+        ));
+    }
+
+    let mut columns = types.iter().map(Column::new).collect::<Result<Vec<_>>>()?;
+    let mut num_rows = 0_u64;
+    while !input.is_empty() {
+        for column in &mut columns {
+            column.read(&mut input)?;
+        }
+        num_rows += 1;
+    }
+
+    let mut out = Vec::new();
+    write::varuint(&mut out, u64::try_from(columns.len())?);
+    write::varuint(&mut out, num_rows);
+    for ((name, typ), column) in names.iter().zip(&types).zip(&columns) {
+        write::var_str(&mut out, name.as_bytes());
+        write::var_str(&mut out, typ.to_string().as_bytes());
+        write::prefix(&mut out, typ);
+        column.write(&mut out)?;
+    }
+    Ok(out)
+}
+
+fn names(input: &[u8]) -> Result<(&[u8], Vec<&str>)> {
+    let (mut input, count) = parse_varuint::<usize>(input)?;
+    // every name takes at least a byte, which bounds a corrupted count
+    let mut names = Vec::with_capacity(count.min(input.len()));
+    for _ in 0..count {
+        let name;
+        (input, name) = parse_var_str(input)?;
+        names.push(name);
+    }
+    Ok((input, names))
+}
+
+/// Splits a single value of a type without nested columns off `input`, `size` bytes or a
+/// string if there is no size.
+fn scalar<'a>(input: &mut &'a [u8], size: Option<usize>) -> Result<&'a [u8]> {
+    let len = match size {
+        Some(size) => size,
+        None => {
+            let (rest, len) = parse_varuint::<usize>(input)?;
+            len.checked_add(input.len() - rest.len())
+                .ok_or_else(|| Error::Overflow(format!("Value of {len} bytes")))?
+        }
+    };
+    let (rest, value) = take(input, len)?;
+    *input = rest;
+    Ok(value)
+}
+
+/// The Native data of a column, built a row at a time.
+enum Column {
+    /// Values a Native column holds the way `RowBinary` writes them, fixed-width ones or
+    /// strings.
+    Plain {
+        size: Option<usize>,
+        data: Vec<u8>,
+    },
+    Nullable {
+        nulls: Vec<u8>,
+        inner: Box<Column>,
+    },
+    Array {
+        offsets: Vec<u64>,
+        inner: Box<Column>,
+    },
+    Map {
+        offsets: Vec<u64>,
+        keys: Box<Column>,
+        values: Box<Column>,
+    },
+    Tuple(Vec<Column>),
+    Variant {
+        discriminators: Vec<u8>,
+        variants: Vec<Column>,
+    },
+    LowCardinality(Box<Dictionary>),
+}
+
+/// A `LowCardinality` column, with the default value first as ClickHouse writes it.
+struct Dictionary {
+    nullable: bool,
+    size: Option<usize>,
+    keys: HashMap<Vec<u8>, u64>,
+    values: Vec<u8>,
+    indices: Vec<u64>,
+}
+
+impl Column {
+    fn new(typ: &Type<'_>) -> Result<Self> {
+        fn array(inner: Column) -> Column {
+            Column::Array {
+                offsets: Vec::new(),
+                inner: Box::new(inner),
+            }
+        }
+
+        let column = match typ {
+            Type::String => Self::Plain {
+                size: None,
+                data: Vec::new(),
+            },
+            Type::Nullable(inner) => Self::Nullable {
+                nulls: Vec::new(),
+                inner: Box::new(Self::new(inner)?),
+            },
+            Type::Array(inner) => array(Self::new(inner)?),
+            Type::Map(key, value) => Self::Map {
+                offsets: Vec::new(),
+                keys: Box::new(Self::new(key)?),
+                values: Box::new(Self::new(value)?),
+            },
+            // zero-width, so array lengths could not be checked against the input
+            Type::Tuple(types, _) if types.is_empty() => return Err(unsupported(typ)),
+            Type::Nested(fields) if fields.is_empty() => return Err(unsupported(typ)),
+            Type::Tuple(types, _) => {
+                Self::Tuple(types.iter().map(Self::new).collect::<Result<_>>()?)
+            }
+            Type::Nested(fields) => array(Self::Tuple(
+                fields
+                    .iter()
+                    .map(|field| Self::new(&field.typ))
+                    .collect::<Result<_>>()?,
+            )),
+            Type::Variant(types) => Self::Variant {
+                discriminators: Vec::new(),
+                variants: types.iter().map(Self::new).collect::<Result<_>>()?,
+            },
+            Type::LowCardinality(inner) => {
+                let nullable = inner.is_nullable();
+                let size = match inner.strip_null() {
+                    Type::String => None,
+                    inner => Some(
+                        inner
+                            .size()
+                            .filter(|&size| size > 0)
+                            .ok_or_else(|| unsupported(typ))?,
+                    ),
+                };
+                let default = match size {
+                    Some(size) => vec![0; size],
+                    None => vec![0],
+                };
+                Self::LowCardinality(Box::new(Dictionary {
+                    nullable,
+                    size,
+                    keys: HashMap::new(),
+                    values: default,
+                    indices: Vec::new(),
+                }))
+            }
+            Type::Point => {
+                Self::Tuple(vec![Self::new(&Type::Float64)?, Self::new(&Type::Float64)?])
+            }
+            Type::Ring | Type::LineString => array(Self::new(&Type::Point)?),
+            Type::Polygon | Type::MultiLineString => array(Self::new(&Type::Ring)?),
+            Type::MultiPolygon => array(Self::new(&Type::Polygon)?),
+            Type::Dynamic | Type::Json | Type::SharedVariant => return Err(unsupported(typ)),
+            typ => Self::Plain {
+                size: Some(
+                    typ.size()
+                        .filter(|&size| size > 0)
+                        .ok_or_else(|| unsupported(typ))?,
+                ),
+                data: Vec::new(),
+            },
+        };
+        Ok(column)
+    }
+
+    /// Reads the value of the next row.
+    fn read(&mut self, input: &mut &[u8]) -> Result<()> {
+        match self {
+            Self::Plain { size, data } => data.extend_from_slice(scalar(input, *size)?),
+            Self::Nullable { nulls, inner } => {
+                let null = scalar(input, Some(1))?[0] != 0;
+                nulls.push(u8::from(null));
+                if null {
+                    inner.push_default();
+                } else {
+                    inner.read(input)?;
+                }
+            }
+            Self::Array { offsets, inner } => {
+                let len = length(input)?;
+                for _ in 0..len {
+                    inner.read(input)?;
+                }
+                push_offset(offsets, len);
+            }
+            Self::Map {
+                offsets,
+                keys,
+                values,
+            } => {
+                let len = length(input)?;
+                for _ in 0..len {
+                    keys.read(input)?;
+                    values.read(input)?;
+                }
+                push_offset(offsets, len);
+            }
+            Self::Tuple(columns) => {
+                for column in columns {
+                    column.read(input)?;
+                }
+            }
+            Self::Variant {
+                discriminators,
+                variants,
+            } => {
+                let discriminator = scalar(input, Some(1))?[0];
+                if discriminator != NULL_DISCRIMINATOR {
+                    let variant =
+                        variants
+                            .get_mut(usize::from(discriminator))
+                            .ok_or_else(|| {
+                                Error::CorruptedData(format!(
+                                    "Variant discriminator {discriminator} out of bounds"
+                                ))
+                            })?;
+                    variant.read(input)?;
+                }
+                discriminators.push(discriminator);
+            }
+            Self::LowCardinality(dictionary) => {
+                let null = dictionary.nullable && scalar(input, Some(1))?[0] != 0;
+                let index = if null {
+                    0
+                } else {
+                    let value = scalar(input, dictionary.size)?;
+                    match dictionary.keys.get(value) {
+                        Some(&index) => index,
+                        None => {
+                            let index = u64::try_from(dictionary.keys.len())? + 1;
+                            dictionary.keys.insert(value.to_vec(), index);
+                            dictionary.values.extend_from_slice(value);
+                            index
+                        }
+                    }
+                };
+                dictionary.indices.push(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the value a NULL row holds in the nested column of a `Nullable`.
+    fn push_default(&mut self) {
+        match self {
+            Self::Plain {
+                size: Some(size),
+                data,
+            } => data.resize(data.len() + *size, 0),
+            Self::Plain { size: None, data } => data.push(0),
+            Self::Nullable { nulls, inner } => {
+                nulls.push(1);
+                inner.push_default();
+            }
+            Self::Array { offsets, .. } | Self::Map { offsets, .. } => push_offset(offsets, 0),
+            Self::Tuple(columns) => columns.iter_mut().for_each(Self::push_default),
+            Self::Variant { discriminators, .. } => discriminators.push(NULL_DISCRIMINATOR),
+            Self::LowCardinality(dictionary) => dictionary.indices.push(0),
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Plain { data, .. } => out.extend_from_slice(data),
+            Self::Nullable { nulls, inner } => {
+                out.extend_from_slice(nulls);
+                inner.write(out)?;
+            }
+            Self::Array { offsets, inner } => {
+                offsets
+                    .iter()
+                    .for_each(|offset| out.extend_from_slice(&offset.to_le_bytes()));
+                inner.write(out)?;
+            }
+            Self::Map {
+                offsets,
+                keys,
+                values,
+            } => {
+                offsets
+                    .iter()
+                    .for_each(|offset| out.extend_from_slice(&offset.to_le_bytes()));
+                keys.write(out)?;
+                values.write(out)?;
+            }
+            Self::Tuple(columns) => {
+                for column in columns {
+                    column.write(out)?;
+                }
+            }
+            Self::Variant {
+                discriminators,
+                variants,
+            } => {
+                out.extend_from_slice(discriminators);
+                for variant in variants {
+                    variant.write(out)?;
+                }
+            }
+            Self::LowCardinality(dictionary) => dictionary.write(out)?,
+        }
+        Ok(())
+    }
+}
+
+impl Dictionary {
+    fn write(&self, out: &mut Vec<u8>) -> Result<()> {
+        // a column without rows has no LowCardinality data at all
+        if self.indices.is_empty() {
+            return Ok(());
+        }
+
+        let size = u64::try_from(self.keys.len())? + 1;
+        let (index_type, width) = match size {
+            ..=0x100 => (TUINT8, 1),
+            0x101..=0x1_0000 => (TUINT16, 2),
+            0x1_0001..=0x1_0000_0000 => (TUINT32, 4),
+            _ => (TUINT64, 8),
+        };
+        out.extend_from_slice(&(index_type | HAS_ADDITIONAL_KEYS_BIT).to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&self.values);
+
+        out.extend_from_slice(&u64::try_from(self.indices.len())?.to_le_bytes());
+        for index in &self.indices {
+            out.extend_from_slice(&index.to_le_bytes()[..width]);
+        }
+        Ok(())
+    }
+}
+
+const NULL_DISCRIMINATOR: u8 = 255;
+
+/// Reads the number of elements of an array or a map. Every element takes at least a byte, as
+/// zero-width types are not supported, so more elements than bytes left are cut short.
+fn length(input: &mut &[u8]) -> Result<usize> {
+    let (rest, len) = parse_varuint(input)?;
+    if len > rest.len() {
+        return Err(Error::Length(len));
+    }
+    *input = rest;
+    Ok(len)
+}
+
+fn push_offset(offsets: &mut Vec<u64>, len: usize) {
+    let last = offsets.last().copied().unwrap_or_default();
+    offsets.push(last + len as u64);
+}
+
+fn unsupported(typ: &Type<'_>) -> Error {
+    Error::NotImplemented(format!("RowBinary decoding of {typ}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{
+        parse::block::parse_many,
+        text::{self, Format},
+    };
+
+    const TYPES: [&str; 8] = [
+        "UInt32",
+        "String",
+        "Nullable(Int64)",
+        "Array(LowCardinality(Nullable(String)))",
+        "Map(String, UInt8)",
+        "Tuple(Int8, String)",
+        "Variant(String, UInt64)",
+        "DateTime64(3, 'UTC')",
+    ];
+
+    /// Two rows of [`TYPES`], as `RowBinary` writes them.
+    fn rows() -> Vec<u8> {
+        let mut rows = Vec::new();
+        for (id, name, number, tag) in [(1_u32, "a\tb", Some(-5_i64), 0_u8), (2, "", None, 1)] {
+            rows.extend_from_slice(&id.to_le_bytes());
+            write::var_str(&mut rows, name.as_bytes());
+            match number {
+                Some(number) => {
+                    rows.push(0);
+                    rows.extend_from_slice(&number.to_le_bytes());
+                }
+                None => rows.push(1),
+            }
+
+            // [x, NULL, x]
+            write::varuint(&mut rows, 3);
+            for element in [Some("x"), None, Some("x")] {
+                rows.push(u8::from(element.is_none()));
+                if let Some(element) = element {
+                    write::var_str(&mut rows, element.as_bytes());
+                }
+            }
+
+            // {'k': id}
+            write::varuint(&mut rows, 1);
+            write::var_str(&mut rows, b"k");
+            rows.push(u8::try_from(id).unwrap_or_default());
+
+            rows.push(7);
+            write::var_str(&mut rows, b"t");
+
+            rows.push(tag);
+            if tag == 0 {
+                write::var_str(&mut rows, b"v");
+            } else {
+                rows.extend_from_slice(&42_u64.to_le_bytes());
+            }
+
+            rows.extend_from_slice(&1_700_000_000_123_i64.to_le_bytes());
+        }
+        rows
+    }
+
+    fn tsv(native: &[u8]) -> TestResult<String> {
+        let blocks = parse_many(native)?;
+        let mut out = Vec::new();
+        text::write_blocks(&mut out, &blocks, Format::TabSeparated, text::Header::Names)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    const EXPECTED: &str = "a\tb\tc\td\te\tf\tg\th\n\
+        1\ta\\tb\t-5\t['x',NULL,'x']\t{'k':1}\t(7,'t')\tv\t2023-11-14 22:13:20.123\n\
+        2\t\t\\N\t['x',NULL,'x']\t{'k':2}\t(7,'t')\t42\t2023-11-14 22:13:20.123\n";
+
+    #[test]
+    fn with_names_and_types() -> TestResult {
+        let mut input = Vec::new();
+        write::varuint(&mut input, 8);
+        for name in ["a", "b", "c", "d", "e", "f", "g", "h"] {
+            write::var_str(&mut input, name.as_bytes());
+        }
+        for typ in TYPES {
+            write::var_str(&mut input, typ.as_bytes());
+        }
+        input.extend(rows());
+
+        assert_eq!(tsv(&to_native(&input, Header::NamesAndTypes)?)?, EXPECTED);
+        Ok(())
+    }
+
+    #[test]
+    fn given_types() -> TestResult {
+        let types = TYPES
+            .iter()
+            .map(|typ| Ok(crate::parse::typ::parse_type(typ.as_bytes())?.1))
+            .collect::<Result<Vec<_>>>()?;
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let header = Header::None {
+            names: &names,
+            types: &types,
+        };
+        assert_eq!(tsv(&to_native(&rows(), header)?)?, EXPECTED);
+
+        let mut input = Vec::new();
+        write::varuint(&mut input, 8);
+        for name in names {
+            write::var_str(&mut input, name.as_bytes());
+        }
+        input.extend(rows());
+        assert_eq!(tsv(&to_native(&input, Header::Names(&types))?)?, EXPECTED);
+
+        // a row cut short
+        let rows = rows();
+        let result = to_native(&rows[..rows.len() - 1], header);
+        assert!(result.is_err_and(|err| err.is_incomplete()));
+
+        // a string length near the end of the address space
+        let names = ["a"];
+        let types = [Type::String];
+        let header = Header::None {
+            names: &names,
+            types: &types,
+        };
+        let mut input = Vec::new();
+        write::varuint(&mut input, u64::MAX - 1);
+        let result = to_native(&input, header);
+        assert!(matches!(result, Err(Error::Overflow(_))));
+
+        // more array elements than bytes left
+        let types = [Type::Array(Box::new(Type::UInt8))];
+        let header = Header::None {
+            names: &names,
+            types: &types,
+        };
+        let mut input = Vec::new();
+        write::varuint(&mut input, 1 << 32);
+        input.push(1);
+        let result = to_native(&input, header);
+        assert!(matches!(result, Err(Error::Length(_))));
+
+        // zero-width elements would not consume the input
+        assert!(crate::parse::typ::parse_type(b"Array(FixedString(0))").is_err());
+        let types = [Type::Array(Box::new(Type::FixedString(0)))];
+        let header = Header::None {
+            names: &names,
+            types: &types,
+        };
+        let result = to_native(&input, header);
+        assert!(matches!(result, Err(Error::NotImplemented(_))));
+        Ok(())
+    }
+}
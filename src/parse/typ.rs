@@ -1,12 +1,15 @@
-use std::str::{FromStr, from_utf8};
+use std::{
+    borrow::Cow,
+    str::{FromStr, from_utf8},
+};
 
 use chrono_tz::{Tz, Tz::UTC};
 use nom::{
     IResult, Parser,
     branch::alt,
-    bytes::complete::{tag, take_while1},
+    bytes::complete::tag,
     character::complete::{alphanumeric1, char, digit1, multispace0, multispace1},
-    combinator::{map, map_res, opt, recognize},
+    combinator::{map, map_res, opt, recognize, verify},
     error::{ErrorKind, FromExternalError as _, ParseError},
     multi::{many0, separated_list1},
     sequence::{delimited, pair, preceded, separated_pair},
@@ -50,10 +53,16 @@ fn parse_decimal_type(input: &[u8]) -> IResult<&[u8], Type> {
     .parse(input)?;
 
     let typ = match precision {
-        0..10 => Type::Decimal32(scale),
-        10..19 => Type::Decimal64(scale),
-        19..39 => Type::Decimal128(scale),
-        39..77 => Type::Decimal256(scale),
+        _ if scale > precision => {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                input,
+                ErrorKind::Fail,
+            )));
+        }
+        1..10 => Type::Decimal32(precision, scale),
+        10..19 => Type::Decimal64(precision, scale),
+        19..39 => Type::Decimal128(precision, scale),
+        39..77 => Type::Decimal256(precision, scale),
         _ => {
             return Err(nom::Err::Error(nom::error::Error::new(
                 input,
@@ -75,7 +84,10 @@ fn parse_fixed_string(input: &[u8]) -> IResult<&[u8], Type> {
             tag("FixedString"),
             delimited(
                 ws(char('(')),
-                map_res(digit1, |s: &[u8]| parse_num::<usize>(s)),
+                // ClickHouse has no zero-length FixedString either
+                verify(map_res(digit1, |s: &[u8]| parse_num::<usize>(s)), |&size| {
+                    size > 0
+                }),
                 ws(char(')')),
             ),
         ),
@@ -121,39 +133,87 @@ fn parse_inet_primitives(input: &[u8]) -> IResult<&[u8], Type> {
     .parse(input)
 }
 
+fn parse_timezone(input: &[u8]) -> IResult<&[u8], Tz> {
+    let (rest, tz) = quoted_str(input)?;
+    let tz = Tz::from_str(&tz)
+        .map_err(|_| nom::Err::Error(nom::error::Error::new(input, ErrorKind::Fail)))?;
+    Ok((rest, tz))
+}
+
 fn parse_datetime64(input: &[u8]) -> IResult<&[u8], Type> {
-    let (input, (precision, tz)) = preceded(
-        tag("DateTime64"),
-        delimited(
-            ws(char('(')),
-            separated_pair(
-                map_res(digit1, parse_num::<u8>),
-                ws(char(',')),
-                delimited(ws(char('\'')), take_while1(|c| c != b'\''), ws(char('\''))),
+    map(
+        preceded(
+            tag("DateTime64"),
+            delimited(
+                ws(char('(')),
+                pair(
+                    // nanoseconds at most, as in ClickHouse
+                    verify(map_res(digit1, parse_num::<u8>), |&precision| {
+                        precision <= 9
+                    }),
+                    opt(preceded(ws(char(',')), parse_timezone)),
+                ),
+                ws(char(')')),
             ),
-            ws(char(')')),
         ),
+        |(precision, tz)| Type::DateTime64(precision, tz.unwrap_or(UTC)),
     )
-    .parse(input)?;
-
-    let tz = unsafe { std::str::from_utf8_unchecked(tz) };
-
-    let tz = Tz::from_str(tz)
-        .map_err(|_| nom::Err::Error(nom::error::Error::new(input, ErrorKind::Fail)))?;
-    Ok((input, Type::DateTime64(precision, tz)))
+    .parse(input)
 }
 
-fn parse_tuple(input: &[u8]) -> IResult<&[u8], Type> {
+fn parse_datetime(input: &[u8]) -> IResult<&[u8], Type<'_>> {
     map(
+        preceded(
+            tag("DateTime"),
+            delimited(ws(char('(')), parse_timezone, ws(char(')'))),
+        ),
+        Type::DateTime,
+    )
+    .parse(input)
+}
+
+/// A tuple, either unnamed or with a name for every element.
+fn parse_tuple(input: &[u8]) -> IResult<&[u8], Type<'_>> {
+    map_res(
         preceded(
             tag("Tuple"),
             delimited(
                 ws(char('(')),
-                separated_list1(ws(char(',')), parse_type),
+                separated_list1(
+                    ws(char(',')),
+                    alt((
+                        map(
+                            separated_pair(identifier, multispace1, parse_any),
+                            |(name, typ)| (Some(name), typ),
+                        ),
+                        map(parse_any, |typ| (None, typ)),
+                    )),
+                ),
                 ws(char(')')),
             ),
         ),
-        Type::Tuple,
+        |elements| {
+            let (names, types): (Vec<_>, Vec<_>) = elements.into_iter().unzip();
+            let names = names.into_iter().flatten().collect::<Vec<_>>();
+            if names.is_empty() || names.len() == types.len() {
+                Ok(Type::Tuple(types, names.into()))
+            } else {
+                Err(ErrorKind::Verify)
+            }
+        },
+    )
+    .parse(input)
+}
+
+/// An element name of a named tuple or a `Nested` field name.
+fn identifier(input: &[u8]) -> IResult<&[u8], &str> {
+    map(
+        recognize(pair(
+            alt((alphanumeric1, tag("_"))),
+            many0(alt((alphanumeric1, tag("_")))),
+        )),
+        // only ASCII letters, digits and underscores were recognized
+        |name| unsafe { std::str::from_utf8_unchecked(name) },
     )
     .parse(input)
 }
@@ -162,6 +222,7 @@ fn parse_date_primitives(input: &[u8]) -> IResult<&[u8], Type> {
     alt((
         parse_datetime64,
         map(tag("DateTime64"), |_| Type::DateTime64(3, UTC)),
+        parse_datetime,
         map(tag("DateTime"), |_| Type::DateTime(UTC)),
         map(tag("Date32"), |_| Type::Date32),
         map(tag("Date"), |_| Type::Date),
@@ -207,7 +268,7 @@ fn parse_nullable(input: &[u8]) -> IResult<&[u8], Type> {
     map(
         preceded(
             tag("Nullable"),
-            delimited(ws(char('(')), parse_type, ws(char(')'))),
+            delimited(ws(char('(')), parse_any, ws(char(')'))),
         ),
         |inner| Type::Nullable(Box::new(inner)),
     )
@@ -220,7 +281,7 @@ fn parse_map(input: &[u8]) -> IResult<&[u8], Type> {
             tag("Map"),
             delimited(
                 ws(char('(')),
-                separated_pair(parse_type, ws(char(',')), parse_type),
+                separated_pair(parse_any, ws(char(',')), parse_any),
                 ws(char(')')),
             ),
         ),
@@ -233,7 +294,7 @@ fn parse_array(input: &[u8]) -> IResult<&[u8], Type> {
     map(
         preceded(
             tag("Array"),
-            delimited(ws(char('(')), parse_type, ws(char(')'))),
+            delimited(ws(char('(')), parse_any, ws(char(')'))),
         ),
         |inner| Type::Array(Box::new(inner)),
     )
@@ -246,7 +307,7 @@ fn parse_variant(input: &[u8]) -> IResult<&[u8], Type> {
             tag("Variant"),
             delimited(
                 ws(char('(')),
-                separated_list1(ws(char(',')), parse_type),
+                separated_list1(ws(char(',')), parse_any),
                 ws(char(')')),
             ),
         ),
@@ -259,7 +320,7 @@ fn parse_lowcardinality(input: &[u8]) -> IResult<&[u8], Type> {
     map(
         preceded(
             tag("LowCardinality"),
-            delimited(ws(char('(')), parse_type, ws(char(')'))),
+            delimited(ws(char('(')), parse_any, ws(char(')'))),
         ),
         |inner| Type::LowCardinality(Box::new(inner)),
     )
@@ -273,11 +334,7 @@ fn parse_nested(input: &[u8]) -> IResult<&[u8], Type> {
             ws(char('(')),
             separated_list1(
                 ws(char(',')),
-                separated_pair(
-                    recognize(pair(alphanumeric1, many0(alt((alphanumeric1, tag("_")))))),
-                    multispace1,
-                    parse_type,
-                ),
+                separated_pair(identifier, multispace1, parse_any),
             ),
             ws(char(')')),
         ),
@@ -286,15 +343,62 @@ fn parse_nested(input: &[u8]) -> IResult<&[u8], Type> {
 
     let fields = pairs
         .into_iter()
-        .map(|(name, typ)| Field {
-            name: unsafe { std::str::from_utf8_unchecked(name) },
-            typ,
-        })
+        .map(|(name, typ)| Field { name, typ })
         .collect::<Vec<_>>();
 
     Ok((input, Type::Nested(fields)))
 }
 
+/// A single-quoted string, with its backslash escapes resolved. Only a string with escapes is
+/// copied.
+fn quoted_str(input: &[u8]) -> IResult<&[u8], Cow<'_, str>> {
+    let fail = |input| nom::Err::Error(nom::error::Error::new(input, ErrorKind::Fail));
+
+    let (input, _) = ws(char('\'')).parse(input)?;
+    let mut len = 0;
+    let mut escaped = false;
+    loop {
+        match input.get(len) {
+            None => return Err(fail(input)),
+            Some(b'\'') => break,
+            Some(b'\\') => {
+                escaped = true;
+                len += 2;
+            }
+            Some(_) => len += 1,
+        }
+    }
+    let (text, rest) = input.split_at(len);
+    let (rest, _) = ws(char('\'')).parse(rest)?;
+
+    if !escaped {
+        return Ok((
+            rest,
+            Cow::Borrowed(from_utf8(text).map_err(|_| fail(input))?),
+        ));
+    }
+    let mut unescaped = Vec::with_capacity(text.len());
+    let mut bytes = text.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte != b'\\' {
+            unescaped.push(byte);
+            continue;
+        }
+        unescaped.push(match bytes.next() {
+            Some(b't') => b'\t',
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b'0') => b'\0',
+            Some(b'b') => b'\x08',
+            Some(b'f') => b'\x0c',
+            Some(&other) => other,
+            None => return Err(fail(input)),
+        });
+    }
+    let unescaped = String::from_utf8(unescaped).map_err(|_| fail(input))?;
+    Ok((rest, Cow::Owned(unescaped)))
+}
+
 fn parse_enum8(input: &[u8]) -> IResult<&[u8], Type> {
     map(
         preceded(
@@ -304,7 +408,7 @@ fn parse_enum8(input: &[u8]) -> IResult<&[u8], Type> {
                 separated_list1(
                     ws(char(',')),
                     separated_pair(
-                        delimited(ws(char('\'')), take_while1(|c| c != b'\''), ws(char('\''))),
+                        quoted_str,
                         ws(char('=')),
                         map_res(recognize(pair(opt(char('-')), digit1)), parse_num::<i8>),
                     ),
@@ -315,8 +419,7 @@ fn parse_enum8(input: &[u8]) -> IResult<&[u8], Type> {
         |pairs| {
             let mut enum_values = Vec::new();
             for (name, value) in pairs {
-                let name_str = unsafe { std::str::from_utf8_unchecked(name) };
-                enum_values.push((name_str, value));
+                enum_values.push((name, value));
             }
             Type::Enum8(enum_values)
         },
@@ -333,9 +436,9 @@ fn parse_enum16(input: &[u8]) -> IResult<&[u8], Type> {
                 separated_list1(
                     ws(char(',')),
                     separated_pair(
-                        delimited(ws(char('\'')), take_while1(|c| c != b'\''), ws(char('\''))),
+                        quoted_str,
                         ws(char('=')),
-                        map_res(digit1, parse_num::<i16>),
+                        map_res(recognize(pair(opt(char('-')), digit1)), parse_num::<i16>),
                     ),
                 ),
                 ws(char(')')),
@@ -344,8 +447,7 @@ fn parse_enum16(input: &[u8]) -> IResult<&[u8], Type> {
         |pairs| {
             let mut enum_values = Vec::new();
             for (name, value) in pairs {
-                let name_str = unsafe { std::str::from_utf8_unchecked(name) };
-                enum_values.push((name_str, value));
+                enum_values.push((name, value));
             }
             Type::Enum16(enum_values)
         },
@@ -353,7 +455,39 @@ fn parse_enum16(input: &[u8]) -> IResult<&[u8], Type> {
     .parse(input)
 }
 
+/// Types nested deeper than this are rejected: the parser and the decoder are both recursive,
+/// and a hostile type name must not be able to overflow the stack.
+const MAX_NESTING: usize = 64;
+
 pub fn parse_type(input: &[u8]) -> IResult<&[u8], Type> {
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut bytes = input.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            // an escaped character never ends a quoted string, as in `quoted_str`
+            b'\\' if quoted => {
+                bytes.next();
+            }
+            b'\'' => quoted = !quoted,
+            b'(' if !quoted => {
+                depth += 1;
+                if depth > MAX_NESTING {
+                    return Err(nom::Err::Failure(nom::error::Error::new(
+                        input,
+                        ErrorKind::TooLarge,
+                    )));
+                }
+            }
+            b')' if !quoted => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    parse_any(input)
+}
+
+fn parse_any(input: &[u8]) -> IResult<&[u8], Type<'_>> {
     alt((
         parse_lowcardinality,
         parse_nullable,
@@ -373,6 +507,8 @@ pub fn parse_type(input: &[u8]) -> IResult<&[u8], Type> {
 
 #[cfg(test)]
 mod tests {
+    use testresult::TestResult;
+
     use super::*;
     #[test]
     fn decimal() {
@@ -464,14 +600,95 @@ mod tests {
         let (_, typ) = parse_type(input).unwrap();
         assert_eq!(
             typ,
-            Type::Enum8(vec![("Red", 1), ("Green", 2), ("Blue", 3)])
+            Type::Enum8(vec![
+                ("Red".into(), 1),
+                ("Green".into(), 2),
+                ("Blue".into(), 3)
+            ])
         );
     }
 
+    #[test]
+    fn display_round_trips() -> TestResult {
+        let mut names = vec![
+            "Decimal(5, 2)".to_owned(),
+            "Decimal(20, 0)".to_owned(),
+            "Decimal(40, 40)".to_owned(),
+            "DateTime('Europe/Amsterdam')".to_owned(),
+            "DateTime64(6)".to_owned(),
+            "Enum16('low' = -300, 'high' = 300)".to_owned(),
+            "Array(Nested(id UInt64, tags Array(LowCardinality(String))))".to_owned(),
+            "Tuple(id UInt64, point Tuple(x Float64, y Float64), name Nullable(String))".to_owned(),
+        ];
+        for (_, buf) in crate::common::load_testdata()? {
+            for block in crate::parse::block::parse_many(&buf)? {
+                names.extend(block.types.iter().map(ToString::to_string));
+            }
+        }
+
+        for name in &names {
+            let typ = Type::from_bytes(name.as_bytes())?;
+            let text = typ.to_string();
+            assert_eq!(
+                Type::from_bytes(text.as_bytes())?,
+                typ,
+                "{name} written as {text}"
+            );
+        }
+
+        let typ = Type::Enum8(vec![
+            ("it's".into(), 1),
+            ("back\\slash".into(), 2),
+            ("tab\tnew line\n".into(), -3),
+        ]);
+        let text = typ.to_string();
+        assert_eq!(
+            text,
+            r"Enum8('it\'s' = 1, 'back\\slash' = 2, 'tab\tnew line\n' = -3)"
+        );
+        assert_eq!(Type::from_bytes(text.as_bytes())?, typ);
+
+        assert_eq!(Type::from_bytes(b"Decimal(5, 2)")?, Type::Decimal32(5, 2));
+        assert_eq!(Type::Decimal32(5, 2).to_string(), "Decimal(5, 2)");
+        assert!(Type::from_bytes(b"Decimal(5, 6)").is_err());
+        assert!(Type::from_bytes(b"DateTime64(10)").is_err());
+        assert!(Type::from_bytes(b"DateTime64(64, 'UTC')").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn named_tuple() -> TestResult {
+        let typ = Type::from_bytes(b"Tuple(a UInt8, b_2 Nullable(String))")?;
+        assert_eq!(
+            typ,
+            Type::Tuple(
+                vec![Type::UInt8, Type::Nullable(Box::new(Type::String))],
+                ["a", "b_2"].into()
+            )
+        );
+        assert_eq!(typ.to_string(), "Tuple(a UInt8, b_2 Nullable(String))");
+
+        let typ = Type::from_bytes(b"Tuple(UInt8, Nullable(String))")?;
+        assert_eq!(
+            typ,
+            Type::Tuple(
+                vec![Type::UInt8, Type::Nullable(Box::new(Type::String))],
+                Box::default()
+            )
+        );
+        assert!(Type::from_bytes(b"Tuple(a UInt8, String)").is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn enum16() {
         let input = b"Enum16('Foo' = 1000, 'Bar' = 2000)";
         let (_, typ) = parse_type(input).unwrap();
-        assert_eq!(typ, Type::Enum16(vec![("Foo", 1000), ("Bar", 2000)]));
+        assert_eq!(
+            typ,
+            Type::Enum16(vec![("Foo".into(), 1000), ("Bar".into(), 2000)])
+        );
     }
 }
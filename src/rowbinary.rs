@@ -0,0 +1,1563 @@
+//! Encoding rows as `RowBinary`, e.g. for `INSERT ... FORMAT RowBinary`.
+//!
+//! Every value is validated against the declared type of its column before it is written: an
+//! integer must fit the column, a float must not overflow it, a decimal must be representable
+//! with the column scale, a string must name a variant of an enum, and so on. `LowCardinality`
+//! columns take plain values. A value goes into the first `Variant` member of its own type, or
+//! else into the first member it fits, and into `Dynamic` with its own type written before it.
+//! Decoding is done by [`crate::parse::rowbinary`].
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    hash::BuildHasher,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use half::bf16;
+use uuid::Uuid;
+
+use crate::{
+    Result, conv,
+    error::Error,
+    text::{self, Item, Unscaled},
+    types::{OffsetIndexPair as _, Type},
+    value::{NestedIterator, OwnedValue, Value},
+    write,
+};
+
+/// Discriminator of a NULL `Variant` value.
+const NULL_DISCRIMINATOR: u8 = 255;
+
+/// Rows of the given column types, encoded one by one.
+pub struct Writer<'t> {
+    types: &'t [Type<'t>],
+    out: Vec<u8>,
+}
+
+impl<'t> Writer<'t> {
+    /// Plain `RowBinary`, without a header.
+    pub fn new(types: &'t [Type<'t>]) -> Self {
+        Self {
+            types,
+            out: Vec::new(),
+        }
+    }
+
+    /// `RowBinaryWithNamesAndTypes`: the header with the names and types is written first.
+    pub fn with_names_and_types(names: &[&str], types: &'t [Type<'t>]) -> Result<Self> {
+        if names.len() != types.len() {
+            return Err(Error::ProgrammingError(format!(
+                "{} column names for {} types",
+                names.len(),
+                types.len()
+            )));
+        }
+
+        let mut out = Vec::new();
+        write::varuint(&mut out, u64::try_from(names.len())?);
+        for name in names {
+            write::var_str(&mut out, name.as_bytes());
+        }
+        for typ in types {
+            write::var_str(&mut out, typ.to_string().as_bytes());
+        }
+        Ok(Self { types, out })
+    }
+
+    /// Encodes a row. Nothing is written if any of its values does not fit its column.
+    pub fn write_row<R: Row + ?Sized>(&mut self, row: &R) -> Result<()> {
+        let start = self.out.len();
+        let result = row.encode_row(self.types, &mut self.out);
+        if result.is_err() {
+            self.out.truncate(start);
+        }
+        result
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.out
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.out
+    }
+}
+
+/// A row: a value per column.
+pub trait Row {
+    fn encode_row(&self, types: &[Type<'_>], out: &mut Vec<u8>) -> Result<()>;
+}
+
+impl<T: Encode> Row for [T] {
+    fn encode_row(&self, types: &[Type<'_>], out: &mut Vec<u8>) -> Result<()> {
+        if self.len() != types.len() {
+            return Err(columns_mismatch(self.len(), types.len()));
+        }
+        self.iter()
+            .zip(types)
+            .try_for_each(|(value, typ)| encode(value, typ, out))
+    }
+}
+
+impl<T: Encode> Row for Vec<T> {
+    fn encode_row(&self, types: &[Type<'_>], out: &mut Vec<u8>) -> Result<()> {
+        self.as_slice().encode_row(types, out)
+    }
+}
+
+impl<T: Encode, const N: usize> Row for [T; N] {
+    fn encode_row(&self, types: &[Type<'_>], out: &mut Vec<u8>) -> Result<()> {
+        self.as_slice().encode_row(types, out)
+    }
+}
+
+fn columns_mismatch(values: usize, columns: usize) -> Error {
+    Error::ProgrammingError(format!("{values} values for {columns} columns"))
+}
+
+/// A value that can be written into a column.
+pub trait Encode {
+    /// Writes the value as a value of `typ`, which is never `Nullable`, `LowCardinality`,
+    /// `Variant`, `Dynamic` or a type stored as another one, e.g. `Point`: these are resolved by
+    /// [`encode`].
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()>;
+
+    /// The type of the value on its own, if it can be told, e.g. not for NULL.
+    fn own_type(&self) -> Option<Type<'static>>;
+
+    fn is_null(&self) -> bool {
+        false
+    }
+}
+
+/// Writes `value` as a value of a column of type `typ`.
+pub fn encode<E: Encode + ?Sized>(value: &E, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    match typ {
+        Type::Nullable(inner) => {
+            if value.is_null() {
+                out.push(1);
+                return Ok(());
+            }
+            out.push(0);
+            encode(value, inner, out)
+        }
+        Type::LowCardinality(inner) => encode(value, inner, out),
+        Type::Variant(types) => variant(value, types, out),
+        Type::Dynamic => {
+            if value.is_null() {
+                out.push(0x00);
+                return Ok(());
+            }
+            let own = value
+                .own_type()
+                .ok_or_else(|| mismatch(describe(value), typ))?;
+            binary_type(&own, out)?;
+            encode(value, &own, out)
+        }
+        Type::Point => encode(
+            value,
+            &Type::Tuple(vec![Type::Float64, Type::Float64], Box::default()),
+            out,
+        ),
+        Type::Ring | Type::LineString => encode(value, &Type::Array(Box::new(Type::Point)), out),
+        Type::Polygon | Type::MultiLineString => {
+            encode(value, &Type::Array(Box::new(Type::Ring)), out)
+        }
+        Type::MultiPolygon => encode(value, &Type::Array(Box::new(Type::Polygon)), out),
+        Type::Nested(fields) => {
+            let fields = fields.iter().map(|field| field.typ.clone()).collect();
+            encode(
+                value,
+                &Type::Array(Box::new(Type::Tuple(fields, Box::default()))),
+                out,
+            )
+        }
+        _ if value.is_null() => Err(mismatch("NULL", typ)),
+        Type::Json | Type::SharedVariant => Err(Error::NotImplemented(format!(
+            "RowBinary encoding of {typ}"
+        ))),
+        _ => value.encode_value(typ, out),
+    }
+}
+
+/// Writes the discriminator of the member the value goes into, then the value.
+fn variant<E: Encode + ?Sized>(value: &E, types: &[Type<'_>], out: &mut Vec<u8>) -> Result<()> {
+    if value.is_null() {
+        out.push(NULL_DISCRIMINATOR);
+        return Ok(());
+    }
+
+    let own = value.own_type();
+    let exact = own
+        .as_ref()
+        .and_then(|own| types.iter().position(|typ| typ == own));
+    let start = out.len();
+    for index in exact.into_iter().chain(0..types.len()) {
+        out.push(u8::try_from(index)?);
+        if encode(value, &types[index], out).is_ok() {
+            return Ok(());
+        }
+        out.truncate(start);
+    }
+    Err(mismatch(describe(value), &Type::Variant(types.to_vec())))
+}
+
+/// Writes `typ` in the binary encoding of types `Dynamic` values are prefixed with.
+fn binary_type(typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    fn enum_variants<V: Copy>(
+        out: &mut Vec<u8>,
+        variants: &[(Cow<'_, str>, V)],
+        bytes: impl Fn(V) -> Vec<u8>,
+    ) -> Result<()> {
+        write::varuint(out, u64::try_from(variants.len())?);
+        for (name, value) in variants {
+            write::var_str(out, name.as_bytes());
+            out.extend(bytes(*value));
+        }
+        Ok(())
+    }
+
+    fn types(out: &mut Vec<u8>, types: &[Type<'_>]) -> Result<()> {
+        write::varuint(out, u64::try_from(types.len())?);
+        types.iter().try_for_each(|typ| binary_type(typ, out))
+    }
+
+    match typ {
+        Type::UInt8 => out.push(0x01),
+        Type::UInt16 => out.push(0x02),
+        Type::UInt32 => out.push(0x03),
+        Type::UInt64 => out.push(0x04),
+        Type::UInt128 => out.push(0x05),
+        Type::UInt256 => out.push(0x06),
+        Type::Int8 => out.push(0x07),
+        Type::Int16 => out.push(0x08),
+        Type::Int32 => out.push(0x09),
+        Type::Int64 => out.push(0x0A),
+        Type::Int128 => out.push(0x0B),
+        Type::Int256 => out.push(0x0C),
+        Type::Float32 => out.push(0x0D),
+        Type::Float64 => out.push(0x0E),
+        Type::Date => out.push(0x0F),
+        Type::Date32 => out.push(0x10),
+        Type::DateTime(tz) => {
+            out.push(0x12);
+            write::var_str(out, tz.name().as_bytes());
+        }
+        Type::DateTime64(precision, tz) => {
+            out.extend([0x14, *precision]);
+            write::var_str(out, tz.name().as_bytes());
+        }
+        Type::String => out.push(0x15),
+        Type::FixedString(size) => {
+            out.push(0x16);
+            write::varuint(out, u64::try_from(*size)?);
+        }
+        Type::Enum8(variants) => {
+            out.push(0x17);
+            enum_variants(out, variants, |value| value.to_le_bytes().to_vec())?;
+        }
+        Type::Enum16(variants) => {
+            out.push(0x18);
+            enum_variants(out, variants, |value| value.to_le_bytes().to_vec())?;
+        }
+        Type::Decimal32(precision, scale) => out.extend([0x19, *precision, *scale]),
+        Type::Decimal64(precision, scale) => out.extend([0x1A, *precision, *scale]),
+        Type::Decimal128(precision, scale) => out.extend([0x1B, *precision, *scale]),
+        Type::Decimal256(precision, scale) => out.extend([0x1C, *precision, *scale]),
+        Type::Uuid => out.push(0x1D),
+        Type::Array(inner) => {
+            out.push(0x1E);
+            binary_type(inner, out)?;
+        }
+        Type::Tuple(members, names) if names.is_empty() => {
+            out.push(0x1F);
+            types(out, members)?;
+        }
+        Type::Tuple(members, names) => {
+            out.push(0x20);
+            write::varuint(out, u64::try_from(members.len())?);
+            for (name, member) in names.iter().zip(members) {
+                write::var_str(out, name.as_bytes());
+                binary_type(member, out)?;
+            }
+        }
+        Type::Nullable(inner) => {
+            out.push(0x23);
+            binary_type(inner, out)?;
+        }
+        Type::LowCardinality(inner) => {
+            out.push(0x26);
+            binary_type(inner, out)?;
+        }
+        Type::Map(key, value) => {
+            out.push(0x27);
+            binary_type(key, out)?;
+            binary_type(value, out)?;
+        }
+        Type::Ipv4 => out.push(0x28),
+        Type::Ipv6 => out.push(0x29),
+        Type::Variant(members) => {
+            out.push(0x2A);
+            types(out, members)?;
+        }
+        // the default maximum number of types
+        Type::Dynamic => out.extend([0x2B, 32]),
+        Type::Point
+        | Type::Ring
+        | Type::Polygon
+        | Type::MultiPolygon
+        | Type::LineString
+        | Type::MultiLineString => {
+            out.push(0x2C);
+            write::var_str(out, typ.to_string().as_bytes());
+        }
+        Type::Bool => out.push(0x2D),
+        Type::Nested(fields) => {
+            out.push(0x2F);
+            write::varuint(out, u64::try_from(fields.len())?);
+            for field in fields {
+                write::var_str(out, field.name.as_bytes());
+                binary_type(&field.typ, out)?;
+            }
+        }
+        Type::Json => out.push(0x30),
+        Type::BFloat16 => out.push(0x31),
+        Type::SharedVariant => {
+            return Err(Error::NotImplemented(format!("binary encoding of {typ}")));
+        }
+    }
+    Ok(())
+}
+
+fn mismatch(value: impl Display, typ: &Type<'_>) -> Error {
+    Error::Encode(value.to_string(), typ.to_string())
+}
+
+fn describe<E: Encode + ?Sized>(value: &E) -> String {
+    value
+        .own_type()
+        .map_or_else(|| "value".to_owned(), |typ| format!("{typ} value"))
+}
+
+/// Writes an integer into an integer, `Bool`, decimal or enum column.
+fn integer(value: i128, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    macro_rules! fit {
+        ($t:ty) => {
+            out.extend_from_slice(
+                &<$t>::try_from(value)
+                    .map_err(|_| mismatch(value, typ))?
+                    .to_le_bytes(),
+            )
+        };
+    }
+
+    match typ {
+        Type::Int8 => fit!(i8),
+        Type::Int16 => fit!(i16),
+        Type::Int32 => fit!(i32),
+        Type::Int64 => fit!(i64),
+        Type::Int128 => out.extend_from_slice(&value.to_le_bytes()),
+        Type::UInt8 => fit!(u8),
+        Type::UInt16 => fit!(u16),
+        Type::UInt32 => fit!(u32),
+        Type::UInt64 => fit!(u64),
+        Type::UInt128 => fit!(u128),
+        Type::Int256 => {
+            out.extend_from_slice(&value.to_le_bytes());
+            out.extend([if value < 0 { 0xFF } else { 0 }; 16]);
+        }
+        Type::UInt256 => {
+            fit!(u128);
+            out.extend([0; 16]);
+        }
+        Type::Bool => match value {
+            0 => out.push(0),
+            1 => out.push(1),
+            _ => return Err(mismatch(value, typ)),
+        },
+        Type::Enum8(variants) => {
+            let value = i8::try_from(value).map_err(|_| mismatch(value, typ))?;
+            if !variants.iter().any(|&(_, variant)| variant == value) {
+                return Err(mismatch(value, typ));
+            }
+            out.extend(value.to_le_bytes());
+        }
+        Type::Enum16(variants) => {
+            let value = i16::try_from(value).map_err(|_| mismatch(value, typ))?;
+            if !variants.iter().any(|&(_, variant)| variant == value) {
+                return Err(mismatch(value, typ));
+            }
+            out.extend(value.to_le_bytes());
+        }
+        Type::Decimal32(..) | Type::Decimal64(..) | Type::Decimal128(..) | Type::Decimal256(..) => {
+            decimal(value, 0, typ, out)?;
+        }
+        _ => return Err(mismatch(value, typ)),
+    }
+    Ok(())
+}
+
+/// Writes an unsigned 128-bit integer, which may not fit [`integer`].
+fn unsigned(value: u128, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    if let Ok(value) = i128::try_from(value) {
+        return integer(value, typ, out);
+    }
+    match typ {
+        Type::UInt128 => out.extend_from_slice(&value.to_le_bytes()),
+        Type::UInt256 => {
+            out.extend_from_slice(&value.to_le_bytes());
+            out.extend([0; 16]);
+        }
+        _ => return Err(mismatch(value, typ)),
+    }
+    Ok(())
+}
+
+/// Writes a 256-bit little-endian integer, copied into 256-bit columns of its signedness and
+/// narrowed for any other column.
+fn wide(bytes: &[u8; 32], signed: bool, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    let negative = signed && bytes[31] & 0x80 != 0;
+    match typ {
+        Type::Int256 if signed || bytes[31] & 0x80 == 0 => {
+            out.extend_from_slice(bytes);
+            return Ok(());
+        }
+        Type::UInt256 if !negative => {
+            out.extend_from_slice(bytes);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let (low, high) = bytes.split_at(16);
+    let low = u128::from_le_bytes(low.try_into().unwrap_or_default());
+    let extension = if negative { 0xFF } else { 0 };
+    if high.iter().any(|&byte| byte != extension) {
+        return Err(mismatch(text::wide(bytes, signed), typ));
+    }
+    if negative {
+        // two's complement of a value that fits 128 bits
+        let value = low.cast_signed();
+        if value >= 0 {
+            return Err(mismatch(text::wide(bytes, signed), typ));
+        }
+        integer(value, typ, out)
+    } else {
+        unsigned(low, typ, out)
+    }
+}
+
+/// Writes a float, which must not overflow a narrower column. NaN and infinities are kept.
+fn float(value: f64, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    let overflows = |narrow_infinite: bool| value.is_finite() && narrow_infinite;
+    match typ {
+        Type::Float32 => {
+            #[expect(clippy::cast_possible_truncation)]
+            let narrow = value as f32;
+            if overflows(narrow.is_infinite()) {
+                return Err(mismatch(value, typ));
+            }
+            out.extend(narrow.to_le_bytes());
+        }
+        Type::Float64 => out.extend(value.to_le_bytes()),
+        Type::BFloat16 => {
+            let narrow = bf16::from_f64(value);
+            if overflows(narrow.is_infinite()) {
+                return Err(mismatch(value, typ));
+            }
+            out.extend(narrow.to_le_bytes());
+        }
+        _ => return Err(mismatch(value, typ)),
+    }
+    Ok(())
+}
+
+/// Writes bytes into a string column, or the variant of an enum they name.
+fn string(bytes: &[u8], typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    let unfit = || mismatch(format!("{:?}", String::from_utf8_lossy(bytes)), typ);
+    match typ {
+        Type::String => write::var_str(out, bytes),
+        Type::FixedString(size) => {
+            let padding = size.checked_sub(bytes.len()).ok_or_else(unfit)?;
+            out.extend_from_slice(bytes);
+            out.resize(out.len() + padding, 0);
+        }
+        Type::Enum8(variants) => {
+            let &(_, value) = variants
+                .iter()
+                .find(|(name, _)| name.as_bytes() == bytes)
+                .ok_or_else(unfit)?;
+            out.extend(value.to_le_bytes());
+        }
+        Type::Enum16(variants) => {
+            let &(_, value) = variants
+                .iter()
+                .find(|(name, _)| name.as_bytes() == bytes)
+                .ok_or_else(unfit)?;
+            out.extend(value.to_le_bytes());
+        }
+        _ => return Err(unfit()),
+    }
+    Ok(())
+}
+
+/// Writes the decimal `unscaled * 10^-scale`, rescaled to the column scale, which must not
+/// drop any digit, and checked against the column precision.
+fn decimal(unscaled: i128, scale: u8, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    let unfit = || {
+        let mut digits = String::new();
+        let _ = text::decimal(&mut digits, &unscaled.to_string(), scale);
+        mismatch(digits, typ)
+    };
+
+    let (Type::Decimal32(precision, target)
+    | Type::Decimal64(precision, target)
+    | Type::Decimal128(precision, target)
+    | Type::Decimal256(precision, target)) = *typ
+    else {
+        return Err(unfit());
+    };
+    let value = if target >= scale {
+        10_i128
+            .checked_pow(u32::from(target - scale))
+            .and_then(|factor| unscaled.checked_mul(factor))
+    } else {
+        match 10_i128.checked_pow(u32::from(scale - target)) {
+            Some(factor) => (unscaled % factor == 0).then(|| unscaled / factor),
+            // no i128 is a multiple of a larger factor, but zero
+            None => (unscaled == 0).then_some(0),
+        }
+    }
+    .ok_or_else(unfit)?;
+    if precision <= 38 && value.unsigned_abs() >= 10_u128.pow(u32::from(precision)) {
+        return Err(unfit());
+    }
+
+    match typ {
+        Type::Decimal32(..) => out.extend(i32::try_from(value)?.to_le_bytes()),
+        Type::Decimal64(..) => out.extend(i64::try_from(value)?.to_le_bytes()),
+        Type::Decimal128(..) => out.extend(value.to_le_bytes()),
+        _ => {
+            out.extend(value.to_le_bytes());
+            out.extend([if value < 0 { 0xFF } else { 0 }; 16]);
+        }
+    }
+    Ok(())
+}
+
+/// Writes the instant `seconds` and `nanos` after the epoch into a `DateTime` column, or a
+/// `DateTime64` one, where the fraction beyond its precision is truncated.
+fn datetime(seconds: i64, nanos: u32, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    let unfit = || mismatch(format!("{seconds}.{nanos:09} seconds"), typ);
+    match *typ {
+        Type::DateTime(_) => out.extend(u32::try_from(seconds).map_err(|_| unfit())?.to_le_bytes()),
+        Type::DateTime64(precision, _) if precision <= 9 => {
+            let scale = 10_i64.pow(u32::from(precision));
+            let fraction = i64::from(nanos) / 10_i64.pow(9 - u32::from(precision));
+            let ticks = seconds
+                .checked_mul(scale)
+                .and_then(|ticks| ticks.checked_add(fraction))
+                .ok_or_else(unfit)?;
+            out.extend(ticks.to_le_bytes());
+        }
+        _ => return Err(unfit()),
+    }
+    Ok(())
+}
+
+/// Splits ticks of the given precision into seconds and nanoseconds.
+fn instant(ticks: i64, precision: u8, typ: &Type<'_>) -> Result<(i64, u32)> {
+    conv::split_ticks(ticks, precision)
+        .ok_or_else(|| mismatch(format!("DateTime64({precision}) value"), typ))
+}
+
+fn date(value: NaiveDate, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    let days = (value - NaiveDate::default()).num_days();
+    let unfit = || mismatch(value, typ);
+    match typ {
+        Type::Date => out.extend(u16::try_from(days).map_err(|_| unfit())?.to_le_bytes()),
+        Type::Date32 => out.extend(i32::try_from(days).map_err(|_| unfit())?.to_le_bytes()),
+        _ => return Err(unfit()),
+    }
+    Ok(())
+}
+
+/// Writes the length of an array, then its elements.
+fn array<'e, E: Encode + ?Sized + 'e>(
+    elements: impl ExactSizeIterator<Item = &'e E>,
+    typ: &Type<'_>,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    let Type::Array(inner) = typ else {
+        return Err(mismatch("array", typ));
+    };
+    write::varuint(out, u64::try_from(elements.len())?);
+    elements
+        .into_iter()
+        .try_for_each(|element| encode(element, inner, out))
+}
+
+fn map<'e, K: Encode + 'e, V: Encode + 'e>(
+    entries: impl ExactSizeIterator<Item = (&'e K, &'e V)>,
+    typ: &Type<'_>,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    let Type::Map(key_type, value_type) = typ else {
+        return Err(mismatch("map", typ));
+    };
+    write::varuint(out, u64::try_from(entries.len())?);
+    for (key, value) in entries {
+        encode(key, key_type, out)?;
+        encode(value, value_type, out)?;
+    }
+    Ok(())
+}
+
+/// The type of an array of `elements`, Nullable if any of them is NULL. Unknown if there are
+/// no elements that are not NULL.
+fn array_type<'e, E: Encode + ?Sized + 'e>(
+    elements: impl IntoIterator<Item = &'e E>,
+) -> Option<Type<'static>> {
+    let mut nullable = false;
+    let mut inner = None;
+    for element in elements {
+        if element.is_null() {
+            nullable = true;
+        } else if inner.is_none() {
+            inner = element.own_type();
+        }
+    }
+    let inner = inner?;
+    let inner = if nullable && !matches!(inner, Type::Nullable(_)) {
+        Type::Nullable(Box::new(inner))
+    } else {
+        inner
+    };
+    Some(Type::Array(Box::new(inner)))
+}
+
+impl Encode for Value<'_> {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        Item::from(self.clone()).encode_value(typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Item::from(self.clone()).own_type()
+    }
+
+    fn is_null(&self) -> bool {
+        matches!(self, Value::Empty)
+    }
+}
+
+impl Encode for Item<'_> {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        let value = match self {
+            Item::Decimal(unscaled, scale) => {
+                return match *unscaled {
+                    Unscaled::I32(value) => decimal(i128::from(value), *scale, typ, out),
+                    Unscaled::I64(value) => decimal(i128::from(value), *scale, typ, out),
+                    Unscaled::I128(value) => decimal(value.get(), *scale, typ, out),
+                    Unscaled::I256(value) => wide_decimal(&value.0, *scale, typ, out),
+                };
+            }
+            Item::DateTime {
+                ticks, precision, ..
+            } => {
+                let (seconds, nanos) = instant(*ticks, *precision, typ)?;
+                return datetime(seconds, nanos, typ, out);
+            }
+            Item::Value(value) => value,
+        };
+
+        match *value {
+            Value::Bool(value) => integer(i128::from(value), typ, out),
+            Value::Int8(value) => integer(i128::from(value), typ, out),
+            Value::Int16(value) => integer(i128::from(value), typ, out),
+            Value::Int32(value) => integer(i128::from(value), typ, out),
+            Value::Int64(value) => integer(i128::from(value), typ, out),
+            Value::Int128(value) => integer(value.get(), typ, out),
+            Value::UInt8(value) => integer(i128::from(value), typ, out),
+            Value::UInt16(value) => integer(i128::from(value), typ, out),
+            Value::UInt32(value) => integer(i128::from(value), typ, out),
+            Value::UInt64(value) => integer(i128::from(value), typ, out),
+            Value::UInt128(value) => unsigned(value.get(), typ, out),
+            Value::Int256(value) => wide(&value.0, true, typ, out),
+            Value::UInt256(value) => wide(&value.0, false, typ, out),
+            Value::Float32(value) => float(f64::from(value), typ, out),
+            Value::Float64(value) => float(value, typ, out),
+            Value::BFloat16(value) => float(value.to_f64(), typ, out),
+            Value::String(value) | Value::Enum8(value, _) | Value::Enum16(value, _) => {
+                string(value.as_bytes(), typ, out)
+            }
+            Value::Bytes(value) => string(value, typ, out),
+            Value::Uuid(&value) => Uuid::from(value).encode_value(typ, out),
+            Value::Date(value) | Value::Date32(value) => date(value, typ, out),
+            Value::Ipv4(value) => value.encode_value(typ, out),
+            Value::Ipv6(&value) => Ipv6Addr::from(value).encode_value(typ, out),
+            Value::Tuple { index, mark } => {
+                let Type::Tuple(types, _) = typ else {
+                    return Err(mismatch(describe(self), typ));
+                };
+                if types.len() != mark.values.len() {
+                    return Err(mismatch(describe(self), typ));
+                }
+                for (column, typ) in mark.values.iter().zip(types) {
+                    let value = column
+                        .get(index)
+                        .ok_or(Error::IndexOutOfBounds(index, "Tuple"))?;
+                    encode(&value, typ, out)?;
+                }
+                Ok(())
+            }
+            Value::Map { mark, index } => {
+                let Type::Map(key_type, value_type) = typ else {
+                    return Err(mismatch("map", typ));
+                };
+                let (start, end) = mark
+                    .offsets
+                    .offset_indices(index)?
+                    .ok_or(Error::IndexOutOfBounds(index, "Map"))?;
+                write::varuint(out, u64::try_from(end - start)?);
+                for position in start..end {
+                    let key = mark
+                        .keys
+                        .get(position)
+                        .ok_or(Error::IndexOutOfBounds(position, "Map keys"))?;
+                    let value = mark
+                        .values
+                        .get(position)
+                        .ok_or(Error::IndexOutOfBounds(position, "Map values"))?;
+                    encode(&key, key_type, out)?;
+                    encode(&value, value_type, out)?;
+                }
+                Ok(())
+            }
+            Value::Nested { .. } => {
+                let Type::Array(inner) = typ else {
+                    return Err(mismatch("Nested", typ));
+                };
+                let Type::Tuple(types, _) = inner.as_ref() else {
+                    return Err(mismatch("Nested", typ));
+                };
+                let rows = NestedIterator::try_from(value.clone())?;
+                write::varuint(out, u64::try_from(rows.len())?);
+                for row in rows {
+                    if row.len() != types.len() {
+                        return Err(mismatch("Nested", typ));
+                    }
+                    for ((_, value), typ) in row.zip(types) {
+                        encode(&value, typ, out)?;
+                    }
+                }
+                Ok(())
+            }
+            Value::Json { .. } => Err(Error::NotImplemented(
+                "RowBinary encoding of JSON".to_owned(),
+            )),
+            Value::Empty => Err(mismatch("NULL", typ)),
+            // every other value is an array
+            _ => {
+                let elements = text::elements(value)?;
+                array(elements.iter(), typ, out)
+            }
+        }
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        let value = match self {
+            Item::Decimal(unscaled, scale) => {
+                return Some(match unscaled {
+                    // a value does not know the precision of its column, so it takes the widest
+                    Unscaled::I32(_) => Type::Decimal32(9, *scale),
+                    Unscaled::I64(_) => Type::Decimal64(18, *scale),
+                    Unscaled::I128(_) => Type::Decimal128(38, *scale),
+                    Unscaled::I256(_) => Type::Decimal256(76, *scale),
+                });
+            }
+            Item::DateTime {
+                precision: 0, tz, ..
+            } => return Some(Type::DateTime(*tz)),
+            Item::DateTime { precision, tz, .. } => return Some(Type::DateTime64(*precision, *tz)),
+            Item::Value(value) => value,
+        };
+
+        Some(match *value {
+            Value::Empty | Value::Nested { .. } | Value::Json { .. } => return None,
+            Value::Bool(_) => Type::Bool,
+            Value::Int8(_) => Type::Int8,
+            Value::Int16(_) => Type::Int16,
+            Value::Int32(_) => Type::Int32,
+            Value::Int64(_) => Type::Int64,
+            Value::Int128(_) => Type::Int128,
+            Value::Int256(_) => Type::Int256,
+            Value::UInt8(_) => Type::UInt8,
+            Value::UInt16(_) => Type::UInt16,
+            Value::UInt32(_) => Type::UInt32,
+            Value::UInt64(_) => Type::UInt64,
+            Value::UInt128(_) => Type::UInt128,
+            Value::UInt256(_) => Type::UInt256,
+            Value::Float32(_) => Type::Float32,
+            Value::Float64(_) => Type::Float64,
+            Value::BFloat16(_) => Type::BFloat16,
+            Value::String(_) | Value::Bytes(_) | Value::Enum8(..) | Value::Enum16(..) => {
+                Type::String
+            }
+            Value::Uuid(_) => Type::Uuid,
+            Value::Date(_) => Type::Date,
+            Value::Date32(_) => Type::Date32,
+            Value::Ipv4(_) => Type::Ipv4,
+            Value::Ipv6(_) => Type::Ipv6,
+            // the element names borrow from the block, so the owned type is unnamed
+            Value::Tuple { index, mark } => Type::Tuple(
+                mark.values
+                    .iter()
+                    .map(|column| column.get(index)?.own_type())
+                    .collect::<Option<_>>()?,
+                Box::default(),
+            ),
+            Value::Map { mark, index } => {
+                let (start, _) = mark.offsets.offset_indices(index).ok()??;
+                let key = mark.keys.get(start)?.own_type()?;
+                let value = mark.values.get(start)?.own_type()?;
+                Type::Map(Box::new(key), Box::new(value))
+            }
+            _ => return array_type(text::elements(value).ok()?.iter()),
+        })
+    }
+
+    fn is_null(&self) -> bool {
+        matches!(self, Item::Value(Value::Empty))
+    }
+}
+
+/// Writes a 256-bit decimal: copied into `Decimal256` columns of its scale, narrowed otherwise.
+fn wide_decimal(unscaled: &[u8; 32], scale: u8, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+    if let Type::Decimal256(precision, target) = *typ
+        && target == scale
+    {
+        let digits = text::wide(unscaled, true);
+        if digits.trim_start_matches('-').len() > usize::from(precision) {
+            let mut value = String::new();
+            let _ = text::decimal(&mut value, &digits, scale);
+            return Err(mismatch(value, typ));
+        }
+        out.extend_from_slice(unscaled);
+        return Ok(());
+    }
+
+    let mut narrow = Vec::with_capacity(16);
+    wide(unscaled, true, &Type::Int128, &mut narrow).map_err(|_| {
+        let mut digits = String::new();
+        let _ = text::decimal(&mut digits, &text::wide(unscaled, true), scale);
+        mismatch(digits, typ)
+    })?;
+    let value = i128::from_le_bytes(narrow.try_into().unwrap_or_default());
+    decimal(value, scale, typ, out)
+}
+
+impl Encode for OwnedValue {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            OwnedValue::Null => Err(mismatch("NULL", typ)),
+            OwnedValue::Bool(value) => integer(i128::from(*value), typ, out),
+            OwnedValue::Int8(value) => integer(i128::from(*value), typ, out),
+            OwnedValue::Int16(value) => integer(i128::from(*value), typ, out),
+            OwnedValue::Int32(value) => integer(i128::from(*value), typ, out),
+            OwnedValue::Int64(value) => integer(i128::from(*value), typ, out),
+            OwnedValue::Int128(value) => integer(*value, typ, out),
+            OwnedValue::UInt8(value) => integer(i128::from(*value), typ, out),
+            OwnedValue::UInt16(value) => integer(i128::from(*value), typ, out),
+            OwnedValue::UInt32(value) => integer(i128::from(*value), typ, out),
+            OwnedValue::UInt64(value) => integer(i128::from(*value), typ, out),
+            OwnedValue::UInt128(value) => unsigned(*value, typ, out),
+            OwnedValue::Int256(bytes) => wide(bytes, true, typ, out),
+            OwnedValue::UInt256(bytes) => wide(bytes, false, typ, out),
+            OwnedValue::Float32(value) => float(f64::from(*value), typ, out),
+            OwnedValue::Float64(value) => float(*value, typ, out),
+            OwnedValue::BFloat16(value) => float(value.to_f64(), typ, out),
+            OwnedValue::Decimal32 { unscaled, scale } => {
+                decimal(i128::from(*unscaled), *scale, typ, out)
+            }
+            OwnedValue::Decimal64 { unscaled, scale } => {
+                decimal(i128::from(*unscaled), *scale, typ, out)
+            }
+            OwnedValue::Decimal128 { unscaled, scale } => decimal(*unscaled, *scale, typ, out),
+            OwnedValue::Decimal256 { unscaled, scale } => wide_decimal(unscaled, *scale, typ, out),
+            OwnedValue::String(value)
+            | OwnedValue::Enum8(value, _)
+            | OwnedValue::Enum16(value, _) => string(value.as_bytes(), typ, out),
+            OwnedValue::Bytes(value) => string(value, typ, out),
+            OwnedValue::Uuid(value) => value.encode_value(typ, out),
+            OwnedValue::Date(value) | OwnedValue::Date32(value) => date(*value, typ, out),
+            OwnedValue::DateTime {
+                ticks, precision, ..
+            } => {
+                let (seconds, nanos) = instant(*ticks, *precision, typ)?;
+                datetime(seconds, nanos, typ, out)
+            }
+            OwnedValue::Ipv4(value) => value.encode_value(typ, out),
+            OwnedValue::Ipv6(value) => value.encode_value(typ, out),
+            OwnedValue::Array(elements) => array(elements.iter(), typ, out),
+            OwnedValue::Tuple(elements) => {
+                let Type::Tuple(types, _) = typ else {
+                    return Err(mismatch(describe(self), typ));
+                };
+                if types.len() != elements.len() {
+                    return Err(mismatch(describe(self), typ));
+                }
+                elements
+                    .iter()
+                    .zip(types)
+                    .try_for_each(|(element, typ)| encode(element, typ, out))
+            }
+            OwnedValue::Map(entries) => {
+                map(entries.iter().map(|(key, value)| (key, value)), typ, out)
+            }
+            // `encode` passes `Nested` columns on as arrays of tuples
+            OwnedValue::Nested { rows, .. } => {
+                let Type::Array(inner) = typ else {
+                    return Err(mismatch("Nested", typ));
+                };
+                let Type::Tuple(types, _) = inner.as_ref() else {
+                    return Err(mismatch("Nested", typ));
+                };
+                write::varuint(out, u64::try_from(rows.len())?);
+                for row in rows {
+                    if row.len() != types.len() {
+                        return Err(mismatch("Nested", typ));
+                    }
+                    for (value, typ) in row.iter().zip(types) {
+                        encode(value, typ, out)?;
+                    }
+                }
+                Ok(())
+            }
+            OwnedValue::Json(_) => Err(Error::NotImplemented(
+                "RowBinary encoding of JSON".to_owned(),
+            )),
+        }
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(match self {
+            OwnedValue::Null | OwnedValue::Nested { .. } | OwnedValue::Json(_) => return None,
+            OwnedValue::Bool(_) => Type::Bool,
+            OwnedValue::Int8(_) => Type::Int8,
+            OwnedValue::Int16(_) => Type::Int16,
+            OwnedValue::Int32(_) => Type::Int32,
+            OwnedValue::Int64(_) => Type::Int64,
+            OwnedValue::Int128(_) => Type::Int128,
+            OwnedValue::Int256(_) => Type::Int256,
+            OwnedValue::UInt8(_) => Type::UInt8,
+            OwnedValue::UInt16(_) => Type::UInt16,
+            OwnedValue::UInt32(_) => Type::UInt32,
+            OwnedValue::UInt64(_) => Type::UInt64,
+            OwnedValue::UInt128(_) => Type::UInt128,
+            OwnedValue::UInt256(_) => Type::UInt256,
+            OwnedValue::Float32(_) => Type::Float32,
+            OwnedValue::Float64(_) => Type::Float64,
+            OwnedValue::BFloat16(_) => Type::BFloat16,
+            // the widest precision, as for decimals of a block
+            OwnedValue::Decimal32 { scale, .. } => Type::Decimal32(9, *scale),
+            OwnedValue::Decimal64 { scale, .. } => Type::Decimal64(18, *scale),
+            OwnedValue::Decimal128 { scale, .. } => Type::Decimal128(38, *scale),
+            OwnedValue::Decimal256 { scale, .. } => Type::Decimal256(76, *scale),
+            OwnedValue::String(_)
+            | OwnedValue::Bytes(_)
+            | OwnedValue::Enum8(..)
+            | OwnedValue::Enum16(..) => Type::String,
+            OwnedValue::Uuid(_) => Type::Uuid,
+            OwnedValue::Date(_) => Type::Date,
+            OwnedValue::Date32(_) => Type::Date32,
+            OwnedValue::DateTime {
+                precision: 0, tz, ..
+            } => Type::DateTime(*tz),
+            OwnedValue::DateTime { precision, tz, .. } => Type::DateTime64(*precision, *tz),
+            OwnedValue::Ipv4(_) => Type::Ipv4,
+            OwnedValue::Ipv6(_) => Type::Ipv6,
+            OwnedValue::Array(elements) => return array_type(elements),
+            OwnedValue::Tuple(elements) => Type::Tuple(
+                elements
+                    .iter()
+                    .map(Encode::own_type)
+                    .collect::<Option<_>>()?,
+                Box::default(),
+            ),
+            OwnedValue::Map(entries) => {
+                let (key, value) = entries.first()?;
+                Type::Map(Box::new(key.own_type()?), Box::new(value.own_type()?))
+            }
+        })
+    }
+
+    fn is_null(&self) -> bool {
+        matches!(self, OwnedValue::Null)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        (**self).encode_value(typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        (**self).own_type()
+    }
+
+    fn is_null(&self) -> bool {
+        (**self).is_null()
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Some(value) => value.encode_value(typ, out),
+            None => Err(mismatch("NULL", typ)),
+        }
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        self.as_ref()?.own_type()
+    }
+
+    fn is_null(&self) -> bool {
+        self.as_ref().is_none_or(Encode::is_null)
+    }
+}
+
+macro_rules! encode_integer {
+    ($($t:ty => $typ:ident),+ $(,)?) => {
+        $(
+            impl Encode for $t {
+                fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+                    integer(i128::from(*self), typ, out)
+                }
+
+                fn own_type(&self) -> Option<Type<'static>> {
+                    Some(Type::$typ)
+                }
+            }
+        )+
+    };
+}
+
+encode_integer!(
+    bool => Bool,
+    i8 => Int8,
+    i16 => Int16,
+    i32 => Int32,
+    i64 => Int64,
+    i128 => Int128,
+    u8 => UInt8,
+    u16 => UInt16,
+    u32 => UInt32,
+    u64 => UInt64,
+);
+
+impl Encode for u128 {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        unsigned(*self, typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::UInt128)
+    }
+}
+
+impl Encode for f32 {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        float(f64::from(*self), typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::Float32)
+    }
+}
+
+impl Encode for f64 {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        float(*self, typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::Float64)
+    }
+}
+
+impl Encode for bf16 {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        float(self.to_f64(), typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::BFloat16)
+    }
+}
+
+impl Encode for str {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        string(self.as_bytes(), typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::String)
+    }
+}
+
+impl Encode for String {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        self.as_str().encode_value(typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::String)
+    }
+}
+
+impl Encode for Uuid {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        if *typ != Type::Uuid {
+            return Err(mismatch(self, typ));
+        }
+        let (high, low) = self.as_u64_pair();
+        out.extend(high.to_le_bytes());
+        out.extend(low.to_le_bytes());
+        Ok(())
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::Uuid)
+    }
+}
+
+impl Encode for NaiveDate {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        date(*self, typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::Date32)
+    }
+}
+
+impl Encode for chrono::DateTime<Tz> {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        datetime(self.timestamp(), self.timestamp_subsec_nanos(), typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::DateTime64(6, self.timezone()))
+    }
+}
+
+impl Encode for chrono::DateTime<Utc> {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        datetime(self.timestamp(), self.timestamp_subsec_nanos(), typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::DateTime64(6, Tz::UTC))
+    }
+}
+
+impl Encode for Ipv4Addr {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        if *typ != Type::Ipv4 {
+            return Err(mismatch(self, typ));
+        }
+        out.extend(u32::from(*self).to_le_bytes());
+        Ok(())
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::Ipv4)
+    }
+}
+
+impl Encode for Ipv6Addr {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        if *typ != Type::Ipv6 {
+            return Err(mismatch(self, typ));
+        }
+        out.extend(self.octets());
+        Ok(())
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::Ipv6)
+    }
+}
+
+impl Encode for rust_decimal::Decimal {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        // the scale of a `Decimal` is at most 28
+        decimal(self.mantissa(), u8::try_from(self.scale())?, typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        Some(Type::Decimal128(38, u8::try_from(self.scale()).ok()?))
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        array(self.iter(), typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        array_type(self)
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        self.as_slice().encode_value(typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        self.as_slice().own_type()
+    }
+}
+
+impl<K: Encode, V: Encode> Encode for BTreeMap<K, V> {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        map(self.iter(), typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        let (key, value) = self.iter().next()?;
+        Some(Type::Map(
+            Box::new(key.own_type()?),
+            Box::new(value.own_type()?),
+        ))
+    }
+}
+
+impl<K: Encode, V: Encode, S: BuildHasher> Encode for HashMap<K, V, S> {
+    fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+        map(self.iter(), typ, out)
+    }
+
+    fn own_type(&self) -> Option<Type<'static>> {
+        let (key, value) = self.iter().next()?;
+        Some(Type::Map(
+            Box::new(key.own_type()?),
+            Box::new(value.own_type()?),
+        ))
+    }
+}
+
+/// Tuples are both rows and `Tuple` values.
+macro_rules! tuples {
+    ($(($($name:ident $index:tt),+)),+ $(,)?) => {
+        $(
+            impl<$($name: Encode),+> Encode for ($($name,)+) {
+                fn encode_value(&self, typ: &Type<'_>, out: &mut Vec<u8>) -> Result<()> {
+                    match typ {
+                        Type::Tuple(types, _) if types.len() == [$($index),+].len() => {
+                            $(encode(&self.$index, &types[$index], out)?;)+
+                            Ok(())
+                        }
+                        _ => Err(mismatch(describe(self), typ)),
+                    }
+                }
+
+                fn own_type(&self) -> Option<Type<'static>> {
+                    Some(Type::Tuple(vec![$(self.$index.own_type()?),+], Box::default()))
+                }
+            }
+
+            impl<$($name: Encode),+> Row for ($($name,)+) {
+                fn encode_row(&self, types: &[Type<'_>], out: &mut Vec<u8>) -> Result<()> {
+                    let len = [$($index),+].len();
+                    if types.len() != len {
+                        return Err(columns_mismatch(len, types.len()));
+                    }
+                    $(encode(&self.$index, &types[$index], out)?;)+
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
+
+tuples!(
+    (A 0),
+    (A 0, B 1),
+    (A 0, B 1, C 2),
+    (A 0, B 1, C 2, D 3),
+    (A 0, B 1, C 2, D 3, E 4),
+    (A 0, B 1, C 2, D 3, E 4, F 5),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7),
+);
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{
+        ParsedBlock,
+        common::load_testdata,
+        parse::{
+            block::parse_many,
+            rowbinary::{Header, to_native},
+            typ::parse_type,
+        },
+        text::{Format, column_value, write_blocks},
+    };
+
+    fn types(types: &[&'static str]) -> TestResult<Vec<Type<'static>>> {
+        let mut parsed = Vec::new();
+        for typ in types {
+            parsed.push(parse_type(typ.as_bytes())?.1);
+        }
+        Ok(parsed)
+    }
+
+    fn tsv(blocks: &[ParsedBlock<'_>]) -> TestResult<String> {
+        let mut out = Vec::new();
+        write_blocks(&mut out, blocks, Format::TabSeparated, text::Header::Names)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    fn decode(writer: &Writer<'_>) -> TestResult<String> {
+        let native = to_native(writer.as_bytes(), Header::NamesAndTypes)?;
+        tsv(&parse_many(&native)?)
+    }
+
+    #[test]
+    fn round_trips_testdata() -> TestResult {
+        for (path, data) in load_testdata()? {
+            for block in parse_many(&data)? {
+                let header = Header::None {
+                    names: &block.col_names,
+                    types: &block.types,
+                };
+                if let Err(Error::NotImplemented(_)) = to_native(&[], header) {
+                    // e.g. `Dynamic`, which cannot be decoded back
+                    continue;
+                }
+
+                let mut writer = Writer::with_names_and_types(&block.col_names, &block.types)?;
+                for row in 0..block.num_rows {
+                    let values = block
+                        .markers
+                        .iter()
+                        .map(|mark| column_value(mark, row))
+                        .collect::<Result<Vec<_>>>()?;
+                    writer.write_row(&values)?;
+                }
+                let native = to_native(writer.as_bytes(), Header::NamesAndTypes)?;
+                let expected = tsv(std::slice::from_ref(&block))?;
+                assert_eq!(tsv(&parse_many(&native)?)?, expected, "{}", path.display());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn round_trips_owned_values() -> TestResult {
+        for (path, data) in load_testdata()? {
+            for block in parse_many(&data)? {
+                let header = Header::None {
+                    names: &block.col_names,
+                    types: &block.types,
+                };
+                if let Err(Error::NotImplemented(_)) = to_native(&[], header) {
+                    continue;
+                }
+
+                let rows = |block: &ParsedBlock<'_>| {
+                    (0..block.num_rows)
+                        .map(|row| {
+                            block
+                                .markers
+                                .iter()
+                                .map(|mark| column_value(mark, row)?.to_owned())
+                                .collect::<Result<Vec<_>>>()
+                        })
+                        .collect::<Result<Vec<_>>>()
+                };
+                let expected = rows(&block)?;
+                let mut writer = Writer::new(&block.types);
+                for row in &expected {
+                    writer.write_row(row)?;
+                }
+                let native = to_native(writer.as_bytes(), header)?;
+                let decoded = parse_many(&native)?;
+                assert_eq!(rows(&decoded[0])?, expected, "{}", path.display());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn typed_values() -> TestResult {
+        let types = types(&[
+            "UInt8",
+            "Nullable(String)",
+            "LowCardinality(String)",
+            "Array(Int64)",
+            "Map(String, Float64)",
+            "Tuple(Decimal(9, 2), Enum8('a' = 1, 'b' = 2))",
+            "DateTime64(3, 'UTC')",
+            "UUID",
+        ])?;
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let mut writer = Writer::with_names_and_types(&names, &types)?;
+
+        let time = chrono::DateTime::from_timestamp(1_700_000_000, 123_456_789).ok_or("time")?;
+        let uuid = Uuid::from_u64_pair(1, 2);
+        writer.write_row(&(
+            7_u32,
+            Some("x"),
+            "lc",
+            vec![1_i8, -2],
+            BTreeMap::from([("k", 0.5)]),
+            (rust_decimal::Decimal::new(125, 1), "b"),
+            time,
+            uuid,
+        ))?;
+        writer.write_row(&(
+            0_i64,
+            None::<&str>,
+            String::new(),
+            Vec::<i64>::new(),
+            HashMap::<String, f64>::new(),
+            (3, 1),
+            time.with_timezone(&Tz::Europe__Berlin),
+            uuid,
+        ))?;
+
+        let expected = "a\tb\tc\td\te\tf\tg\th\n\
+            7\tx\tlc\t[1,-2]\t{'k':0.5}\t(12.50,'b')\t2023-11-14 22:13:20.123\t\
+            00000000-0000-0001-0000-000000000002\n\
+            0\t\\N\t\t[]\t{}\t(3.00,'a')\t2023-11-14 22:13:20.123\t\
+            00000000-0000-0001-0000-000000000002\n";
+        assert_eq!(decode(&writer)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn validates_values() -> TestResult {
+        let types = types(&[
+            "UInt8",
+            "Enum8('a' = 1)",
+            "Decimal(9, 2)",
+            "FixedString(2)",
+            "Date",
+        ])?;
+        let mut writer = Writer::new(&types);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).ok_or("date")?;
+        let decimal = rust_decimal::Decimal::new(1, 0);
+        writer.write_row(&(255, "a", decimal, "ab", date))?;
+        let written = writer.as_bytes().len();
+
+        let rejected = [
+            writer.write_row(&(256, "a", decimal, "ab", date)),
+            writer.write_row(&(-1, "a", decimal, "ab", date)),
+            writer.write_row(&(1, "b", decimal, "ab", date)),
+            writer.write_row(&(1, 2, decimal, "ab", date)),
+            writer.write_row(&(1, "a", rust_decimal::Decimal::new(1234, 3), "ab", date)),
+            writer.write_row(&(1, "a", 1_i128 << 40, "ab", date)),
+            writer.write_row(&(1, "a", decimal, "abc", date)),
+            writer.write_row(&(1, "a", decimal, "ab", NaiveDate::MIN)),
+            writer.write_row(&(None::<u8>, "a", decimal, "ab", date)),
+            writer.write_row(&(1.5, "a", decimal, "ab", date)),
+        ];
+        for result in rejected {
+            assert!(matches!(result, Err(Error::Encode(..))), "{result:?}");
+        }
+        let result = writer.write_row(&(1, "a"));
+        assert!(matches!(result, Err(Error::ProgrammingError(_))));
+        assert_eq!(writer.as_bytes().len(), written);
+        Ok(())
+    }
+
+    #[test]
+    fn validates_floats_and_nulls() -> TestResult {
+        let types = types(&["Float32", "BFloat16", "Array(UInt8)"])?;
+        let mut writer = Writer::new(&types);
+        writer.write_row(&(f64::NAN, f64::NEG_INFINITY, vec![1_u8]))?;
+        writer.write_row(&(f64::from(f32::MAX), 1e38, Vec::<u8>::new()))?;
+        let written = writer.as_bytes().len();
+        let rejected = [
+            writer.write_row(&(1e39, 0.0, vec![1_u8])),
+            writer.write_row(&(0.0, -1e39, vec![1_u8])),
+            writer.write_row(&(0.0, 0.0, None::<Vec<u8>>)),
+        ];
+        for result in rejected {
+            assert!(matches!(result, Err(Error::Encode(..))), "{result:?}");
+        }
+        assert_eq!(writer.as_bytes().len(), written);
+        Ok(())
+    }
+
+    #[test]
+    fn rescales_decimals_by_many_digits() -> TestResult {
+        let typ = Type::Decimal32(9, 0);
+        let mut out = Vec::new();
+        // 1e-38, which does not fit a scale of zero
+        let result = decimal(10_i128.pow(38), 76, &typ, &mut out);
+        assert!(matches!(result, Err(Error::Encode(..))), "{result:?}");
+        assert!(out.is_empty());
+
+        decimal(0, 76, &typ, &mut out)?;
+        assert_eq!(out, [0; 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unknown_precisions() {
+        let tz = chrono_tz::Tz::UTC;
+        let value = OwnedValue::DateTime {
+            ticks: 1,
+            precision: 64,
+            tz,
+        };
+        let mut out = Vec::new();
+        let result = value.encode_value(&Type::DateTime64(3, tz), &mut out);
+        assert!(matches!(result, Err(Error::Encode(..))), "{result:?}");
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn variants_and_dynamic() -> TestResult {
+        let types = types(&["Variant(String, UInt64)", "Dynamic"])?;
+        let mut writer = Writer::new(&types);
+
+        writer.write_row(&("v", 5_i32))?;
+        writer.write_row(&(42_u8, vec!["a"]))?;
+        writer.write_row(&(None::<u64>, None::<i32>))?;
+
+        let mut expected = vec![0, 1, b'v', 0x09, 5, 0, 0, 0];
+        expected.extend([1, 42, 0, 0, 0, 0, 0, 0, 0, 0x1E, 0x15, 1, 1, b'a']);
+        expected.extend([NULL_DISCRIMINATOR, 0x00]);
+        assert_eq!(writer.as_bytes(), expected);
+
+        let result = writer.write_row(&(-1, 1));
+        assert!(matches!(result, Err(Error::Encode(..))));
+        Ok(())
+    }
+
+    #[test]
+    fn named_tuple_binary_type() -> TestResult {
+        let mut out = Vec::new();
+        binary_type(&Type::from_bytes(b"Tuple(a UInt8, b String)")?, &mut out)?;
+        assert_eq!(out, [0x20, 2, 1, b'a', 0x01, 1, b'b', 0x15]);
+
+        out.clear();
+        binary_type(&Type::from_bytes(b"Tuple(UInt8, String)")?, &mut out)?;
+        assert_eq!(out, [0x1F, 2, 0x01, 0x15]);
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+//! Checking blocks against an expected set of columns and types.
+
+use std::fmt::{self, Display};
+
+use crate::{ParsedBlock, Result, error::Error, types::Type};
+
+/// The columns a block is expected to have, by name. Column order is not checked: use
+/// [`crate::iter_blocks_ordered`] to put the columns in the order a row decoder wants.
+#[derive(Debug, Clone)]
+pub struct Schema<'a> {
+    pub columns: Vec<(&'a str, Type<'a>)>,
+    extra_columns: bool,
+    nullable_widening: bool,
+}
+
+impl<'a> Schema<'a> {
+    pub fn new(columns: impl IntoIterator<Item = (&'a str, Type<'a>)>) -> Self {
+        Self {
+            columns: columns.into_iter().collect(),
+            extra_columns: false,
+            nullable_widening: false,
+        }
+    }
+
+    /// Accept blocks with columns the schema does not list.
+    pub fn with_extra_columns(self, allowed: bool) -> Self {
+        Self {
+            extra_columns: allowed,
+            ..self
+        }
+    }
+
+    /// Accept `T` where the schema expects `Nullable(T)`, at any nesting level: such a column
+    /// can be read as if it was nullable, it just never holds a null.
+    pub fn with_nullable_widening(self, allowed: bool) -> Self {
+        Self {
+            nullable_widening: allowed,
+            ..self
+        }
+    }
+
+    /// Fails with [`Error::SchemaMismatch`] listing every difference, if there are any.
+    pub fn check(&self, block: &ParsedBlock<'_>) -> Result<()> {
+        let diff = self.diff(block);
+        if diff.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::SchemaMismatch(diff))
+        }
+    }
+
+    /// All differences between the schema and the block: missing columns first, in schema
+    /// order, then type mismatches and extra columns, in block order.
+    pub fn diff(&self, block: &ParsedBlock<'_>) -> Diff {
+        let mut mismatches = Vec::new();
+
+        for (name, expected) in &self.columns {
+            if !block.col_names.contains(name) {
+                mismatches.push(Mismatch {
+                    column: (*name).to_owned(),
+                    expected: Some(expected.to_string()),
+                    actual: None,
+                });
+            }
+        }
+
+        for (name, actual) in block.col_names.iter().zip(&block.types) {
+            match self.columns.iter().find(|(expected, _)| expected == name) {
+                Some((_, expected)) if !self.matches(expected, actual) => {
+                    mismatches.push(Mismatch {
+                        column: (*name).to_owned(),
+                        expected: Some(expected.to_string()),
+                        actual: Some(actual.to_string()),
+                    });
+                }
+                Some(_) => {}
+                None if self.extra_columns => {}
+                None => mismatches.push(Mismatch {
+                    column: (*name).to_owned(),
+                    expected: None,
+                    actual: Some(actual.to_string()),
+                }),
+            }
+        }
+
+        Diff(mismatches)
+    }
+
+    fn matches(&self, expected: &Type<'_>, actual: &Type<'_>) -> bool {
+        match (expected, actual) {
+            (Type::Nullable(expected), Type::Nullable(actual))
+            | (Type::Array(expected), Type::Array(actual))
+            | (Type::LowCardinality(expected), Type::LowCardinality(actual)) => {
+                self.matches(expected, actual)
+            }
+            (Type::Nullable(expected), actual) if self.nullable_widening => {
+                self.matches(expected, actual)
+            }
+            (Type::Map(expected_key, expected_value), Type::Map(key, value)) => {
+                self.matches(expected_key, key) && self.matches(expected_value, value)
+            }
+            (Type::Tuple(expected, expected_names), Type::Tuple(actual, names))
+                if expected_names != names =>
+            {
+                false
+            }
+            (Type::Tuple(expected, _), Type::Tuple(actual, _))
+            | (Type::Variant(expected), Type::Variant(actual)) => {
+                expected.len() == actual.len()
+                    && expected
+                        .iter()
+                        .zip(actual)
+                        .all(|(expected, actual)| self.matches(expected, actual))
+            }
+            (Type::Nested(expected), Type::Nested(actual)) => {
+                expected.len() == actual.len()
+                    && expected.iter().zip(actual).all(|(expected, actual)| {
+                        expected.name == actual.name && self.matches(&expected.typ, &actual.typ)
+                    })
+            }
+            _ => expected == actual,
+        }
+    }
+}
+
+/// A single difference between a [`Schema`] and a block. Types are rendered as ClickHouse type
+/// names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub column: String,
+
+    /// The type the schema expects, `None` for a column the schema does not list (reported
+    /// only if extra columns are not allowed).
+    pub expected: Option<String>,
+
+    /// The type found in the block, `None` if the block does not have the column.
+    pub actual: Option<String>,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let column = &self.column;
+        match (&self.expected, &self.actual) {
+            (Some(expected), Some(actual)) => write!(f, "~ {column} {expected} -> {actual}"),
+            (Some(expected), None) => write!(f, "- {column} {expected}"),
+            (None, Some(actual)) => write!(f, "+ {column} {actual}"),
+            (None, None) => write!(f, "? {column}"),
+        }
+    }
+}
+
+/// Every difference between a [`Schema`] and a block, empty if the block conforms.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diff(pub Vec<Mismatch>);
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn mismatches(&self) -> &[Mismatch] {
+        &self.0
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, mismatch) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{mismatch}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{common::load, parse::block::parse_many};
+
+    fn typ(name: &str) -> Type<'_> {
+        Type::from_bytes(name.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for name in [
+            "Array(LowCardinality(Nullable(String)))",
+            "Map(String, Array(Nullable(Int64)))",
+            "Tuple(UInt8, Decimal(38, 10), DateTime64(6, 'Europe/Amsterdam'))",
+            "Variant(String, Array(UInt64), Tuple(Float32, Bool))",
+            "Nested(id UInt32, name String)",
+            "Enum8('red' = -1, 'green' = 2)",
+            "FixedString(16)",
+            "DateTime",
+            "IPv6",
+            "UUID",
+        ] {
+            assert_eq!(typ(name).to_string(), name);
+        }
+    }
+
+    #[test]
+    fn diff_lists_every_mismatch() -> TestResult {
+        let buf = load("./testdata/nullable_string.native")?;
+        let blocks = parse_many(&buf)?;
+        let block = &blocks[0];
+        let actual = block
+            .col_names
+            .iter()
+            .zip(&block.types)
+            .map(|(name, typ)| (*name, typ.clone()))
+            .collect::<Vec<_>>();
+
+        let schema = Schema::new(actual.clone());
+        schema.check(block)?;
+
+        // the widened schema accepts the non-nullable columns as they are
+        let widened = actual
+            .iter()
+            .map(|(name, typ)| (*name, Type::Nullable(Box::new(typ.strip_null().clone()))));
+        let schema = Schema::new(widened);
+        let strict = schema.diff(block);
+        assert!(!strict.is_empty());
+        assert!(
+            strict
+                .mismatches()
+                .iter()
+                .all(|mismatch| mismatch.expected.is_some() && mismatch.actual.is_some())
+        );
+        schema.with_nullable_widening(true).check(block)?;
+
+        let (first, first_type) = actual[0].clone();
+        let schema = Schema::new(
+            actual[1..]
+                .iter()
+                .cloned()
+                .chain([("missing", typ("UInt8"))]),
+        );
+        let Err(Error::SchemaMismatch(diff)) = schema.check(block) else {
+            panic!("expected a schema mismatch");
+        };
+        assert_eq!(
+            diff.mismatches(),
+            [
+                Mismatch {
+                    column: "missing".to_owned(),
+                    expected: Some("UInt8".to_owned()),
+                    actual: None,
+                },
+                Mismatch {
+                    column: first.to_owned(),
+                    expected: None,
+                    actual: Some(first_type.to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            format!("- missing UInt8; + {first} {first_type}")
+        );
+        let diff = schema.with_extra_columns(true).diff(block);
+        assert_eq!(diff.to_string(), "- missing UInt8");
+
+        Ok(())
+    }
+}
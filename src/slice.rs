@@ -13,6 +13,14 @@ pub struct ByteView<'a, T: Unaligned + FromBytes + Copy> {
     _pd: PhantomData<&'a T>,
 }
 
+impl<T: Unaligned + FromBytes + Copy> Clone for ByteView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Unaligned + FromBytes + Copy> Copy for ByteView<'_, T> {}
+
 impl<'a, T: Unaligned + FromBytes + Copy> TryFrom<&'a [u8]> for ByteView<'a, T> {
     type Error = crate::Error;
 
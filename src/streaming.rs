@@ -0,0 +1,255 @@
+//! Async adapter that turns a byte source into a [`Stream`] of parsed blocks.
+
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, ready},
+};
+
+use bytes::{Buf as _, Bytes, BytesMut};
+use futures_core::Stream;
+use tokio::io::AsyncRead;
+use tokio_util::io::{StreamReader, poll_read_buf};
+
+use crate::{OwnedBlock, Result, error::ServerException, parse::block::block_len};
+
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Reads Native data from an [`AsyncRead`] and yields one [`OwnedBlock`] per block as soon as
+/// the block has been fully received. The stream ends after the first error.
+pub struct BlockStream<R> {
+    reader: R,
+    buffer: BytesMut,
+
+    /// Do not try to find a block boundary until the buffer grows to this size: rescanning a
+    /// large, still incomplete block after every small read would be quadratic.
+    next_attempt: usize,
+
+    /// Number of blocks and bytes handed out so far, to locate errors in the whole input.
+    num_blocks: usize,
+    consumed: usize,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> BlockStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: BytesMut::new(),
+            next_attempt: 0,
+            num_blocks: 0,
+            consumed: 0,
+            eof: false,
+            done: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<S, E> BlockStream<StreamReader<S, Bytes>>
+where
+    S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+    E: Into<std::io::Error>,
+{
+    /// Reads from a stream of byte chunks, e.g. an HTTP response body.
+    pub fn from_stream(stream: S) -> Self {
+        Self::new(StreamReader::new(stream))
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for BlockStream<R> {
+    type Item = Result<OwnedBlock>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            if this.buffer.is_empty() {
+                if this.eof {
+                    this.done = true;
+                    return Poll::Ready(None);
+                }
+            } else if ServerException::is_start(&this.buffer) {
+                // the server gave up and is sending the exception text; read it to the end
+                if this.eof {
+                    this.done = true;
+                    let err = ServerException::detect(&this.buffer).map_or_else(
+                        || crate::Error::Parse("Malformed server exception".into()),
+                        Into::into,
+                    );
+                    return Poll::Ready(Some(Err(err)));
+                }
+            } else if this.eof || this.buffer.len() >= this.next_attempt {
+                match block_len(&this.buffer) {
+                    Ok(len) => {
+                        let block = OwnedBlock::parse(Arc::<[u8]>::from(&this.buffer[..len]))
+                            .map_err(|err| err.in_block(this.num_blocks, this.consumed));
+                        this.buffer.advance(len);
+                        this.next_attempt = 0;
+                        this.num_blocks += 1;
+                        this.consumed += len;
+                        this.done = block.is_err();
+                        return Poll::Ready(Some(block));
+                    }
+                    Err(err) if err.is_incomplete() && !this.eof => {
+                        this.next_attempt = this.buffer.len().saturating_mul(2);
+                    }
+                    Err(err) => {
+                        this.done = true;
+                        let err = ServerException::detect(&this.buffer).map_or_else(
+                            || err.in_block(this.num_blocks, this.consumed),
+                            Into::into,
+                        );
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                }
+            }
+
+            this.buffer.reserve(READ_CHUNK);
+            match ready!(poll_read_buf(
+                Pin::new(&mut this.reader),
+                cx,
+                &mut this.buffer
+            )) {
+                Ok(0) => this.eof = true,
+                Ok(_) => {}
+                Err(err) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+
+    use testresult::TestResult;
+    use tokio::io::AsyncWriteExt as _;
+
+    use super::*;
+    use crate::{common::load, parse::block::parse_many};
+
+    async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+        poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    fn concat(files: &[&str]) -> std::io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        for file in files {
+            buf.extend(load(file)?);
+        }
+        Ok(buf)
+    }
+
+    #[tokio::test]
+    async fn duplex_small_chunks() -> TestResult {
+        let buf = concat(&[
+            "./testdata/sample.native",
+            "./testdata/json.native",
+            "./testdata/array_lc_string.native",
+        ])?;
+        let expected = parse_many(&buf)?
+            .iter()
+            .map(|block| (block.num_rows, block.col_names.join(",")))
+            .collect::<Vec<_>>();
+
+        let (mut tx, rx) = tokio::io::duplex(1024);
+        let writer = tokio::spawn(async move {
+            for chunk in buf.chunks(777) {
+                tx.write_all(chunk).await?;
+            }
+            tx.shutdown().await
+        });
+
+        let mut stream = BlockStream::new(rx);
+        let mut actual = Vec::new();
+        while let Some(block) = next(&mut stream).await {
+            let block = block?;
+            let block = block.block();
+            actual.push((block.num_rows, block.col_names.join(",")));
+        }
+        writer.await??;
+
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bytes_stream() -> TestResult {
+        let buf = concat(&[
+            "./testdata/plain_strings.native",
+            "./testdata/variant.native",
+        ])?;
+        let num_blocks = parse_many(&buf)?.len();
+
+        let chunks = buf
+            .chunks(5)
+            .map(|chunk| Ok::<_, std::io::Error>(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+        let source = ChunkStream(chunks.into_iter());
+
+        let mut stream = BlockStream::from_stream(source);
+        let mut count = 0;
+        while let Some(block) = next(&mut stream).await {
+            block?;
+            count += 1;
+        }
+        assert_eq!(count, num_blocks);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn truncated_input_fails() -> TestResult {
+        let buf = load("./testdata/plain_strings.native")?;
+        let truncated = &buf[..buf.len() - 3];
+
+        let mut stream = BlockStream::new(truncated);
+        let Some(Err(err)) = next(&mut stream).await else {
+            panic!("expected an error for truncated input");
+        };
+        assert!(err.is_incomplete());
+        assert!(next(&mut stream).await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn server_exception_trailer() -> TestResult {
+        let mut buf = load("./testdata/plain_strings.native")?;
+        let num_blocks = parse_many(&buf)?.len();
+        buf.extend_from_slice(
+            b"Code: 241. DB::Exception: Memory limit (total) exceeded. (MEMORY_LIMIT_EXCEEDED)\n",
+        );
+
+        let mut stream = BlockStream::new(buf.as_slice());
+        for _ in 0..num_blocks {
+            next(&mut stream).await.transpose()?;
+        }
+        let Some(Err(crate::Error::ServerException(exception))) = next(&mut stream).await else {
+            panic!("expected a server exception");
+        };
+        assert_eq!(exception.code, 241);
+        assert_eq!(exception.name.as_deref(), Some("MEMORY_LIMIT_EXCEEDED"));
+        assert!(next(&mut stream).await.is_none());
+        Ok(())
+    }
+
+    struct ChunkStream<I>(I);
+
+    impl<I: Iterator + Unpin> Stream for ChunkStream<I> {
+        type Item = I::Item;
+
+        fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.get_mut().0.next())
+        }
+    }
+}
@@ -0,0 +1,723 @@
+//! ClickHouse text formats: values written the way ClickHouse writes them, and blocks as
+//! `TabSeparated` and `CSV`.
+//!
+//! Scalars are written as ClickHouse prints them: dates as `2024-01-31`, `DateTime` as
+//! `2024-01-31 12:00:00` in the column timezone with the `DateTime64` fraction, decimals with
+//! all the digits of their scale, UUIDs hyphenated and floats as `inf`, `-inf` and `nan` when
+//! not finite. Arrays are written as `[1,2]`, tuples as `(1,'a')`, maps as `{'k':1}` and JSON
+//! objects as nested objects, their elements always in the [`Escaping::Quoted`] form.
+
+use std::{
+    fmt, io,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use uuid::Uuid;
+use zerocopy::little_endian::I128;
+
+use crate::{
+    I256, ParsedBlock, Result, TinyRange,
+    error::Error,
+    json,
+    mark::Mark,
+    types::OffsetIndexPair as _,
+    value::{JsonIterator, NestedIterator, Value},
+};
+
+/// How strings and other text-like values, e.g. dates and UUIDs, are escaped and quoted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escaping {
+    /// As they are, the way `clickhouse-client` shows them; NULL is `NULL`.
+    Raw,
+
+    /// Backslash escapes of `\t`, `\n`, `\\` and the other control characters, and of `'`;
+    /// NULL is `\N`.
+    TabSeparated,
+
+    /// In double quotes, doubling the quotes inside, composite values included; NULL is `\N`.
+    Csv,
+
+    /// In single quotes with backslash escapes, as elements of arrays, tuples and maps are
+    /// written; NULL is `NULL`.
+    Quoted,
+}
+
+/// The row-oriented text format [`write_blocks`] produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    TabSeparated,
+    Csv,
+}
+
+/// The header lines [`write_blocks`] starts with, like the `WithNames` and
+/// `WithNamesAndTypes` variants of the ClickHouse formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Header {
+    #[default]
+    None,
+    Names,
+    NamesAndTypes,
+}
+
+impl Format {
+    const fn escaping(self) -> Escaping {
+        match self {
+            Self::TabSeparated => Escaping::TabSeparated,
+            Self::Csv => Escaping::Csv,
+        }
+    }
+
+    const fn delimiter(self) -> char {
+        match self {
+            Self::TabSeparated => '\t',
+            Self::Csv => ',',
+        }
+    }
+}
+
+/// Writes the rows of all blocks, a line per row. The header, if any, is taken from the first
+/// block; nothing is written without blocks.
+pub fn write_blocks(
+    mut out: impl io::Write,
+    blocks: &[ParsedBlock<'_>],
+    format: Format,
+    header: Header,
+) -> Result<()> {
+    let escaping = format.escaping();
+    let delimiter = format.delimiter();
+    let mut line = String::new();
+
+    if let Some(first) = blocks.first() {
+        if header != Header::None {
+            let names = first.col_names.iter().map(|name| Value::String(name));
+            write_line(&mut line, names, escaping, delimiter)?;
+        }
+        if header == Header::NamesAndTypes {
+            let types = first
+                .types
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            let types = types.iter().map(|typ| Value::String(typ));
+            write_line(&mut line, types, escaping, delimiter)?;
+        }
+        out.write_all(line.as_bytes())?;
+    }
+
+    for block in blocks {
+        for row in 0..block.num_rows {
+            line.clear();
+            let values = block
+                .markers
+                .iter()
+                .map(|mark| column_value(mark, row))
+                .collect::<Result<Vec<_>>>()?;
+            write_line(&mut line, values, escaping, delimiter)?;
+            out.write_all(line.as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_line<'a>(
+    line: &mut String,
+    values: impl IntoIterator<Item = Value<'a>>,
+    escaping: Escaping,
+    delimiter: char,
+) -> Result<()> {
+    for (index, value) in values.into_iter().enumerate() {
+        if index > 0 {
+            line.push(delimiter);
+        }
+        // writing to a `String` never fails by itself
+        write_value(line, &value, escaping).map_err(|_| outside_column())?;
+    }
+    line.push('\n');
+    Ok(())
+}
+
+/// The value of a row of a top-level column.
+pub(crate) fn column_value<'a>(mark: &'a Mark<'a>, row: usize) -> Result<Value<'a>> {
+    match mark.get(row) {
+        // arrays without any elements may have no values column at all
+        Some(Value::Empty) if matches!(mark, Mark::Array(_)) => Ok(Value::StringSlice(&[])),
+        Some(value) => Ok(value),
+        None if matches!(mark, Mark::Variant(_) | Mark::Dynamic(_)) => Ok(Value::Empty),
+        None => Err(Error::IndexOutOfBounds(row, "column")),
+    }
+}
+
+/// The error for a value whose offsets or indices point past the data of its column.
+pub(crate) fn outside_column() -> Error {
+    Error::CorruptedData("Value points outside of its column".to_owned())
+}
+
+/// Writes a single value. Fails only if the writer does or the value points outside of its
+/// column, which a validated block rules out.
+pub fn write_value<W: fmt::Write>(
+    out: &mut W,
+    value: &Value<'_>,
+    escaping: Escaping,
+) -> fmt::Result {
+    write_item(out, Item::from(value.clone()), escaping)
+}
+
+/// A single value. The elements of decimal and `DateTime` arrays have no `Value` of their own.
+pub(crate) enum Item<'a> {
+    Value(Value<'a>),
+
+    /// The unscaled value and the scale.
+    Decimal(Unscaled<'a>, u8),
+
+    /// `DateTime` and `DateTime64`, as ticks of the precision, i.e. seconds for `DateTime`.
+    DateTime {
+        ticks: i64,
+        precision: u8,
+        tz: Tz,
+    },
+}
+
+/// The unscaled value of a decimal, as wide as its column.
+#[derive(Clone, Copy)]
+pub(crate) enum Unscaled<'a> {
+    I32(i32),
+    I64(i64),
+    I128(&'a I128),
+    I256(&'a I256),
+}
+
+impl Unscaled<'_> {
+    /// The decimal digits, with the sign.
+    pub(crate) fn digits(self) -> String {
+        match self {
+            Self::I32(value) => value.to_string(),
+            Self::I64(value) => value.to_string(),
+            Self::I128(value) => value.get().to_string(),
+            Self::I256(value) => wide(&value.0, true),
+        }
+    }
+}
+
+impl<'a> From<Value<'a>> for Item<'a> {
+    fn from(value: Value<'a>) -> Self {
+        match value {
+            Value::Decimal32(index, d) => {
+                Self::Decimal(Unscaled::I32(d.data[index].0.get()), d.precision)
+            }
+            Value::Decimal64(index, d) => {
+                Self::Decimal(Unscaled::I64(d.data[index].0.get()), d.precision)
+            }
+            Value::Decimal128(index, d) => {
+                Self::Decimal(Unscaled::I128(&d.data[index].0), d.precision)
+            }
+            Value::Decimal256(index, d) => {
+                Self::Decimal(Unscaled::I256(&d.data[index].0), d.precision)
+            }
+            Value::DateTime(index, d) => Self::DateTime {
+                ticks: i64::from(d.data[index].0.get()),
+                precision: 0,
+                tz: d.tz,
+            },
+            Value::DateTime64(index, d) => Self::DateTime {
+                ticks: d.data[index].0.get(),
+                precision: d.precision,
+                tz: d.tz,
+            },
+            value => Self::Value(value),
+        }
+    }
+}
+
+pub(crate) fn write_item<W: fmt::Write>(
+    out: &mut W,
+    item: Item<'_>,
+    escaping: Escaping,
+) -> fmt::Result {
+    let value = match item {
+        Item::Decimal(unscaled, scale) => return decimal(out, &unscaled.digits(), scale),
+        Item::DateTime {
+            ticks,
+            precision,
+            tz,
+        } => return string(out, &datetime(ticks, precision, tz), escaping),
+        Item::Value(value) => value,
+    };
+
+    match value {
+        Value::Empty => match escaping {
+            Escaping::Raw | Escaping::Quoted => out.write_str("NULL"),
+            Escaping::TabSeparated | Escaping::Csv => out.write_str("\\N"),
+        },
+        Value::Bool(value) => write!(out, "{value}"),
+        Value::Int8(value) => write!(out, "{value}"),
+        Value::Int16(value) => write!(out, "{value}"),
+        Value::Int32(value) => write!(out, "{value}"),
+        Value::Int64(value) => write!(out, "{value}"),
+        Value::Int128(value) => write!(out, "{}", value.get()),
+        Value::Int256(value) => out.write_str(&wide(&value.0, true)),
+        Value::UInt8(value) => write!(out, "{value}"),
+        Value::UInt16(value) => write!(out, "{value}"),
+        Value::UInt32(value) => write!(out, "{value}"),
+        Value::UInt64(value) => write!(out, "{value}"),
+        Value::UInt128(value) => write!(out, "{}", value.get()),
+        Value::UInt256(value) => out.write_str(&wide(&value.0, false)),
+        Value::Float32(value) => float(out, value),
+        Value::Float64(value) => float(out, value),
+        Value::BFloat16(value) => float(out, value.to_f32()),
+        Value::String(value) | Value::Enum8(value, _) | Value::Enum16(value, _) => {
+            string(out, value, escaping)
+        }
+        // a `fmt::Write` only takes UTF-8
+        Value::Bytes(value) => string(out, &String::from_utf8_lossy(value), escaping),
+        Value::Uuid(value) => {
+            let [hi, lo] = value.0;
+            string(
+                out,
+                &Uuid::from_u64_pair(hi.get(), lo.get()).to_string(),
+                escaping,
+            )
+        }
+        Value::Date(date) | Value::Date32(date) => string(out, &date.to_string(), escaping),
+        Value::Ipv4(ip) => string(out, &ip.to_string(), escaping),
+        Value::Ipv6(ip) => string(out, &Ipv6Addr::from(ip.0).to_string(), escaping),
+        Value::Json { .. } => {
+            let paths = JsonIterator::try_from(value).map_err(|_| fmt::Error)?;
+            let mut json = String::new();
+            json::write_object(&mut json, paths, json::Options::default())?;
+            string(out, &json, escaping)
+        }
+        Value::Tuple { index, mark } => composite(out, escaping, |text| {
+            text.push('(');
+            for (position, column) in mark.values.iter().enumerate() {
+                if position > 0 {
+                    text.push(',');
+                }
+                let value = column.get(index).ok_or(fmt::Error)?;
+                write_item(text, Item::from(value), Escaping::Quoted)?;
+            }
+            text.push(')');
+            Ok(())
+        }),
+        Value::Map { mark, index } => composite(out, escaping, |text| {
+            let (start, end) = mark
+                .offsets
+                .offset_indices(index)
+                .ok()
+                .flatten()
+                .ok_or(fmt::Error)?;
+            text.push('{');
+            for position in start..end {
+                if position > start {
+                    text.push(',');
+                }
+                let key = mark.keys.get(position).ok_or(fmt::Error)?;
+                let value = mark.values.get(position).ok_or(fmt::Error)?;
+                write_item(text, Item::from(key), Escaping::Quoted)?;
+                text.push(':');
+                write_item(text, Item::from(value), Escaping::Quoted)?;
+            }
+            text.push('}');
+            Ok(())
+        }),
+        Value::Nested { .. } => composite(out, escaping, |text| {
+            text.push('[');
+            let rows = NestedIterator::try_from(value).map_err(|_| fmt::Error)?;
+            for (position, row) in rows.enumerate() {
+                if position > 0 {
+                    text.push(',');
+                }
+                text.push('(');
+                for (field, (_, value)) in row.enumerate() {
+                    if field > 0 {
+                        text.push(',');
+                    }
+                    write_item(text, Item::from(value), Escaping::Quoted)?;
+                }
+                text.push(')');
+            }
+            text.push(']');
+            Ok(())
+        }),
+        // every other value is an array
+        value => composite(out, escaping, |text| {
+            text.push('[');
+            let mut first = true;
+            for_each_element(&value, &mut |item| {
+                if !first {
+                    text.push(',');
+                }
+                first = false;
+                write_item(text, item, Escaping::Quoted)
+            })?;
+            text.push(']');
+            Ok(())
+        }),
+    }
+}
+
+/// Calls `f` with every element of an array value.
+pub(crate) fn for_each_element<'a>(
+    value: &Value<'a>,
+    f: &mut dyn FnMut(Item<'a>) -> fmt::Result,
+) -> fmt::Result {
+    fn values<'a, T: 'a>(
+        slice: &'a [T],
+        f: &mut dyn FnMut(Item<'a>) -> fmt::Result,
+        value: impl Fn(&'a T) -> Value<'a>,
+    ) -> fmt::Result {
+        slice
+            .iter()
+            .try_for_each(|element| f(Item::Value(value(element))))
+    }
+
+    fn rows<'a>(
+        range: TinyRange,
+        f: &mut dyn FnMut(Item<'a>) -> fmt::Result,
+        get: impl Fn(usize) -> Option<Value<'a>>,
+    ) -> fmt::Result {
+        std::ops::Range::from(range)
+            .try_for_each(|index| f(Item::from(get(index).ok_or(fmt::Error)?)))
+    }
+
+    match *value {
+        Value::StringSlice(slice) => values(slice, f, |&s| Value::string(s)),
+        Value::BoolSlice(slice) => values(slice, f, |&b| Value::Bool(b != 0)),
+        Value::Int8Slice(slice) => values(slice, f, |&v| Value::Int8(v)),
+        Value::Int16Slice(slice) => values(slice, f, |v| Value::Int16(v.get())),
+        Value::Int32Slice(slice) => values(slice, f, |v| Value::Int32(v.get())),
+        Value::Int64Slice(slice) => values(slice, f, |v| Value::Int64(v.get())),
+        Value::Int128Slice(slice) => values(slice, f, Value::Int128),
+        Value::Int256Slice(slice) => values(slice, f, Value::Int256),
+        Value::UInt8Slice(slice) => values(slice, f, |&v| Value::UInt8(v)),
+        Value::UInt16Slice(slice) => values(slice, f, |v| Value::UInt16(v.get())),
+        Value::UInt32Slice(slice) => values(slice, f, |v| Value::UInt32(v.get())),
+        Value::UInt64Slice(slice) => values(slice, f, |v| Value::UInt64(v.get())),
+        Value::UInt128Slice(slice) => values(slice, f, Value::UInt128),
+        Value::UInt256Slice(slice) => values(slice, f, Value::UInt256),
+        Value::Float32Slice(slice) => values(slice, f, |v| Value::Float32(v.get())),
+        Value::Float64Slice(slice) => values(slice, f, |v| Value::Float64(v.get())),
+        Value::BFloat16Slice(slice) => values(slice, f, |&v| Value::BFloat16(v.into())),
+        Value::UuidSlice(slice) => values(slice, f, Value::Uuid),
+        Value::Date16Slice(slice) => values(slice, f, |&d| Value::Date(NaiveDate::from(d))),
+        Value::Date32Slice(slice) => values(slice, f, |&d| Value::Date32(NaiveDate::from(d))),
+        Value::Ipv4Slice(slice) => values(slice, f, |&ip| Value::Ipv4(Ipv4Addr::from(ip))),
+        Value::Ipv6Slice(slice) => values(slice, f, Value::Ipv6),
+        Value::Decimal32Slice { precision, slice } => slice
+            .iter()
+            .try_for_each(|d| f(Item::Decimal(Unscaled::I32(d.0.get()), precision))),
+        Value::Decimal64Slice { precision, slice } => slice
+            .iter()
+            .try_for_each(|d| f(Item::Decimal(Unscaled::I64(d.0.get()), precision))),
+        Value::Decimal128Slice { precision, slice } => slice
+            .iter()
+            .try_for_each(|d| f(Item::Decimal(Unscaled::I128(&d.0), precision))),
+        Value::Decimal256Slice { precision, slice } => slice
+            .iter()
+            .try_for_each(|d| f(Item::Decimal(Unscaled::I256(&d.0), precision))),
+        Value::DateTime32Slice { tz, slice } => slice.iter().try_for_each(|d| {
+            f(Item::DateTime {
+                ticks: i64::from(d.0.get()),
+                precision: 0,
+                tz,
+            })
+        }),
+        Value::DateTime64Slice {
+            tz,
+            precision,
+            slice,
+        } => slice.iter().try_for_each(|d| {
+            f(Item::DateTime {
+                ticks: d.0.get(),
+                precision,
+                tz,
+            })
+        }),
+        Value::LowCardinalitySlice { range, mark } => rows(range, f, |index| mark.get(index)),
+        Value::ArraySlice { mark, range } => rows(range, f, |index| {
+            // an element without values of its own is an empty array
+            match mark.get(index)? {
+                Value::Empty => Some(Value::StringSlice(&[])),
+                value => Some(value),
+            }
+        }),
+        Value::TupleSlice { mark, range } => {
+            rows(range, f, |index| Some(Value::Tuple { index, mark }))
+        }
+        Value::MapSlice { mark, range } => rows(range, f, |index| Some(Value::Map { mark, index })),
+        Value::NullableSlice { mark, range } => rows(range, f, |index| mark.get(index)),
+        Value::NestedSlice { mark, range } => rows(range, f, |index| mark.get(index)),
+        Value::FixedStringSlice { mark, range } => rows(range, f, |index| mark.get(index)),
+        Value::Enum8Slice { mark, range } => rows(range, f, |index| mark.get(index)),
+        Value::Enum16Slice { mark, range } => rows(range, f, |index| mark.get(index)),
+        Value::JsonSlice { mark, range } => {
+            rows(range, f, |index| Some(Value::Json { mark, index }))
+        }
+        // NULL has no discriminator of its own
+        Value::VariantSlice { mark, range } => rows(range, f, |index| {
+            Some(mark.get(index).unwrap_or(Value::Empty))
+        }),
+        Value::DynamicSlice { mark, range } => rows(range, f, |index| {
+            Some(mark.get(index).unwrap_or(Value::Empty))
+        }),
+        _ => Err(fmt::Error),
+    }
+}
+
+/// The elements of an array value.
+pub(crate) fn elements<'a>(value: &Value<'a>) -> Result<Vec<Item<'a>>> {
+    let mut elements = Vec::new();
+    for_each_element(value, &mut |element| {
+        elements.push(element);
+        Ok(())
+    })
+    .map_err(|_| outside_column())?;
+    Ok(elements)
+}
+
+fn string<W: fmt::Write>(out: &mut W, value: &str, escaping: Escaping) -> fmt::Result {
+    match escaping {
+        Escaping::Raw => out.write_str(value),
+        Escaping::TabSeparated => escape(out, value),
+        Escaping::Quoted => {
+            out.write_char('\'')?;
+            escape(out, value)?;
+            out.write_char('\'')
+        }
+        Escaping::Csv => {
+            out.write_char('"')?;
+            for part in value.split_inclusive('"') {
+                out.write_str(part)?;
+                if part.ends_with('"') {
+                    out.write_char('"')?;
+                }
+            }
+            out.write_char('"')
+        }
+    }
+}
+
+/// Backslash escapes, the same for `TabSeparated` and quoted values.
+pub(crate) fn escape<W: fmt::Write>(out: &mut W, value: &str) -> fmt::Result {
+    let mut rest = value;
+    while let Some(position) = rest.find(['\\', '\'', '\t', '\n', '\r', '\0', '\x08', '\x0c']) {
+        out.write_str(&rest[..position])?;
+        let escaped = match rest.as_bytes()[position] {
+            b'\\' => "\\\\",
+            b'\'' => "\\'",
+            b'\t' => "\\t",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\0' => "\\0",
+            b'\x08' => "\\b",
+            _ => "\\f",
+        };
+        out.write_str(escaped)?;
+        rest = &rest[position + 1..];
+    }
+    out.write_str(rest)
+}
+
+/// Arrays, tuples and maps are written as their text, which CSV quotes as a whole.
+fn composite<W: fmt::Write>(
+    out: &mut W,
+    escaping: Escaping,
+    write: impl FnOnce(&mut String) -> fmt::Result,
+) -> fmt::Result {
+    let mut text = String::new();
+    write(&mut text)?;
+    match escaping {
+        Escaping::Csv => string(out, &text, escaping),
+        Escaping::Raw | Escaping::TabSeparated | Escaping::Quoted => out.write_str(&text),
+    }
+}
+
+fn float<W: fmt::Write, F: Into<f64> + fmt::Display + Copy>(out: &mut W, value: F) -> fmt::Result {
+    let wide: f64 = value.into();
+    if wide.is_nan() {
+        out.write_str("nan")
+    } else if wide.is_infinite() {
+        out.write_str(if wide > 0.0 { "inf" } else { "-inf" })
+    } else {
+        write!(out, "{value}")
+    }
+}
+
+/// `2024-01-31 12:00:00`, followed by as many fractional digits as the precision.
+fn datetime(ticks: i64, precision: u8, tz: Tz) -> String {
+    // a precision no i64 scales to, which the type parser rules out
+    let Some(scale) = 10_i64.checked_pow(u32::from(precision)) else {
+        return ticks.to_string();
+    };
+    let seconds = ticks.div_euclid(scale);
+    let Some(time) = chrono::DateTime::from_timestamp(seconds, 0) else {
+        // beyond what chrono represents, the raw ticks are still informative
+        return ticks.to_string();
+    };
+
+    let time = time.with_timezone(&tz);
+    let time = time.format("%Y-%m-%d %H:%M:%S");
+    if precision == 0 {
+        return time.to_string();
+    }
+
+    let fraction = ticks.rem_euclid(scale);
+    format!("{time}.{fraction:0width$}", width = usize::from(precision))
+}
+
+/// Writes the unscaled `digits` with `scale` of them after the point.
+pub(crate) fn decimal<W: fmt::Write>(out: &mut W, digits: &str, scale: u8) -> fmt::Result {
+    let (sign, digits) = match digits.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", digits),
+    };
+    let scale = usize::from(scale);
+    if scale == 0 {
+        return write!(out, "{sign}{digits}");
+    }
+
+    let padded = format!("{digits:0>width$}", width = scale + 1);
+    let (whole, fraction) = padded.split_at(padded.len() - scale);
+    write!(out, "{sign}{whole}.{fraction}")
+}
+
+/// The decimal digits of a 256-bit little-endian integer.
+pub(crate) fn wide(bytes: &[u8; 32], signed: bool) -> String {
+    let mut limbs = [0_u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+    }
+
+    let negative = signed && bytes[31] & 0x80 != 0;
+    if negative {
+        // two's complement
+        let mut carry = true;
+        for limb in &mut limbs {
+            (*limb, carry) = (!*limb).overflowing_add(u64::from(carry));
+        }
+    }
+
+    let mut digits = Vec::new();
+    while limbs != [0; 4] {
+        let mut remainder = 0_u128;
+        for limb in limbs.iter_mut().rev() {
+            let current = (remainder << 64) | u128::from(*limb);
+            // the remainder is below 10, so the quotient fits into a limb
+            *limb = u64::try_from(current / 10).unwrap_or_default();
+            remainder = current % 10;
+        }
+        digits.push(b'0' + u8::try_from(remainder).unwrap_or_default());
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    if negative {
+        digits.push(b'-');
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{common::load, parse::block::parse_many};
+
+    fn export(name: &str, format: Format, header: Header) -> TestResult<Vec<String>> {
+        let data = load(format!("./testdata/{name}.native"))?;
+        let blocks = parse_many(&data)?;
+        let mut out = Vec::new();
+        write_blocks(&mut out, &blocks, format, header)?;
+        Ok(String::from_utf8(out)?.lines().map(str::to_owned).collect())
+    }
+
+    #[test]
+    fn tab_separated_scalars() -> TestResult {
+        let lines = export(
+            "uuid_and_dates",
+            Format::TabSeparated,
+            Header::NamesAndTypes,
+        )?;
+        assert_eq!(lines[0], "id\tdate\tdate32\tdatetime\tdatetime64");
+        assert_eq!(lines[1], "UUID\tDate\tDate32\tDateTime\tDateTime64(3)");
+        assert_eq!(
+            lines[2],
+            "00000000-0000-0000-0000-000000000001\t2023-01-01\t2023-01-01\t\
+             2023-01-01 12:00:00\t2023-01-01 12:00:00.123"
+        );
+
+        let lines = export("decimal_sample", Format::TabSeparated, Header::None)?;
+        assert_eq!(
+            lines[0],
+            "0\t1.234\t1.234567\t1.234567890123\t1.234567890123456556104338"
+        );
+
+        let lines = export("nullable_string", Format::TabSeparated, Header::Names)?;
+        assert_eq!(lines[..3], ["id\tnstr", "0\thello", "1\t\\N"]);
+        Ok(())
+    }
+
+    #[test]
+    fn composites() -> TestResult {
+        let lines = export("map_sample", Format::TabSeparated, Header::None)?;
+        assert_eq!(lines[0], "0\t{'a':'apple','b':'banana','c':'cherry'}");
+
+        let lines = export("tuple", Format::TabSeparated, Header::None)?;
+        assert_eq!(lines[0], "0\t(1,'a')");
+
+        let lines = export("array_of_nested", Format::TabSeparated, Header::None)?;
+        assert_eq!(lines[0], "0\t[[(1,'Alice'),(2,'Bob')]]");
+        assert_eq!(lines[3], "3\t[[]]");
+
+        let lines = export("array_lc_nullable_string", Format::Csv, Header::Names)?;
+        assert_eq!(lines[0], "\"id\",\"arr\"");
+        assert_eq!(lines[1], "0,\"['apple','banana',NULL]\"");
+        assert_eq!(lines[5], "4,\"[]\"");
+        Ok(())
+    }
+
+    #[test]
+    fn escapes_strings() -> TestResult {
+        let value = Value::String("a\tb\nc\\d'e\"f");
+        let written = |escaping| -> std::result::Result<String, fmt::Error> {
+            let mut out = String::new();
+            write_value(&mut out, &value, escaping)?;
+            Ok(out)
+        };
+
+        assert_eq!(written(Escaping::Raw)?, "a\tb\nc\\d'e\"f");
+        assert_eq!(written(Escaping::TabSeparated)?, "a\\tb\\nc\\\\d\\'e\"f");
+        assert_eq!(written(Escaping::Quoted)?, "'a\\tb\\nc\\\\d\\'e\"f'");
+        assert_eq!(written(Escaping::Csv)?, "\"a\tb\nc\\d'e\"\"f\"");
+        Ok(())
+    }
+
+    #[test]
+    fn formats_numbers() {
+        let mut out = String::new();
+        for value in [
+            Value::Float64(f64::NAN),
+            Value::Float32(f32::NEG_INFINITY),
+            Value::Int64(-7),
+        ] {
+            write_value(&mut out, &value, Escaping::Raw).unwrap_or_default();
+            out.push(' ');
+        }
+        assert_eq!(out, "nan -inf -7 ");
+
+        let mut out = String::new();
+        decimal(&mut out, "-5", 3).unwrap_or_default();
+        assert_eq!(out, "-0.005");
+
+        let minus_one = [0xff; 32];
+        assert_eq!(wide(&minus_one, true), "-1");
+        assert_eq!(wide(&minus_one, false).len(), 78);
+        assert_eq!(wide(&[0; 32], true), "0");
+        assert_eq!(datetime(-1_500, 3, Tz::UTC), "1969-12-31 23:59:58.500");
+        assert_eq!(datetime(-1_500, 64, Tz::UTC), "-1500");
+    }
+}
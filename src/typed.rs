@@ -0,0 +1,378 @@
+//! Reading column values as Rust types.
+
+use core::marker::PhantomData;
+use std::ops::Range;
+
+use zerocopy::little_endian::{F32, F64, I16, I32, I64, I128, U16, U32, U64, U128};
+
+use crate::{
+    ParsedBlock, Result,
+    error::Error,
+    mark::{Mark, Nullable},
+    types::OffsetIndexPair as _,
+    value::Value,
+};
+
+/// A Rust type a single row of a column can be read as, see [`crate::ColumnAccessor::into`].
+///
+/// Every type that converts from a [`Value`] qualifies, e.g. integers, `f64`, `&str`,
+/// `Decimal`, `NaiveDate`, `DateTime<Tz>`, `Uuid`, IP addresses and tuples of them; enums read
+/// as `&str`. `Option<T>` reads a `Nullable` column, giving `None` for nulls, and arrays read
+/// as `Vec<T>` or, without allocating, as [`ArrayElements`].
+pub trait FromColumn<'a>: Sized {
+    fn from_column(mark: &'a Mark<'a>, index: usize) -> Result<Self>;
+}
+
+impl<'a, T> FromColumn<'a> for T
+where
+    T: TryFrom<Value<'a>, Error = Error>,
+{
+    #[inline]
+    fn from_column(mark: &'a Mark<'a>, index: usize) -> Result<Self> {
+        check_row(mark, index)?;
+        let value = mark
+            .get(index)
+            .ok_or(Error::IndexOutOfBounds(index, "column"))?;
+        T::try_from(value)
+    }
+}
+
+impl<'a, T> FromColumn<'a> for Vec<T>
+where
+    T: FromColumn<'a>,
+{
+    fn from_column(mark: &'a Mark<'a>, index: usize) -> Result<Self> {
+        ArrayElements::from_column(mark, index)?.collect()
+    }
+}
+
+/// The elements of a single array, read one by one as `T`.
+pub struct ArrayElements<'a, T> {
+    values: &'a Mark<'a>,
+    range: Range<usize>,
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T> FromColumn<'a> for ArrayElements<'a, T>
+where
+    T: FromColumn<'a>,
+{
+    fn from_column(mark: &'a Mark<'a>, index: usize) -> Result<Self> {
+        let Mark::Array(array) = mark else {
+            return Err(Error::MismatchedType(mark.as_str(), "Array"));
+        };
+        let (start, end) = array
+            .offsets
+            .offset_indices(index)?
+            .ok_or(Error::IndexOutOfBounds(index, "Array"))?;
+        if start > end {
+            return Err(Error::CorruptedData(format!(
+                "Array offsets decrease at row {index}: {start} > {end}"
+            )));
+        }
+
+        Ok(Self {
+            values: &array.values,
+            range: start..end,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, T> Iterator for ArrayElements<'a, T>
+where
+    T: FromColumn<'a>,
+{
+    type Item = Result<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.range.next()?;
+        Some(T::from_column(self.values, index))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for ArrayElements<'a, T> where T: FromColumn<'a> {}
+
+/// A numeric type whose column data can be borrowed as it is stored in the block, see
+/// [`crate::iter_column`].
+pub trait Primitive {
+    /// The little-endian representation of the type in the block, e.g. `F64` for `f64`.
+    type Data: 'static;
+
+    const NAME: &'static str;
+
+    fn data<'a>(mark: &'a Mark<'a>) -> Option<&'a [Self::Data]>;
+}
+
+macro_rules! impl_primitive {
+    ($($ty:ty => $variant:ident($data:ty)),+ $(,)?) => {
+        $(
+            impl Primitive for $ty {
+                type Data = $data;
+
+                const NAME: &'static str = stringify!($ty);
+
+                #[inline]
+                fn data<'a>(mark: &'a Mark<'a>) -> Option<&'a [Self::Data]> {
+                    match mark {
+                        Mark::$variant(bv) => Some(bv.as_slice()),
+                        _ => None,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_primitive!(
+    i8 => Int8(i8),
+    i16 => Int16(I16),
+    i32 => Int32(I32),
+    i64 => Int64(I64),
+    i128 => Int128(I128),
+    u8 => UInt8(u8),
+    u16 => UInt16(U16),
+    u32 => UInt32(U32),
+    u64 => UInt64(U64),
+    u128 => UInt128(U128),
+    f32 => Float32(F32),
+    f64 => Float64(F64),
+);
+
+impl Primitive for bool {
+    type Data = u8;
+
+    const NAME: &'static str = "bool";
+
+    #[inline]
+    fn data<'a>(mark: &'a Mark<'a>) -> Option<&'a [Self::Data]> {
+        match mark {
+            Mark::Bool(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+/// The data of a column in a single block.
+pub struct Chunk<'a, T: Primitive> {
+    pub values: &'a [T::Data],
+
+    /// The null map of a `Nullable` column, `1` marking a null; the value at a null position is
+    /// a default.
+    pub nulls: Option<&'a [u8]>,
+}
+
+impl<'a, T: Primitive> Chunk<'a, T> {
+    fn new(mark: &'a Mark<'a>) -> Result<Self> {
+        let (data, nulls) = match mark {
+            Mark::Nullable(Nullable { mask, data }) => (data.as_ref(), Some(*mask)),
+            mark => (mark, None),
+        };
+        let values = T::data(data).ok_or(Error::MismatchedType(mark.as_str(), T::NAME))?;
+        if let Some(nulls) = nulls
+            && nulls.len() != values.len()
+        {
+            return Err(Error::CorruptedData(format!(
+                "Null map has {} rows, values have {}",
+                nulls.len(),
+                values.len()
+            )));
+        }
+
+        Ok(Self { values, nulls })
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn is_null(&self, index: usize) -> bool {
+        self.nulls.is_some_and(|nulls| nulls.get(index) == Some(&1))
+    }
+}
+
+/// One column of a sequence of blocks, as a [`Chunk`] per block.
+pub struct Chunks<'a, 'n, T> {
+    blocks: std::slice::Iter<'a, ParsedBlock<'a>>,
+    name: &'n str,
+    _marker: PhantomData<T>,
+}
+
+impl<'a, 'n, T> Chunks<'a, 'n, T> {
+    pub(crate) fn new(blocks: &'a [ParsedBlock<'a>], name: &'n str) -> Self {
+        Self {
+            blocks: blocks.iter(),
+            name,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Primitive> Iterator for Chunks<'a, '_, T> {
+    type Item = Result<Chunk<'a, T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block = self.blocks.next()?;
+        let Some(mark) = block.column(self.name) else {
+            return Some(Err(Error::MissingColumn(self.name.to_owned())));
+        };
+        Some(Chunk::new(mark))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.blocks.size_hint()
+    }
+}
+
+/// Rows past the end of the column fail instead of reaching the indexing code, some of which
+/// assumes a valid index.
+fn check_row(mark: &Mark<'_>, index: usize) -> Result<()> {
+    match mark.num_rows() {
+        Some(num_rows) if index >= num_rows => Err(Error::IndexOutOfBounds(index, "column")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{common::load, iter_blocks, iter_column, parse::block::parse_many};
+
+    #[test]
+    fn arrays_and_nulls() -> TestResult {
+        let buf = load("./testdata/array_nullable_int64.native")?;
+        let blocks = parse_many(&buf)?;
+        for row in iter_blocks(&blocks) {
+            let id = row.get("id").unwrap().into::<i64>()?;
+            let arr = row.get("arr").unwrap();
+            let values = arr.into::<Vec<Option<i64>>>()?;
+            match id {
+                1 => assert_eq!(values, [None, Some(4), Some(5)]),
+                5 => assert_eq!(values, [Some(10)]),
+                _ => {}
+            }
+
+            let lazy = arr.into::<ArrayElements<'_, Option<i64>>>()?;
+            assert_eq!(lazy.len(), values.len());
+            assert_eq!(lazy.collect::<Result<Vec<_>>>()?, values);
+
+            // nulls and arrays do not read as plain integers
+            assert_eq!(arr.into::<Vec<i64>>().is_err(), values.contains(&None));
+            assert!(arr.into::<i64>().is_err());
+        }
+
+        let buf = load("./testdata/array_in_array_in64.native")?;
+        let blocks = parse_many(&buf)?;
+        let row = iter_blocks(&blocks)
+            .find(|row| {
+                row.get("id")
+                    .is_some_and(|id| id.into::<i64>().ok() == Some(2))
+            })
+            .unwrap();
+        let arr = row.get("arr").unwrap().into::<Vec<Vec<u64>>>()?;
+        assert_eq!(arr, [vec![9], vec![10, 11]]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn decimals_and_out_of_range_rows() -> TestResult {
+        let buf = load("./testdata/decimal_sample.native")?;
+        let blocks = parse_many(&buf)?;
+        let row = iter_blocks(&blocks)
+            .find(|row| {
+                row.get("id")
+                    .is_some_and(|id| id.into::<i64>().ok() == Some(0))
+            })
+            .unwrap();
+        assert_eq!(
+            row.get("d32").unwrap().into::<Decimal>()?,
+            Decimal::new(1234, 3)
+        );
+        assert_eq!(
+            row.get("d64").unwrap().into::<Option<Decimal>>()?,
+            Some(Decimal::new(1_234_567, 6))
+        );
+
+        let mark = blocks[0].column("d32").unwrap();
+        assert!(matches!(
+            Decimal::from_column(mark, blocks[0].num_rows),
+            Err(Error::IndexOutOfBounds(..))
+        ));
+        assert!(Vec::<i64>::from_column(mark, 0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn column_chunks() -> TestResult {
+        let buf = load("./testdata/float_sample.native")?;
+        let blocks = parse_many(&buf)?;
+        let values = iter_column::<f64>(&blocks, "f64")
+            .map(|chunk| {
+                let chunk = chunk?;
+                assert!(chunk.nulls.is_none());
+                Ok(chunk
+                    .values
+                    .iter()
+                    .map(|value| value.get())
+                    .collect::<Vec<_>>())
+            })
+            .collect::<Result<Vec<_>>>()?
+            .concat();
+        let mut sorted = values.clone();
+        sorted.sort_by(f64::total_cmp);
+        assert_eq!(
+            sorted,
+            [
+                0.577_215_664_901_532_9,
+                std::f64::consts::SQRT_2,
+                std::f64::consts::E,
+                std::f64::consts::PI
+            ]
+        );
+
+        assert!(iter_column::<f32>(&blocks, "f64").all(|chunk| chunk.is_err()));
+        assert!(matches!(
+            iter_column::<f64>(&blocks, "missing").next(),
+            Some(Err(Error::MissingColumn(_)))
+        ));
+
+        // nullable columns come with their null map, matching the row-wise view
+        let buf = load("./testdata/sample.native")?;
+        let blocks = parse_many(&buf)?;
+        let mut rows = iter_blocks(&blocks);
+        for chunk in iter_column::<i32>(&blocks, "n_i32") {
+            let chunk = chunk?;
+            assert!(chunk.nulls.is_some());
+            for (index, value) in chunk.values.iter().enumerate() {
+                let expected = rows
+                    .next()
+                    .unwrap()
+                    .get("n_i32")
+                    .unwrap()
+                    .into::<Option<i32>>()?;
+                assert_eq!(chunk.is_null(index), expected.is_none());
+                if let Some(expected) = expected {
+                    assert_eq!(value.get(), expected);
+                }
+            }
+        }
+        assert!(rows.next().is_none());
+
+        Ok(())
+    }
+}
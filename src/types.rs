@@ -1,3 +1,8 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Write as _},
+};
+
 pub use chrono_tz::Tz;
 use zerocopy::little_endian::U64;
 
@@ -8,6 +13,7 @@ use crate::{
     },
     parse::typ::parse_type,
     slice::ByteView,
+    text,
 };
 
 pub type Offsets<'a> = ByteView<'a, U64>;
@@ -60,26 +66,26 @@ impl OffsetIndexPair for Offsets<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct MapHeader<'a> {
     pub key: TypeHeader<'a>,
     pub value: TypeHeader<'a>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct DynamicHeader<'a> {
     pub types: Vec<Type<'a>>,
     pub headers: Vec<TypeHeader<'a>>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct JsonHeader<'a> {
     pub paths: Vec<&'a str>,
     pub col_headers: Vec<JsonColumnHeader<'a>>,
     pub type_headers: Vec<TypeHeader<'a>>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum TypeHeader<'a> {
     Empty,
     Tuple(Vec<TypeHeader<'a>>),
@@ -93,68 +99,89 @@ pub enum TypeHeader<'a> {
 }
 
 impl<'a> TypeHeader<'a> {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TypeHeader::Empty => "Empty",
+            TypeHeader::Tuple(_) => "Tuple",
+            TypeHeader::Json(_) => "Json",
+            TypeHeader::Map(_) => "Map",
+            TypeHeader::Variant(_) => "Variant",
+            TypeHeader::Array(_) => "Array",
+            TypeHeader::Dynamic(_) => "Dynamic",
+            TypeHeader::Nullable(_) => "Nullable",
+            TypeHeader::Nested(_) => "Nested",
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> crate::Error {
+        crate::Error::CorruptedData(format!(
+            "Expected {expected} type header, got {}",
+            self.as_str()
+        ))
+    }
+
     #[inline]
-    pub fn into_array(self) -> TypeHeader<'a> {
+    pub fn into_array(self) -> crate::Result<TypeHeader<'a>> {
         match self {
-            TypeHeader::Array(inner) => *inner,
-            e => unreachable!("Unexpected type header: {e:?}"),
+            TypeHeader::Array(inner) => Ok(*inner),
+            e => Err(e.unexpected("Array")),
         }
     }
 
     #[inline]
-    pub fn into_tuple(self) -> Vec<TypeHeader<'a>> {
+    pub fn into_tuple(self) -> crate::Result<Vec<TypeHeader<'a>>> {
         match self {
-            TypeHeader::Tuple(t) => t,
-            e => unreachable!("Unexpected type header: {e:?}"),
+            TypeHeader::Tuple(t) => Ok(t),
+            e => Err(e.unexpected("Tuple")),
         }
     }
 
     #[inline]
-    pub fn into_map(self) -> MapHeader<'a> {
+    pub fn into_map(self) -> crate::Result<MapHeader<'a>> {
         match self {
-            TypeHeader::Map(map) => *map,
-            e => unreachable!("Unexpected type header: {e:?}"),
+            TypeHeader::Map(map) => Ok(*map),
+            e => Err(e.unexpected("Map")),
         }
     }
 
     #[inline]
-    pub fn into_variant(self) -> Vec<TypeHeader<'a>> {
+    pub fn into_variant(self) -> crate::Result<Vec<TypeHeader<'a>>> {
         match self {
-            TypeHeader::Variant(variants) => variants,
-            e => unreachable!("Unexpected type header: {e:?}"),
+            TypeHeader::Variant(variants) => Ok(variants),
+            e => Err(e.unexpected("Variant")),
         }
     }
 
     #[inline]
-    pub fn into_json(self) -> JsonHeader<'a> {
+    pub fn into_json(self) -> crate::Result<JsonHeader<'a>> {
         match self {
-            TypeHeader::Json(json) => *json,
-            e => unreachable!("Unexpected type header: {e:?}"),
+            TypeHeader::Json(json) => Ok(*json),
+            e => Err(e.unexpected("Json")),
         }
     }
 
     #[inline]
-    pub fn into_dynamic(self) -> DynamicHeader<'a> {
+    pub fn into_dynamic(self) -> crate::Result<DynamicHeader<'a>> {
         match self {
-            TypeHeader::Dynamic(d) => *d,
-            e => unreachable!("Unexpected type header: {e:?}"),
+            TypeHeader::Dynamic(d) => Ok(*d),
+            e => Err(e.unexpected("Dynamic")),
         }
     }
 
     #[inline]
-    pub fn into_nested(self) -> Vec<TypeHeader<'a>> {
+    pub fn into_nested(self) -> crate::Result<Vec<TypeHeader<'a>>> {
         match self {
-            TypeHeader::Nested(n) => n,
-            e => unreachable!("Unexpected type header: {e:?}"),
+            TypeHeader::Nested(n) => Ok(n),
+            e => Err(e.unexpected("Nested")),
         }
     }
 
     #[inline]
-    pub fn into_nullable(self) -> TypeHeader<'a> {
+    pub fn into_nullable(self) -> crate::Result<TypeHeader<'a>> {
         match self {
-            TypeHeader::Nullable(inner) => *inner,
-            TypeHeader::Empty => TypeHeader::Empty,
-            e => unreachable!("Unexpected type header: {e:?}"),
+            TypeHeader::Nullable(inner) => Ok(*inner),
+            TypeHeader::Empty => Ok(TypeHeader::Empty),
+            e => Err(e.unexpected("Nullable")),
         }
     }
 }
@@ -181,10 +208,11 @@ pub enum Type<'a> {
 
     BFloat16,
 
-    Decimal32(u8),
-    Decimal64(u8),
-    Decimal128(u8),
-    Decimal256(u8),
+    /// `Decimal(P, S)`: the precision and the scale.
+    Decimal32(u8, u8),
+    Decimal64(u8, u8),
+    Decimal128(u8, u8),
+    Decimal256(u8, u8),
 
     String,
     FixedString(usize),
@@ -220,14 +248,15 @@ pub enum Type<'a> {
     /// MultiLineString is multiple lines stored as an array of LineString: Array(LineString).
     MultiLineString,
 
-    Enum8(Vec<(&'a str, i8)>),
-    Enum16(Vec<(&'a str, i16)>),
+    Enum8(Vec<(Cow<'a, str>, i8)>),
+    Enum16(Vec<(Cow<'a, str>, i16)>),
 
     LowCardinality(Box<Type<'a>>),
 
     Array(Box<Type<'a>>),
 
-    Tuple(Vec<Type<'a>>),
+    /// The element types, with the element names of a named tuple, empty otherwise.
+    Tuple(Vec<Type<'a>>, Box<[&'a str]>),
 
     Nullable(Box<Type<'a>>),
 
@@ -255,7 +284,7 @@ impl<'a> Type<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct JsonColumnHeader<'a> {
     pub path_version: u64,
     pub max_types: usize,
@@ -297,10 +326,10 @@ impl<'a> Type<'a> {
 
             Self::Uuid => Some(16),
 
-            Self::Decimal32(_) => Some(4),
-            Self::Decimal64(_) => Some(8),
-            Self::Decimal128(_) => Some(16),
-            Self::Decimal256(_) => Some(32),
+            Self::Decimal32(..) => Some(4),
+            Self::Decimal64(..) => Some(8),
+            Self::Decimal128(..) => Some(16),
+            Self::Decimal256(..) => Some(32),
 
             Self::FixedString(size) => Some(*size),
 
@@ -329,7 +358,7 @@ impl<'a> Type<'a> {
 
             // we can calculate the size for the tuple of fixed size types, but still we'll need
             // to parse nested columns later, so it's not worth it
-            Self::Tuple(_) => None,
+            Self::Tuple(..) => None,
 
             // TODO: is it always variable?
             Self::Variant(_) => None,
@@ -373,20 +402,20 @@ impl<'a> Type<'a> {
             Type::Float32 => Mark::Float32(ByteView::try_from(data)?),
             Type::Float64 => Mark::Float64(ByteView::try_from(data)?),
             Type::BFloat16 => Mark::BFloat16(ByteView::try_from(data)?),
-            Type::Decimal32(precision) => Mark::Decimal32(Decimal32 {
-                precision,
+            Type::Decimal32(_, scale) => Mark::Decimal32(Decimal32 {
+                precision: scale,
                 data: ByteView::try_from(data)?,
             }),
-            Type::Decimal64(precision) => Mark::Decimal64(Decimal64 {
-                precision,
+            Type::Decimal64(_, scale) => Mark::Decimal64(Decimal64 {
+                precision: scale,
                 data: ByteView::try_from(data)?,
             }),
-            Type::Decimal128(precision) => Mark::Decimal128(Decimal128 {
-                precision,
+            Type::Decimal128(_, scale) => Mark::Decimal128(Decimal128 {
+                precision: scale,
                 data: ByteView::try_from(data)?,
             }),
-            Type::Decimal256(precision) => Mark::Decimal256(Decimal256 {
-                precision,
+            Type::Decimal256(_, scale) => Mark::Decimal256(Decimal256 {
+                precision: scale,
                 data: ByteView::try_from(data)?,
             }),
             Type::FixedString(size) => Mark::FixedString(FixedString { size, data }),
@@ -414,9 +443,112 @@ impl<'a> Type<'a> {
                 data: ByteView::try_from(data)?,
             }),
 
-            _ => unimplemented!("Const size is not implemented for type: {:?}", self),
+            _ => {
+                return Err(crate::Error::NotImplemented(format!(
+                    "Const size is not implemented for type: {self:?}"
+                )));
+            }
         };
 
         Ok(mark)
     }
 }
+
+/// Formats the type the way ClickHouse spells it, so the output can be parsed back.
+impl Display for Type<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list<T: Display>(f: &mut fmt::Formatter<'_>, name: &str, items: &[T]) -> fmt::Result {
+            write!(f, "{name}(")?;
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{item}")?;
+            }
+            f.write_str(")")
+        }
+
+        fn enum_variants<T: Display>(
+            f: &mut fmt::Formatter<'_>,
+            name: &str,
+            variants: &[(Cow<'_, str>, T)],
+        ) -> fmt::Result {
+            let variants = variants
+                .iter()
+                .map(|(name, value)| {
+                    let mut variant = String::from("'");
+                    text::escape(&mut variant, name)?;
+                    write!(variant, "' = {value}")?;
+                    Ok(variant)
+                })
+                .collect::<Result<Vec<_>, fmt::Error>>()?;
+            list(f, name, &variants)
+        }
+
+        match self {
+            Self::Bool => f.write_str("Bool"),
+            Self::Int8 => f.write_str("Int8"),
+            Self::Int16 => f.write_str("Int16"),
+            Self::Int32 => f.write_str("Int32"),
+            Self::Int64 => f.write_str("Int64"),
+            Self::Int128 => f.write_str("Int128"),
+            Self::Int256 => f.write_str("Int256"),
+            Self::UInt8 => f.write_str("UInt8"),
+            Self::UInt16 => f.write_str("UInt16"),
+            Self::UInt32 => f.write_str("UInt32"),
+            Self::UInt64 => f.write_str("UInt64"),
+            Self::UInt128 => f.write_str("UInt128"),
+            Self::UInt256 => f.write_str("UInt256"),
+            Self::Float32 => f.write_str("Float32"),
+            Self::Float64 => f.write_str("Float64"),
+            Self::BFloat16 => f.write_str("BFloat16"),
+            Self::Decimal32(precision, scale)
+            | Self::Decimal64(precision, scale)
+            | Self::Decimal128(precision, scale)
+            | Self::Decimal256(precision, scale) => write!(f, "Decimal({precision}, {scale})"),
+            Self::String => f.write_str("String"),
+            Self::FixedString(size) => write!(f, "FixedString({size})"),
+            Self::Uuid => f.write_str("UUID"),
+            Self::Date => f.write_str("Date"),
+            Self::Date32 => f.write_str("Date32"),
+            Self::DateTime(Tz::UTC) => f.write_str("DateTime"),
+            Self::DateTime(tz) => write!(f, "DateTime('{tz}')"),
+            Self::DateTime64(precision, Tz::UTC) => write!(f, "DateTime64({precision})"),
+            Self::DateTime64(precision, tz) => write!(f, "DateTime64({precision}, '{tz}')"),
+            Self::Ipv4 => f.write_str("IPv4"),
+            Self::Ipv6 => f.write_str("IPv6"),
+            Self::Point => f.write_str("Point"),
+            Self::Ring => f.write_str("Ring"),
+            Self::Polygon => f.write_str("Polygon"),
+            Self::MultiPolygon => f.write_str("MultiPolygon"),
+            Self::LineString => f.write_str("LineString"),
+            Self::MultiLineString => f.write_str("MultiLineString"),
+            Self::Enum8(variants) => enum_variants(f, "Enum8", variants),
+            Self::Enum16(variants) => enum_variants(f, "Enum16", variants),
+            Self::LowCardinality(inner) => write!(f, "LowCardinality({inner})"),
+            Self::Array(inner) => write!(f, "Array({inner})"),
+            Self::Tuple(items, names) if names.is_empty() => list(f, "Tuple", items),
+            Self::Tuple(items, names) => {
+                let items = names
+                    .iter()
+                    .zip(items)
+                    .map(|(name, typ)| format!("{name} {typ}"))
+                    .collect::<Vec<_>>();
+                list(f, "Tuple", &items)
+            }
+            Self::Nullable(inner) => write!(f, "Nullable({inner})"),
+            Self::Map(key, value) => write!(f, "Map({key}, {value})"),
+            Self::Variant(items) => list(f, "Variant", items),
+            Self::Nested(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|field| format!("{} {}", field.name, field.typ))
+                    .collect::<Vec<_>>();
+                list(f, "Nested", &fields)
+            }
+            Self::Dynamic => f.write_str("Dynamic"),
+            Self::Json => f.write_str("JSON"),
+            Self::SharedVariant => f.write_str("SharedVariant"),
+        }
+    }
+}
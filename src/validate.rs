@@ -0,0 +1,445 @@
+//! Structural validation of decoded blocks.
+//!
+//! Decoding only checks what it needs to find the end of a column, while the accessors trust
+//! the data they index into. [`crate::ParsedBlock::validate`] walks every marker and collects
+//! the broken invariants, so a corrupt file can be rejected before it is ingested.
+
+use std::fmt::{self, Display};
+
+use crate::{
+    mark::{Array, Dynamic, Json, LowCardinality, Map, Mark, Variant},
+    types::Offsets,
+};
+
+const NULL_DISCRIMINATOR: u8 = 255;
+
+/// A single broken invariant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation<'a> {
+    /// Name of the top-level column.
+    pub column: &'a str,
+
+    /// Path to the offending part of the column, starting with the column name, e.g.
+    /// `value.element.null_map`.
+    pub path: String,
+
+    /// Row within the (possibly nested) part of the column at `path`; `None` if the violation
+    /// is not tied to a single row.
+    pub row: Option<usize>,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The marker holds a different number of rows than its parent implies.
+    RowCount { expected: usize, actual: usize },
+
+    /// An array or map offset is smaller than the previous one.
+    OffsetDecreasing { previous: u64, offset: u64 },
+
+    /// An array or map offset points past the end of the nested column.
+    OffsetOutOfBounds { offset: u64, len: usize },
+
+    /// A LowCardinality index, or a Variant/Dynamic row offset, points past the end of the
+    /// column it indexes into.
+    IndexOutOfBounds { index: usize, len: usize },
+
+    /// An Enum value that is not one of the declared variants.
+    UnknownEnumValue(i16),
+
+    /// A Variant or Dynamic discriminator without a matching type.
+    InvalidDiscriminator {
+        discriminator: usize,
+        num_types: usize,
+    },
+
+    /// A Nullable mask byte other than 0 or 1.
+    InvalidNullMask(u8),
+
+    /// A Bool byte other than 0 or 1.
+    InvalidBool(u8),
+}
+
+impl Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RowCount { expected, actual } => {
+                write!(f, "expected {expected} rows, got {actual}")
+            }
+            Self::OffsetDecreasing { previous, offset } => {
+                write!(
+                    f,
+                    "offset {offset} is less than the previous offset {previous}"
+                )
+            }
+            Self::OffsetOutOfBounds { offset, len } => {
+                write!(f, "offset {offset} is out of bounds for {len} nested rows")
+            }
+            Self::IndexOutOfBounds { index, len } => {
+                write!(f, "index {index} is out of bounds for {len} rows")
+            }
+            Self::UnknownEnumValue(value) => write!(f, "unknown enum value {value}"),
+            Self::InvalidDiscriminator {
+                discriminator,
+                num_types,
+            } => write!(
+                f,
+                "discriminator {discriminator} is out of bounds for {num_types} types"
+            ),
+            Self::InvalidNullMask(byte) => write!(f, "null mask byte {byte} is neither 0 nor 1"),
+            Self::InvalidBool(byte) => write!(f, "bool byte {byte} is neither 0 nor 1"),
+        }
+    }
+}
+
+impl Display for Violation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.row {
+            Some(row) => write!(f, "{} (row {row}): {}", self.path, self.kind),
+            None => write!(f, "{}: {}", self.path, self.kind),
+        }
+    }
+}
+
+/// Validates a top-level column holding `num_rows` rows.
+pub(crate) fn column<'a>(name: &'a str, mark: &Mark<'_>, num_rows: usize) -> Vec<Violation<'a>> {
+    let mut validator = Validator {
+        column: name,
+        path: name.to_owned(),
+        violations: Vec::new(),
+    };
+    validator.mark(mark, num_rows);
+    validator.violations
+}
+
+struct Validator<'a> {
+    column: &'a str,
+    path: String,
+    violations: Vec<Violation<'a>>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, row: Option<usize>, kind: ViolationKind) {
+        self.violations.push(Violation {
+            column: self.column,
+            path: self.path.clone(),
+            row,
+            kind,
+        });
+    }
+
+    fn nested(&mut self, segment: &str, validate: impl FnOnce(&mut Self)) {
+        let len = self.path.len();
+        self.path.push('.');
+        self.path.push_str(segment);
+        validate(self);
+        self.path.truncate(len);
+    }
+
+    fn mark(&mut self, mark: &Mark<'_>, num_rows: usize) {
+        if let Some(actual) = mark.num_rows()
+            && actual != num_rows
+        {
+            self.report(
+                None,
+                ViolationKind::RowCount {
+                    expected: num_rows,
+                    actual,
+                },
+            );
+        }
+
+        match mark {
+            Mark::Bool(bytes) => {
+                for (row, &byte) in bytes.iter().enumerate() {
+                    if byte > 1 {
+                        self.report(Some(row), ViolationKind::InvalidBool(byte));
+                    }
+                }
+            }
+            Mark::Enum8(e) => {
+                for (row, &value) in e.data.as_slice().iter().enumerate() {
+                    if !e.variants.iter().any(|(_, id)| *id == value) {
+                        self.report(Some(row), ViolationKind::UnknownEnumValue(value.into()));
+                    }
+                }
+            }
+            Mark::Enum16(e) => {
+                for (row, value) in e.data.as_slice().iter().enumerate() {
+                    let value = value.get();
+                    if !e.variants.iter().any(|(_, id)| *id == value) {
+                        self.report(Some(row), ViolationKind::UnknownEnumValue(value));
+                    }
+                }
+            }
+            Mark::Ring(inner)
+            | Mark::Polygon(inner)
+            | Mark::MultiPolygon(inner)
+            | Mark::LineString(inner)
+            | Mark::MultiLineString(inner) => self.mark(inner, num_rows),
+            Mark::LowCardinality(lc) => self.low_cardinality(lc),
+            Mark::Array(Array { offsets, values }) => {
+                let len = self.offsets(offsets, values);
+                self.nested("element", |v| v.mark(values, len));
+            }
+            Mark::Map(Map {
+                offsets,
+                keys,
+                values,
+            }) => {
+                let len = self.offsets(offsets, keys);
+                self.nested("keys", |v| v.mark(keys, len));
+                self.nested("values", |v| v.mark(values, len));
+            }
+            Mark::Tuple(tuple) => {
+                for (index, value) in tuple.values.iter().enumerate() {
+                    self.nested(&(index + 1).to_string(), |v| v.mark(value, num_rows));
+                }
+            }
+            Mark::Nullable(n) => {
+                self.nested("null_map", |v| {
+                    for (row, &byte) in n.mask.iter().enumerate() {
+                        if byte > 1 {
+                            v.report(Some(row), ViolationKind::InvalidNullMask(byte));
+                        }
+                    }
+                });
+                self.mark(&n.data, n.mask.len());
+            }
+            Mark::Variant(variant) => self.variant(variant),
+            Mark::Dynamic(dynamic) => self.dynamic(dynamic),
+            Mark::Nested(n) => self.mark(&n.array_of_tuples, num_rows),
+            Mark::Json(json) => self.json(json),
+            _ => {}
+        }
+    }
+
+    /// Checks that the offsets grow and stay within the nested column. Returns the number of
+    /// nested rows the offsets describe.
+    fn offsets(&mut self, offsets: &Offsets<'_>, values: &Mark<'_>) -> usize {
+        let len = values.num_rows().unwrap_or_default();
+        let mut previous = 0;
+        for (row, offset) in offsets.as_slice().iter().enumerate() {
+            let offset = offset.get();
+            if offset < previous {
+                self.report(
+                    Some(row),
+                    ViolationKind::OffsetDecreasing { previous, offset },
+                );
+            }
+            if usize::try_from(offset).map_or(true, |offset| offset > len) {
+                self.report(Some(row), ViolationKind::OffsetOutOfBounds { offset, len });
+            }
+            previous = offset;
+        }
+
+        usize::try_from(previous).unwrap_or(usize::MAX)
+    }
+
+    fn low_cardinality(&mut self, lc: &LowCardinality<'_>) {
+        let keys = lc.additional_keys.as_deref().unwrap_or(&Mark::Empty);
+        let len = keys.num_rows().unwrap_or_default();
+        let num_rows = lc.indices.num_rows().unwrap_or_default();
+
+        self.nested("indices", |v| {
+            for row in 0..num_rows {
+                let index = lc.value_index(row).unwrap_or(usize::MAX);
+                if index >= len {
+                    v.report(Some(row), ViolationKind::IndexOutOfBounds { index, len });
+                }
+            }
+        });
+        self.nested("dictionary", |v| v.mark(keys, len));
+    }
+
+    fn variant(&mut self, variant: &Variant<'_>) {
+        let num_types = variant.types.len();
+        let mut counts = vec![0; num_types];
+        for (row, (&discriminator, &offset)) in variant
+            .discriminators
+            .iter()
+            .zip(&variant.offsets)
+            .enumerate()
+        {
+            if discriminator == NULL_DISCRIMINATOR {
+                continue;
+            }
+            let discriminator = usize::from(discriminator);
+            self.discriminator(row, discriminator, offset, &variant.types, &mut counts);
+        }
+
+        for (index, (mark, count)) in variant.types.iter().zip(counts).enumerate() {
+            self.nested(&index.to_string(), |v| v.mark(mark, count));
+        }
+    }
+
+    fn dynamic(&mut self, dynamic: &Dynamic<'_>) {
+        let num_types = dynamic.columns.len();
+        let mut counts = vec![0; num_types];
+        for (row, (&discriminator, &offset)) in dynamic
+            .discriminators
+            .iter()
+            .zip(&dynamic.offsets)
+            .enumerate()
+        {
+            self.discriminator(row, discriminator, offset, &dynamic.columns, &mut counts);
+        }
+
+        for (index, (mark, count)) in dynamic.columns.iter().zip(counts).enumerate() {
+            // the shared variant is not decoded
+            if !matches!(mark, Mark::Empty) {
+                self.nested(&index.to_string(), |v| v.mark(mark, count));
+            }
+        }
+    }
+
+    fn discriminator(
+        &mut self,
+        row: usize,
+        discriminator: usize,
+        offset: usize,
+        types: &[Mark<'_>],
+        counts: &mut [usize],
+    ) {
+        let Some(mark) = types.get(discriminator) else {
+            self.report(
+                Some(row),
+                ViolationKind::InvalidDiscriminator {
+                    discriminator,
+                    num_types: types.len(),
+                },
+            );
+            return;
+        };
+        counts[discriminator] += 1;
+
+        if matches!(mark, Mark::Empty) {
+            return;
+        }
+        let len = mark.num_rows().unwrap_or_default();
+        if offset >= len {
+            self.report(
+                Some(row),
+                ViolationKind::IndexOutOfBounds { index: offset, len },
+            );
+        }
+    }
+
+    fn json(&mut self, json: &Json<'_>) {
+        for (path, header) in json.paths.iter().zip(&json.headers) {
+            let count = header
+                .discriminators
+                .iter()
+                .filter(|&&discriminator| discriminator != NULL_DISCRIMINATOR)
+                .count();
+            self.nested(path, |v| v.mark(&header.mark, count));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+    use zerocopy::IntoBytes as _;
+
+    use super::*;
+    use crate::{
+        ParsedBlock,
+        common::load_testdata,
+        mark::{Enum8, Nullable},
+        parse::block::parse_many,
+        types::Type,
+    };
+
+    #[test]
+    fn testdata_is_valid() -> TestResult {
+        for (path, buf) in load_testdata()? {
+            for block in parse_many(&buf)? {
+                assert_eq!(block.validate(), vec![], "{}", path.display());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn reports_every_violation() -> TestResult {
+        let offsets = [1u64, 3, 2, 9].map(zerocopy::little_endian::U64::new);
+        let mask = [0u8, 2, 1, 0];
+        let enum_data = [1i8, 7, 2, 1];
+
+        let block = ParsedBlock::new(
+            vec!["flag", "value", "kind"],
+            vec![
+                Type::Bool,
+                Type::Array(Box::new(Type::Nullable(Box::new(Type::UInt8)))),
+                Type::Enum8(vec![("a".into(), 1), ("b".into(), 2)]),
+            ],
+            vec![
+                Mark::Bool(&[0, 1, 3, 1]),
+                Mark::Array(Array {
+                    offsets: offsets.as_bytes().try_into()?,
+                    values: Box::new(Mark::Nullable(Nullable {
+                        mask: &mask,
+                        data: Box::new(Mark::UInt8([1, 2, 3, 4].as_slice().try_into()?)),
+                    })),
+                }),
+                Mark::Enum8(Enum8 {
+                    variants: vec![("a".into(), 1), ("b".into(), 2)],
+                    data: enum_data.as_bytes().try_into()?,
+                }),
+            ],
+            4,
+        );
+
+        let violation = |column, path: &str, row, kind| Violation {
+            column,
+            path: path.to_owned(),
+            row,
+            kind,
+        };
+        assert_eq!(
+            block.validate(),
+            vec![
+                violation("flag", "flag", Some(2), ViolationKind::InvalidBool(3)),
+                violation(
+                    "value",
+                    "value",
+                    Some(2),
+                    ViolationKind::OffsetDecreasing {
+                        previous: 3,
+                        offset: 2
+                    }
+                ),
+                violation(
+                    "value",
+                    "value",
+                    Some(3),
+                    ViolationKind::OffsetOutOfBounds { offset: 9, len: 4 }
+                ),
+                violation(
+                    "value",
+                    "value.element",
+                    None,
+                    ViolationKind::RowCount {
+                        expected: 9,
+                        actual: 4
+                    }
+                ),
+                violation(
+                    "value",
+                    "value.element.null_map",
+                    Some(1),
+                    ViolationKind::InvalidNullMask(2)
+                ),
+                violation("kind", "kind", Some(1), ViolationKind::UnknownEnumValue(7)),
+            ]
+        );
+        assert_eq!(
+            block.validate()[4].to_string(),
+            "value.element.null_map (row 1): null mask byte 2 is neither 0 nor 1"
+        );
+
+        Ok(())
+    }
+}
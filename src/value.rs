@@ -1,10 +1,13 @@
 use core::{convert::TryFrom, marker::PhantomData};
 use std::{
+    borrow::Cow,
+    fmt,
     hint::unreachable_unchecked,
     net::{Ipv4Addr, Ipv6Addr},
     ops::Range,
 };
 
+use chrono::NaiveDate;
 use chrono_tz::Tz;
 use half::bf16;
 use rust_decimal::Decimal;
@@ -20,9 +23,14 @@ use crate::{
         Array, DateTime, DateTime64, Decimal32, Decimal64, Decimal128, Decimal256, Dynamic, Enum8,
         Enum16, FixedString, Json, LowCardinality, Map, Mark, Nested, Nullable, Tuple, Variant,
     },
+    text::{self, Escaping, Item, Unscaled},
     types::{OffsetIndexPair as _, Offsets},
 };
 
+mod compare;
+
+/// A value of a column, borrowed from its block. Values compare and hash the way ClickHouse
+/// compares them, see the `compare` module for the rules.
 #[derive(Debug, Clone)]
 pub enum Value<'a> {
     Empty,
@@ -47,6 +55,13 @@ pub enum Value<'a> {
     Decimal128(usize, &'a Decimal128<'a>),
     Decimal256(usize, &'a Decimal256<'a>),
     String(&'a str),
+
+    /// A `String` or `FixedString` value that is not UTF-8.
+    Bytes(&'a [u8]),
+
+    /// An enum value, by its name and its number.
+    Enum8(&'a str, i8),
+    Enum16(&'a str, i16),
     Uuid(&'a UuidData),
     Date(chrono::NaiveDate),
     Date32(chrono::NaiveDate),
@@ -55,7 +70,8 @@ pub enum Value<'a> {
     Ipv4(Ipv4Addr),
     Ipv6(&'a Ipv6Data),
 
-    StringSlice(&'a [&'a str]),
+    /// Strings, not necessarily UTF-8.
+    StringSlice(&'a [&'a [u8]]),
     BoolSlice(&'a [u8]),
     Int8Slice(&'a [i8]),
     Int16Slice(&'a [I16]),
@@ -174,7 +190,19 @@ pub enum Value<'a> {
     },
 }
 
-impl Value<'_> {
+impl<'a> Value<'a> {
+    /// A string, or bytes if they are not UTF-8.
+    pub fn string(bytes: &'a [u8]) -> Self {
+        std::str::from_utf8(bytes).map_or(Value::Bytes(bytes), Value::String)
+    }
+
+    /// Copies the value out of its block, elements of arrays, tuples, maps and objects
+    /// included. Fails only if the value points outside of its column, which a validated
+    /// block rules out.
+    pub fn to_owned(&self) -> Result<OwnedValue, Error> {
+        owned(Item::from(self.clone()))
+    }
+
     const fn as_str(&self) -> &'static str {
         match self {
             Value::Empty => "Empty",
@@ -199,6 +227,9 @@ impl Value<'_> {
             Value::Decimal128(_, _) => "Decimal128",
             Value::Decimal256(_, _) => "Decimal256",
             Value::String(_) => "String",
+            Value::Bytes(_) => "Bytes",
+            Value::Enum8(..) => "Enum8",
+            Value::Enum16(..) => "Enum16",
             Value::Uuid(_) => "Uuid",
             Value::Date(_) | Value::Date32(_) => "Date",
             Value::DateTime(_, _) => "DateTime",
@@ -253,6 +284,14 @@ impl Value<'_> {
     }
 }
 
+/// Written the way `clickhouse-client` shows it: strings, dates and UUIDs as they are at the top
+/// level and quoted inside arrays, tuples and maps, NULL as `NULL`, see [`crate::text`].
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text::write_value(f, self, Escaping::Raw)
+    }
+}
+
 macro_rules! impl_try_from_value {
     ($variant:ident, $ty:ty) => {
         impl<'a> TryFrom<Value<'a>> for $ty {
@@ -269,8 +308,20 @@ macro_rules! impl_try_from_value {
     };
 }
 
-impl_try_from_value!(String, &'a str);
-impl_try_from_value!(StringSlice, &'a [&'a str]);
+/// Strings, and the names of enum values.
+impl<'a> TryFrom<Value<'a>> for &'a str {
+    type Error = Error;
+
+    #[inline(always)]
+    fn try_from(value: Value<'a>) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(v) | Value::Enum8(v, _) | Value::Enum16(v, _) => Ok(v),
+            other => Err(Error::MismatchedType(other.as_str(), "&'a str")),
+        }
+    }
+}
+
+impl_try_from_value!(StringSlice, &'a [&'a [u8]]);
 
 impl_try_from_value!(Int8Slice, &'a [i8]);
 impl_try_from_value!(Int16Slice, &'a [I16]);
@@ -1294,7 +1345,7 @@ impl<'a> Iterator for FixedStringSliceIterator<'a> {
         }
 
         let slice = &self.mark.data[start..end].rtrim_zeros();
-        Some(unsafe { std::str::from_utf8_unchecked(slice) })
+        std::str::from_utf8(slice).ok()
     }
 
     #[inline(always)]
@@ -1306,7 +1357,7 @@ impl<'a> Iterator for FixedStringSliceIterator<'a> {
 impl ExactSizeIterator for FixedStringSliceIterator<'_> {}
 
 pub struct Enum8SliceIterator<'a> {
-    variants: &'a [(&'a str, i8)],
+    variants: &'a [(Cow<'a, str>, i8)],
     data: std::slice::Iter<'a, i8>,
 }
 
@@ -1335,7 +1386,7 @@ impl<'a> Iterator for Enum8SliceIterator<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         let value = self.data.next()?;
         if let Ok(index) = self.variants.binary_search_by_key(value, |(_, id)| *id) {
-            return Some(self.variants[index].0);
+            return Some(&self.variants[index].0);
         }
 
         None
@@ -1350,7 +1401,7 @@ impl<'a> Iterator for Enum8SliceIterator<'a> {
 impl ExactSizeIterator for Enum8SliceIterator<'_> {}
 
 pub struct Enum16SliceIterator<'a> {
-    variants: &'a [(&'a str, i16)],
+    variants: &'a [(Cow<'a, str>, i16)],
     data: std::slice::Iter<'a, I16>,
 }
 
@@ -1379,7 +1430,7 @@ impl<'a> Iterator for Enum16SliceIterator<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         let value = self.data.next()?.get();
         if let Ok(index) = self.variants.binary_search_by_key(&value, |(_, id)| *id) {
-            return Some(self.variants[index].0);
+            return Some(&self.variants[index].0);
         }
 
         None
@@ -1566,3 +1617,548 @@ impl<'a> Iterator for DynamicSliceIterator<'a> {
 }
 
 impl ExactSizeIterator for DynamicSliceIterator<'_> {}
+
+/// A [`Value`] copied out of its block, so it can outlive the input, be sent to another thread
+/// or be kept as a key.
+///
+/// Values compare, order and hash by the same key as the [`Value`]s they are copied from, see
+/// the `compare` module for the rules.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[expect(clippy::module_name_repetitions)]
+pub enum OwnedValue {
+    Null,
+    Bool(bool),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    Int128(i128),
+
+    /// Little-endian two's complement.
+    Int256([u8; 32]),
+    UInt8(u8),
+    UInt16(u16),
+    UInt32(u32),
+    UInt64(u64),
+    UInt128(u128),
+
+    /// Little-endian.
+    UInt256([u8; 32]),
+    Float32(f32),
+    Float64(f64),
+    BFloat16(bf16),
+    Decimal32 {
+        unscaled: i32,
+        scale: u8,
+    },
+    Decimal64 {
+        unscaled: i64,
+        scale: u8,
+    },
+    Decimal128 {
+        unscaled: i128,
+        scale: u8,
+    },
+    Decimal256 {
+        /// Little-endian two's complement.
+        unscaled: [u8; 32],
+        scale: u8,
+    },
+
+    /// Strings, and `FixedString` without the trailing zeros.
+    String(String),
+
+    /// A `String` or `FixedString` value that is not UTF-8.
+    Bytes(Vec<u8>),
+
+    /// An enum value, by its name and its number.
+    Enum8(String, i8),
+    Enum16(String, i16),
+    Uuid(Uuid),
+    Date(NaiveDate),
+    Date32(NaiveDate),
+
+    /// `DateTime` and `DateTime64`, as ticks of the precision since the epoch, i.e. seconds for
+    /// `DateTime`.
+    DateTime {
+        ticks: i64,
+        precision: u8,
+        tz: Tz,
+    },
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    Array(Vec<OwnedValue>),
+    Tuple(Vec<OwnedValue>),
+    Map(Vec<(OwnedValue, OwnedValue)>),
+    Nested {
+        names: Vec<String>,
+        rows: Vec<Vec<OwnedValue>>,
+    },
+
+    /// The paths of the object with a value, in the column order.
+    Json(Vec<(String, OwnedValue)>),
+}
+
+/// Copies an element of a value out of its block.
+fn owned(item: Item<'_>) -> Result<OwnedValue, Error> {
+    let value = match item {
+        Item::Decimal(unscaled, scale) => {
+            return Ok(match unscaled {
+                Unscaled::I32(unscaled) => OwnedValue::Decimal32 { unscaled, scale },
+                Unscaled::I64(unscaled) => OwnedValue::Decimal64 { unscaled, scale },
+                Unscaled::I128(unscaled) => OwnedValue::Decimal128 {
+                    unscaled: unscaled.get(),
+                    scale,
+                },
+                Unscaled::I256(unscaled) => OwnedValue::Decimal256 {
+                    unscaled: unscaled.0,
+                    scale,
+                },
+            });
+        }
+        Item::DateTime {
+            ticks,
+            precision,
+            tz,
+        } => {
+            return Ok(OwnedValue::DateTime {
+                ticks,
+                precision,
+                tz,
+            });
+        }
+        Item::Value(value) => value,
+    };
+
+    Ok(match value {
+        Value::Empty => OwnedValue::Null,
+        Value::Bool(value) => OwnedValue::Bool(value),
+        Value::Int8(value) => OwnedValue::Int8(value),
+        Value::Int16(value) => OwnedValue::Int16(value),
+        Value::Int32(value) => OwnedValue::Int32(value),
+        Value::Int64(value) => OwnedValue::Int64(value),
+        Value::Int128(value) => OwnedValue::Int128(value.get()),
+        Value::Int256(value) => OwnedValue::Int256(value.0),
+        Value::UInt8(value) => OwnedValue::UInt8(value),
+        Value::UInt16(value) => OwnedValue::UInt16(value),
+        Value::UInt32(value) => OwnedValue::UInt32(value),
+        Value::UInt64(value) => OwnedValue::UInt64(value),
+        Value::UInt128(value) => OwnedValue::UInt128(value.get()),
+        Value::UInt256(value) => OwnedValue::UInt256(value.0),
+        Value::Float32(value) => OwnedValue::Float32(value),
+        Value::Float64(value) => OwnedValue::Float64(value),
+        Value::BFloat16(value) => OwnedValue::BFloat16(value),
+        Value::String(value) => OwnedValue::String(value.to_owned()),
+        Value::Bytes(value) => OwnedValue::Bytes(value.to_vec()),
+        Value::Enum8(name, value) => OwnedValue::Enum8(name.to_owned(), value),
+        Value::Enum16(name, value) => OwnedValue::Enum16(name.to_owned(), value),
+        Value::Uuid(&value) => OwnedValue::Uuid(value.into()),
+        Value::Date(value) => OwnedValue::Date(value),
+        Value::Date32(value) => OwnedValue::Date32(value),
+        Value::Ipv4(value) => OwnedValue::Ipv4(value),
+        Value::Ipv6(&value) => OwnedValue::Ipv6(value.into()),
+        Value::Tuple { index, mark } => OwnedValue::Tuple(
+            mark.values
+                .iter()
+                .map(|column| {
+                    column
+                        .get(index)
+                        .ok_or(Error::IndexOutOfBounds(index, "Tuple"))?
+                        .to_owned()
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Map { mark, index } => {
+            let (start, end) = mark
+                .offsets
+                .offset_indices(index)?
+                .ok_or(Error::IndexOutOfBounds(index, "Map"))?;
+            OwnedValue::Map(
+                (start..end)
+                    .map(|position| {
+                        let key = mark
+                            .keys
+                            .get(position)
+                            .ok_or(Error::IndexOutOfBounds(position, "Map keys"))?;
+                        let value = mark
+                            .values
+                            .get(position)
+                            .ok_or(Error::IndexOutOfBounds(position, "Map values"))?;
+                        Ok((key.to_owned()?, value.to_owned()?))
+                    })
+                    .collect::<Result<_, Error>>()?,
+            )
+        }
+        Value::Nested { mark, .. } => OwnedValue::Nested {
+            names: mark.col_names.iter().map(ToString::to_string).collect(),
+            rows: NestedIterator::try_from(value)?
+                .map(|row| row.map(|(_, value)| value.to_owned()).collect())
+                .collect::<Result<_, _>>()?,
+        },
+        Value::Json { .. } => OwnedValue::Json(
+            JsonIterator::try_from(value)?
+                .map(|(path, value)| Ok((path.to_owned(), value.to_owned()?)))
+                .collect::<Result<_, Error>>()?,
+        ),
+        // every other value is an array
+        value => OwnedValue::Array(
+            text::elements(&value)?
+                .into_iter()
+                .map(owned)
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        hash::{BuildHasher as _, BuildHasherDefault, DefaultHasher, Hash},
+    };
+
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{
+        common::{load, load_testdata},
+        parse::block::parse_many,
+        text::column_value,
+    };
+
+    fn owned_rows(path: &str) -> TestResult<Vec<Vec<OwnedValue>>> {
+        let data = load(path)?;
+        let mut rows = Vec::new();
+        for block in parse_many(&data)? {
+            for row in 0..block.num_rows {
+                let values = block
+                    .markers
+                    .iter()
+                    .map(|mark| column_value(mark, row)?.to_owned())
+                    .collect::<Result<_, _>>()?;
+                rows.push(values);
+            }
+        }
+        Ok(rows)
+    }
+
+    fn hash_of(value: &impl Hash) -> u64 {
+        BuildHasherDefault::<DefaultHasher>::default().hash_one(value)
+    }
+
+    #[test]
+    fn materializes_testdata() -> TestResult {
+        for (path, data) in load_testdata()? {
+            for block in parse_many(&data)? {
+                for mark in &block.markers {
+                    let mut previous = None;
+                    for row in 0..block.num_rows {
+                        let value = column_value(mark, row)?;
+                        let owned = value.to_owned()?;
+                        // the copy keeps the key of the value
+                        assert_eq!(hash_of(&value), hash_of(&owned), "{}", path.display());
+                        if let Some((previous_value, previous_owned)) = &previous {
+                            assert_eq!(
+                                value.cmp(previous_value),
+                                owned.cmp(previous_owned),
+                                "{}",
+                                path.display()
+                            );
+                        }
+                        previous = Some((value, owned));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn families() -> TestResult {
+        let row = &owned_rows("./testdata/enums_sample.native")?[0];
+        assert!(
+            matches!(&row[1..], [OwnedValue::Enum8(red, 11), OwnedValue::Enum16(foo, 2000)]
+                if red == "Red" && foo == "Foo"),
+            "{row:?}"
+        );
+
+        let row = &owned_rows("./testdata/decimal_sample.native")?[0];
+        assert!(
+            matches!(
+                &row[1..],
+                [
+                    OwnedValue::Decimal32 {
+                        unscaled: 1234,
+                        scale: 3
+                    },
+                    OwnedValue::Decimal64 {
+                        unscaled: 1_234_567,
+                        scale: 6
+                    },
+                    OwnedValue::Decimal128 {
+                        unscaled: 1_234_567_890_123,
+                        scale: 12
+                    },
+                    OwnedValue::Decimal256 { scale: 24, .. },
+                ]
+            ),
+            "{row:?}"
+        );
+
+        let row = &owned_rows("./testdata/ip_sample.native")?[0];
+        let ip6 = "2001:db8::ff00:42:8329".parse::<Ipv6Addr>()?;
+        assert!(
+            matches!(&row[1..], [OwnedValue::Ipv4(ip4), OwnedValue::Ipv6(ip)]
+                if *ip4 == Ipv4Addr::new(100, 64, 0, 2)
+                    && *ip == ip6),
+            "{row:?}"
+        );
+
+        let row = &owned_rows("./testdata/uuid_and_dates.native")?[0];
+        let date = NaiveDate::from_ymd_opt(2023, 1, 1).ok_or("no date")?;
+        assert!(
+            matches!(
+                &row[..],
+                [
+                    OwnedValue::Uuid(uuid),
+                    OwnedValue::Date(left),
+                    OwnedValue::Date32(right),
+                    OwnedValue::DateTime { precision: 0, .. },
+                    OwnedValue::DateTime {
+                        ticks: 1_672_574_400_123,
+                        precision: 3,
+                        tz: Tz::UTC
+                    },
+                ] if uuid.as_u128() == 1 && *left == date && *right == date
+            ),
+            "{row:?}"
+        );
+
+        let wide = I256([0xFF; 32]);
+        assert!(matches!(
+            Value::Int256(&wide).to_owned()?,
+            OwnedValue::Int256(bytes) if bytes == [0xFF; 32]
+        ));
+        assert!(matches!(
+            Value::Float64(f64::NAN).to_owned()?,
+            OwnedValue::Float64(value) if value.is_nan()
+        ));
+        assert!(matches!(
+            Value::BFloat16(bf16::ONE).to_owned()?,
+            OwnedValue::BFloat16(value) if value == bf16::ONE
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn composites() -> TestResult {
+        let string = |value: &str| OwnedValue::String(value.to_owned());
+
+        let rows = owned_rows("./testdata/map_sample.native")?;
+        assert_eq!(
+            rows[0][1],
+            OwnedValue::Map(vec![
+                (string("a"), string("apple")),
+                (string("b"), string("banana")),
+                (string("c"), string("cherry")),
+            ])
+        );
+
+        let rows = owned_rows("./testdata/simple_nested.native")?;
+        let OwnedValue::Nested {
+            names,
+            rows: nested,
+        } = &rows[0][1]
+        else {
+            panic!("not Nested: {:?}", rows[0][1]);
+        };
+        assert_eq!(names, &["child_id", "child_name"]);
+        assert_eq!(
+            nested,
+            &[
+                vec![OwnedValue::UInt64(1), string("Alice")],
+                vec![OwnedValue::UInt64(2), string("Bob")],
+            ]
+        );
+        assert!(matches!(&rows[3][1], OwnedValue::Nested { rows, .. } if rows.is_empty()));
+
+        let rows = owned_rows("./testdata/enums_array_sample.native")?;
+        assert!(
+            matches!(&rows[0][1], OwnedValue::Array(values)
+                if matches!(&values[..], [OwnedValue::Enum8(red, 11), OwnedValue::Enum8(green, 2)]
+                    if red == "Red" && green == "Green")),
+            "{:?}",
+            rows[0][1]
+        );
+
+        let rows = owned_rows("./testdata/nullable_string_array.native")?;
+        let arrays = rows.iter().map(|row| &row[1]).collect::<Vec<_>>();
+        assert!(
+            arrays
+                .iter()
+                .any(|array| matches!(array, OwnedValue::Array(values)
+                if values.contains(&OwnedValue::Null))),
+            "{arrays:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn display() -> TestResult {
+        let data = load("./testdata/map_sample.native")?;
+        let blocks = parse_many(&data)?;
+        let value = blocks[0].markers[1].get(0).ok_or("no row")?;
+        assert_eq!(value.to_string(), "{'a':'apple','b':'banana','c':'cherry'}");
+
+        let data = load("./testdata/array_of_nested.native")?;
+        let blocks = parse_many(&data)?;
+        let value = column_value(&blocks[0].markers[1], 0)?;
+        assert_eq!(value.to_string(), "[[(1,'Alice'),(2,'Bob')]]");
+
+        let numbers = [1, -2].map(I32::new);
+        assert_eq!(Value::Int32Slice(&numbers).to_string(), "[1,-2]");
+        assert_eq!(Value::String("it's").to_string(), "it's");
+        assert_eq!(Value::StringSlice(&[b"it's"]).to_string(), "['it\\'s']");
+        assert_eq!(Value::Float64(f64::NEG_INFINITY).to_string(), "-inf");
+        assert_eq!(Value::Empty.to_string(), "NULL");
+        Ok(())
+    }
+
+    #[test]
+    fn keys() {
+        let values = [
+            OwnedValue::Float64(f64::NAN),
+            OwnedValue::Float32(-f32::NAN),
+            OwnedValue::Float64(-0.0),
+            OwnedValue::Float64(0.0),
+            OwnedValue::Int8(0),
+            OwnedValue::Null,
+        ];
+        assert_eq!(values.iter().collect::<HashSet<_>>().len(), 3);
+
+        // the same numbers and instants, whatever their types
+        let same = [
+            [
+                OwnedValue::Decimal32 {
+                    unscaled: 125,
+                    scale: 2,
+                },
+                OwnedValue::Decimal128 {
+                    unscaled: 1250,
+                    scale: 3,
+                },
+                OwnedValue::Float64(1.25),
+            ],
+            [
+                OwnedValue::Enum8("one".to_owned(), 1),
+                OwnedValue::UInt128(1),
+                OwnedValue::Bool(true),
+            ],
+            [
+                OwnedValue::DateTime {
+                    ticks: 1_700_000_000,
+                    precision: 0,
+                    tz: Tz::UTC,
+                },
+                OwnedValue::DateTime {
+                    ticks: 1_700_000_000_000,
+                    precision: 3,
+                    tz: Tz::Europe__Berlin,
+                },
+                OwnedValue::DateTime {
+                    ticks: 1_700_000_000_000_000_000,
+                    precision: 9,
+                    tz: Tz::Asia__Tokyo,
+                },
+            ],
+        ];
+        for values in &same {
+            for value in values {
+                assert_eq!(value, &values[0]);
+                assert_eq!(hash_of(value), hash_of(&values[0]), "{value:?}");
+            }
+        }
+        assert_ne!(
+            OwnedValue::Enum8("one".to_owned(), 1),
+            OwnedValue::String("one".to_owned())
+        );
+        assert_ne!(
+            OwnedValue::Decimal64 {
+                unscaled: 1,
+                scale: 1
+            },
+            OwnedValue::Float64(0.1)
+        );
+
+        let minus_one = OwnedValue::Int256([0xFF; 32]);
+        let mut one = [0; 32];
+        one[0] = 1;
+        let mut sorted = [
+            OwnedValue::Null,
+            OwnedValue::String("a".to_owned()),
+            OwnedValue::Int256(one),
+            minus_one.clone(),
+            OwnedValue::Bool(false),
+        ];
+        sorted.sort();
+        assert!(matches!(
+            &sorted[..],
+            [
+                OwnedValue::Int256(first),
+                OwnedValue::Bool(false),
+                OwnedValue::Int256(second),
+                OwnedValue::String(_),
+                OwnedValue::Null,
+            ] if *first == [0xFF; 32] && *second == one
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() -> TestResult {
+        // values equal by their keys may differ, so the copies are compared by their fields
+        fn round_trip(values: &Vec<Vec<OwnedValue>>) -> TestResult {
+            let json = serde_json::to_string(values)?;
+            let back = serde_json::from_str::<Vec<Vec<OwnedValue>>>(&json)?;
+            assert_eq!(format!("{back:?}"), format!("{values:?}"));
+            Ok(())
+        }
+
+        for path in [
+            "./testdata/sample.native",
+            "./testdata/uuid_and_dates.native",
+            "./testdata/decimal_sample.native",
+            "./testdata/enums_sample.native",
+            "./testdata/ip_sample.native",
+            "./testdata/map_sample.native",
+            "./testdata/simple_nested.native",
+            "./testdata/nullable_string_array.native",
+        ] {
+            round_trip(&owned_rows(path)?)?;
+        }
+
+        let values = vec![vec![
+            OwnedValue::Null,
+            OwnedValue::Bool(true),
+            OwnedValue::Int128(i128::MIN),
+            OwnedValue::Int256([0xFF; 32]),
+            OwnedValue::UInt128(u128::MAX),
+            OwnedValue::Float32(-0.0),
+            OwnedValue::BFloat16(bf16::ONE),
+            OwnedValue::Decimal256 {
+                unscaled: [0x80; 32],
+                scale: 76,
+            },
+            OwnedValue::Enum16("minus".to_owned(), -1),
+            OwnedValue::DateTime {
+                ticks: -1,
+                precision: 6,
+                tz: Tz::America__New_York,
+            },
+            OwnedValue::Tuple(vec![OwnedValue::Ipv6(Ipv6Addr::LOCALHOST)]),
+            OwnedValue::Json(vec![("a.b".to_owned(), OwnedValue::Int64(1))]),
+        ]];
+        round_trip(&values)
+    }
+}
@@ -0,0 +1,763 @@
+//! Comparison, ordering and hashing of [`Value`]s and [`OwnedValue`]s, following the ClickHouse
+//! rules.
+//!
+//! Every value is reduced to one key, which equality, ordering and hashing all come from, so
+//! they agree with each other and values can be keys of hash and ordered maps.
+//!
+//! - Integers, `Bool`, decimals, floats and enums compare by their numbers, exactly, whatever
+//!   their widths and scales: `1`, `true`, `1.00` and an enum value numbered 1 are equal, while
+//!   the `Float64` nearest to 0.1 is not the decimal `0.1`. Enums never equal their names.
+//! - -0.0 equals 0.0, and NaN equals NaN and sorts after every other number.
+//! - NULL equals NULL, as in `DISTINCT` and `GROUP BY`, and sorts after every other value, as
+//!   with the default `NULLS LAST`.
+//! - `DateTime` and `DateTime64` compare by instant, whatever their precisions and timezones.
+//! - Arrays, tuples, maps (as arrays of key-value tuples), `Nested` (as arrays of tuples) and JSON
+//!   objects (as arrays of path-value tuples) compare lexicographically.
+//! - Values of kinds ClickHouse has no common type for, e.g. a string and a number, are never
+//!   equal, and sort by kind: numbers, strings, UUIDs, dates, instants, IPv4 and IPv6 addresses,
+//!   arrays, tuples, and NULL last.
+
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use uuid::Uuid;
+
+use super::{JsonIterator, NestedIterator, OwnedValue, Value};
+use crate::{
+    conv,
+    error::Error,
+    text::{self, Item, Unscaled},
+    types::OffsetIndexPair as _,
+};
+
+/// Powers of ten exactly representable as `f64`.
+const POWERS_OF_TEN: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+];
+
+/// A value reduced to what it is compared by, in the order of the kinds.
+enum Cell<'a> {
+    /// An integer or a decimal that fits `i128`: the unscaled value and the scale.
+    Exact(i128, u8),
+    UInt128(u128),
+
+    /// A 256-bit integer or decimal, little-endian, signed or not, and the scale.
+    Wide(&'a [u8; 32], bool, u8),
+    Float(f64),
+    Bytes(&'a [u8]),
+    Uuid(u128),
+    Date(chrono::NaiveDate),
+
+    /// Seconds since the epoch and nanoseconds.
+    Instant(i64, u32),
+    Ipv4(u32),
+    Ipv6([u8; 16]),
+    Array(Vec<Cell<'a>>),
+    Tuple(Vec<Cell<'a>>),
+    Null,
+
+    /// A value pointing outside of its column, which a validated block rules out.
+    Invalid,
+}
+
+/// An integer or a decimal: the unscaled value and the scale.
+#[derive(Clone, Copy)]
+struct Exact<'a> {
+    unscaled: Int<'a>,
+    scale: u8,
+}
+
+#[derive(Clone, Copy)]
+enum Int<'a> {
+    Small(i128),
+    Unsigned(u128),
+    Wide(&'a [u8; 32], bool),
+}
+
+impl Int<'_> {
+    fn digits(self) -> String {
+        match self {
+            Self::Small(value) => value.to_string(),
+            Self::Unsigned(value) => value.to_string(),
+            Self::Wide(bytes, signed) => text::wide(bytes, signed),
+        }
+    }
+}
+
+impl Cell<'_> {
+    /// Position of the kind, ordering values of different kinds.
+    const fn rank(&self) -> u8 {
+        match self {
+            Self::Exact(..) | Self::UInt128(_) | Self::Wide(..) | Self::Float(_) => 0,
+            Self::Bytes(_) => 1,
+            Self::Uuid(_) => 2,
+            Self::Date(_) => 3,
+            Self::Instant(..) => 4,
+            Self::Ipv4(_) => 5,
+            Self::Ipv6(_) => 6,
+            Self::Array(_) => 7,
+            Self::Tuple(_) => 8,
+            Self::Null => 9,
+            Self::Invalid => 10,
+        }
+    }
+
+    fn exact(&self) -> Option<Exact<'_>> {
+        let (unscaled, scale) = match *self {
+            Self::Exact(unscaled, scale) => (Int::Small(unscaled), scale),
+            Self::UInt128(unscaled) => (Int::Unsigned(unscaled), 0),
+            Self::Wide(bytes, signed, scale) => (Int::Wide(bytes, signed), scale),
+            _ => return None,
+        };
+        Some(Exact { unscaled, scale })
+    }
+}
+
+impl Exact<'_> {
+    /// Whether the value is an integer `f64` holds exactly.
+    fn fits_f64(self) -> bool {
+        matches!(self.unscaled, Int::Small(value)
+            if self.scale == 0 && value.unsigned_abs() <= 1 << f64::MANTISSA_DIGITS)
+    }
+
+    /// The nearest `f64`.
+    fn to_f64(self) -> f64 {
+        if let Int::Small(value) = self.unscaled
+            && value.unsigned_abs() <= 1 << f64::MANTISSA_DIGITS
+            && let Some(&power) = POWERS_OF_TEN.get(usize::from(self.scale))
+        {
+            // both are exact, so the quotient is correctly rounded
+            #[expect(clippy::cast_precision_loss)]
+            return value as f64 / power;
+        }
+        format!("{}e-{}", self.unscaled.digits(), self.scale)
+            .parse()
+            .unwrap_or(f64::NAN)
+    }
+
+    fn cmp(self, other: Exact<'_>) -> Ordering {
+        if let (Int::Small(left), Int::Small(right)) = (self.unscaled, other.unscaled) {
+            let rescaled = match self.scale.cmp(&other.scale) {
+                Ordering::Equal => Some((left, right)),
+                Ordering::Less => rescale(left, other.scale - self.scale).map(|left| (left, right)),
+                Ordering::Greater => {
+                    rescale(right, self.scale - other.scale).map(|right| (left, right))
+                }
+            };
+            if let Some((left, right)) = rescaled {
+                return left.cmp(&right);
+            }
+        }
+        cmp_digits(
+            &self.unscaled.digits(),
+            usize::from(self.scale),
+            &other.unscaled.digits(),
+            usize::from(other.scale),
+        )
+    }
+
+    /// Compares with the exact value of a float.
+    fn cmp_float(self, float: f64) -> Ordering {
+        if float.is_nan() {
+            return Ordering::Less;
+        }
+        // rounding is monotonic, so it keeps a strict order
+        match self.to_f64().partial_cmp(&float) {
+            Some(Ordering::Equal) if self.fits_f64() => return Ordering::Equal,
+            Some(Ordering::Equal) | None => {}
+            Some(ordering) => return ordering,
+        }
+        // every finite float is a decimal of at most 1074 fractional digits
+        let text = format!("{float:.1074}");
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let fraction = fraction.trim_end_matches('0');
+        cmp_digits(
+            &self.unscaled.digits(),
+            usize::from(self.scale),
+            &format!("{whole}{fraction}"),
+            fraction.len(),
+        )
+    }
+}
+
+/// The float with all zeros and all NaNs made one, so that `total_cmp` orders floats by their
+/// numbers, NaN last.
+fn canonical(value: f64) -> f64 {
+    if value == 0.0 {
+        0.0
+    } else if value.is_nan() {
+        f64::NAN
+    } else {
+        value
+    }
+}
+
+fn rescale(value: i128, by: u8) -> Option<i128> {
+    value.checked_mul(10_i128.checked_pow(u32::from(by))?)
+}
+
+/// Compares decimals given as the digits of their unscaled values, with the signs.
+fn cmp_digits(left: &str, left_scale: usize, right: &str, right_scale: usize) -> Ordering {
+    fn split(digits: &str, scale: usize) -> (bool, String, String) {
+        let (negative, digits) = match digits.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, digits),
+        };
+        let padded = format!("{digits:0>width$}", width = scale + 1);
+        let (whole, fraction) = padded.split_at(padded.len() - scale);
+        let whole = whole.trim_start_matches('0');
+        let fraction = fraction.trim_end_matches('0');
+        let zero = whole.is_empty() && fraction.is_empty();
+        (negative && !zero, whole.to_owned(), fraction.to_owned())
+    }
+
+    let (left_negative, left_whole, left_fraction) = split(left, left_scale);
+    let (right_negative, right_whole, right_fraction) = split(right, right_scale);
+    let magnitude = left_whole
+        .len()
+        .cmp(&right_whole.len())
+        .then_with(|| left_whole.cmp(&right_whole))
+        .then_with(|| left_fraction.cmp(&right_fraction));
+    match (left_negative, right_negative) {
+        (false, false) => magnitude,
+        (true, true) => magnitude.reverse(),
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+    }
+}
+
+impl Ord for Cell<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let numbers = match (self, other) {
+            (Cell::Float(left), Cell::Float(right)) => {
+                Some(canonical(*left).total_cmp(&canonical(*right)))
+            }
+            (Cell::Float(left), right) => {
+                right.exact().map(|right| right.cmp_float(*left).reverse())
+            }
+            (left, Cell::Float(right)) => left.exact().map(|left| left.cmp_float(*right)),
+            _ => self
+                .exact()
+                .zip(other.exact())
+                .map(|(left, right)| left.cmp(right)),
+        };
+        if let Some(ordering) = numbers {
+            return ordering;
+        }
+
+        match (self, other) {
+            (Cell::Bytes(left), Cell::Bytes(right)) => left.cmp(right),
+            (Cell::Uuid(left), Cell::Uuid(right)) => left.cmp(right),
+            (Cell::Date(left), Cell::Date(right)) => left.cmp(right),
+            (Cell::Instant(left, left_nanos), Cell::Instant(right, right_nanos)) => {
+                (left, left_nanos).cmp(&(right, right_nanos))
+            }
+            (Cell::Ipv4(left), Cell::Ipv4(right)) => left.cmp(right),
+            (Cell::Ipv6(left), Cell::Ipv6(right)) => left.cmp(right),
+            (Cell::Array(left), Cell::Array(right)) | (Cell::Tuple(left), Cell::Tuple(right)) => {
+                left.cmp(right)
+            }
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Cell<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Cell<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Cell<'_> {}
+
+/// Numbers hash by their nearest `f64`, which equal numbers share.
+impl Hash for Cell<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u8(self.rank());
+        match self {
+            Cell::Exact(..) | Cell::UInt128(_) | Cell::Wide(..) => {
+                let value = self.exact().map_or(f64::NAN, Exact::to_f64);
+                canonical(value).to_bits().hash(state);
+            }
+            Cell::Float(value) => canonical(*value).to_bits().hash(state),
+            Cell::Bytes(bytes) => bytes.hash(state),
+            Cell::Uuid(value) => value.hash(state),
+            Cell::Date(value) => value.hash(state),
+            Cell::Instant(seconds, nanos) => (seconds, nanos).hash(state),
+            Cell::Ipv4(value) => value.hash(state),
+            Cell::Ipv6(value) => value.hash(state),
+            Cell::Array(cells) | Cell::Tuple(cells) => cells.hash(state),
+            Cell::Null | Cell::Invalid => {}
+        }
+    }
+}
+
+/// The instant of ticks of the precision since the epoch.
+fn instant(ticks: i64, precision: u8) -> Result<Cell<'static>, Error> {
+    let (seconds, nanos) = conv::split_ticks(ticks, precision)
+        .ok_or_else(|| Error::ValueOutOfRange("precision", "DateTime64", precision.to_string()))?;
+    Ok(Cell::Instant(seconds, nanos))
+}
+
+fn cell(item: Item<'_>) -> Result<Cell<'_>, Error> {
+    fn tuple<'a>(values: impl IntoIterator<Item = Value<'a>>) -> Result<Cell<'a>, Error> {
+        let cells = values
+            .into_iter()
+            .map(|value| cell(Item::from(value)))
+            .collect::<Result<_, _>>()?;
+        Ok(Cell::Tuple(cells))
+    }
+
+    let value = match item {
+        Item::Decimal(unscaled, scale) => {
+            return Ok(match unscaled {
+                Unscaled::I32(value) => Cell::Exact(i128::from(value), scale),
+                Unscaled::I64(value) => Cell::Exact(i128::from(value), scale),
+                Unscaled::I128(value) => Cell::Exact(value.get(), scale),
+                Unscaled::I256(value) => Cell::Wide(&value.0, true, scale),
+            });
+        }
+        Item::DateTime {
+            ticks, precision, ..
+        } => return instant(ticks, precision),
+        Item::Value(value) => value,
+    };
+
+    Ok(match value {
+        Value::Empty => Cell::Null,
+        Value::Bool(value) => Cell::Exact(i128::from(value), 0),
+        Value::Int8(value) | Value::Enum8(_, value) => Cell::Exact(i128::from(value), 0),
+        Value::Int16(value) | Value::Enum16(_, value) => Cell::Exact(i128::from(value), 0),
+        Value::Int32(value) => Cell::Exact(i128::from(value), 0),
+        Value::Int64(value) => Cell::Exact(i128::from(value), 0),
+        Value::Int128(value) => Cell::Exact(value.get(), 0),
+        Value::UInt8(value) => Cell::Exact(i128::from(value), 0),
+        Value::UInt16(value) => Cell::Exact(i128::from(value), 0),
+        Value::UInt32(value) => Cell::Exact(i128::from(value), 0),
+        Value::UInt64(value) => Cell::Exact(i128::from(value), 0),
+        Value::UInt128(value) => Cell::UInt128(value.get()),
+        Value::Int256(value) => Cell::Wide(&value.0, true, 0),
+        Value::UInt256(value) => Cell::Wide(&value.0, false, 0),
+        Value::Float32(value) => Cell::Float(f64::from(value)),
+        Value::Float64(value) => Cell::Float(value),
+        Value::BFloat16(value) => Cell::Float(value.to_f64()),
+        Value::String(value) => Cell::Bytes(value.as_bytes()),
+        Value::Bytes(value) => Cell::Bytes(value),
+        Value::Uuid(&value) => Cell::Uuid(Uuid::from(value).as_u128()),
+        Value::Date(value) | Value::Date32(value) => Cell::Date(value),
+        Value::Ipv4(value) => Cell::Ipv4(u32::from(value)),
+        Value::Ipv6(value) => Cell::Ipv6(value.0),
+        Value::Tuple { index, mark } => tuple(
+            mark.values
+                .iter()
+                .map(|column| {
+                    column
+                        .get(index)
+                        .ok_or(Error::IndexOutOfBounds(index, "Tuple"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        )?,
+        Value::Map { mark, index } => {
+            let (start, end) = mark
+                .offsets
+                .offset_indices(index)?
+                .ok_or(Error::IndexOutOfBounds(index, "Map"))?;
+            let entries = (start..end)
+                .map(|position| {
+                    let key = mark.keys.get(position);
+                    let value = mark.values.get(position);
+                    match (key, value) {
+                        (Some(key), Some(value)) => tuple([key, value]),
+                        _ => Err(Error::IndexOutOfBounds(position, "Map")),
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+            Cell::Array(entries)
+        }
+        Value::Nested { .. } => Cell::Array(
+            NestedIterator::try_from(value)?
+                .map(|row| tuple(row.map(|(_, value)| value)))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Json { .. } => Cell::Array(
+            JsonIterator::try_from(value)?
+                .map(|(path, value)| {
+                    Ok(Cell::Tuple(vec![
+                        Cell::Bytes(path.as_bytes()),
+                        cell(Item::from(value))?,
+                    ]))
+                })
+                .collect::<Result<_, Error>>()?,
+        ),
+        // every other value is an array
+        value => Cell::Array(
+            text::elements(&value)?
+                .into_iter()
+                .map(cell)
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+fn owned_cell(value: &OwnedValue) -> Cell<'_> {
+    fn tuple<'a>(values: impl IntoIterator<Item = &'a OwnedValue>) -> Cell<'a> {
+        Cell::Tuple(values.into_iter().map(owned_cell).collect())
+    }
+
+    match value {
+        OwnedValue::Null => Cell::Null,
+        OwnedValue::Bool(value) => Cell::Exact(i128::from(*value), 0),
+        OwnedValue::Int8(value) | OwnedValue::Enum8(_, value) => Cell::Exact(i128::from(*value), 0),
+        OwnedValue::Int16(value) | OwnedValue::Enum16(_, value) => {
+            Cell::Exact(i128::from(*value), 0)
+        }
+        OwnedValue::Int32(value) => Cell::Exact(i128::from(*value), 0),
+        OwnedValue::Int64(value) => Cell::Exact(i128::from(*value), 0),
+        OwnedValue::Int128(value) => Cell::Exact(*value, 0),
+        OwnedValue::Int256(value) => Cell::Wide(value, true, 0),
+        OwnedValue::UInt8(value) => Cell::Exact(i128::from(*value), 0),
+        OwnedValue::UInt16(value) => Cell::Exact(i128::from(*value), 0),
+        OwnedValue::UInt32(value) => Cell::Exact(i128::from(*value), 0),
+        OwnedValue::UInt64(value) => Cell::Exact(i128::from(*value), 0),
+        OwnedValue::UInt128(value) => Cell::UInt128(*value),
+        OwnedValue::UInt256(value) => Cell::Wide(value, false, 0),
+        OwnedValue::Float32(value) => Cell::Float(f64::from(*value)),
+        OwnedValue::Float64(value) => Cell::Float(*value),
+        OwnedValue::BFloat16(value) => Cell::Float(value.to_f64()),
+        OwnedValue::Decimal32 { unscaled, scale } => Cell::Exact(i128::from(*unscaled), *scale),
+        OwnedValue::Decimal64 { unscaled, scale } => Cell::Exact(i128::from(*unscaled), *scale),
+        OwnedValue::Decimal128 { unscaled, scale } => Cell::Exact(*unscaled, *scale),
+        OwnedValue::Decimal256 { unscaled, scale } => Cell::Wide(unscaled, true, *scale),
+        OwnedValue::String(value) => Cell::Bytes(value.as_bytes()),
+        OwnedValue::Bytes(value) => Cell::Bytes(value),
+        OwnedValue::Uuid(value) => Cell::Uuid(value.as_u128()),
+        OwnedValue::Date(value) | OwnedValue::Date32(value) => Cell::Date(*value),
+        OwnedValue::DateTime {
+            ticks, precision, ..
+        } => instant(*ticks, *precision).unwrap_or(Cell::Invalid),
+        OwnedValue::Ipv4(value) => Cell::Ipv4(u32::from(*value)),
+        OwnedValue::Ipv6(value) => Cell::Ipv6(value.octets()),
+        OwnedValue::Array(values) => Cell::Array(values.iter().map(owned_cell).collect()),
+        OwnedValue::Tuple(values) => tuple(values),
+        OwnedValue::Map(entries) => Cell::Array(
+            entries
+                .iter()
+                .map(|(key, value)| tuple([key, value]))
+                .collect(),
+        ),
+        OwnedValue::Nested { rows, .. } => Cell::Array(rows.iter().map(tuple).collect()),
+        OwnedValue::Json(paths) => Cell::Array(
+            paths
+                .iter()
+                .map(|(path, value)| {
+                    Cell::Tuple(vec![Cell::Bytes(path.as_bytes()), owned_cell(value)])
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn key<'a>(value: &Value<'a>) -> Cell<'a> {
+    cell(Item::from(value.clone())).unwrap_or(Cell::Invalid)
+}
+
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        key(self) == key(other)
+    }
+}
+
+impl Eq for Value<'_> {}
+
+impl PartialOrd for Value<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Values pointing outside of their columns, which a validated block rules out, equal each
+/// other and sort last.
+impl Ord for Value<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        key(self).cmp(&key(other))
+    }
+}
+
+impl Hash for Value<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        key(self).hash(state);
+    }
+}
+
+impl PartialEq for OwnedValue {
+    fn eq(&self, other: &Self) -> bool {
+        owned_cell(self) == owned_cell(other)
+    }
+}
+
+impl Eq for OwnedValue {}
+
+impl PartialOrd for OwnedValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OwnedValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        owned_cell(self).cmp(&owned_cell(other))
+    }
+}
+
+impl Hash for OwnedValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        owned_cell(self).hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        hash::{BuildHasher as _, BuildHasherDefault, DefaultHasher},
+    };
+
+    use chrono_tz::Tz;
+    use testresult::TestResult;
+    use zerocopy::little_endian::{I32, I128, U128};
+
+    use super::*;
+    use crate::{
+        I256, TinyRange,
+        common::load_testdata,
+        mark::{DateTime, DateTime64, Decimal64, Decimal256, Enum8},
+        parse::block::parse_many,
+        slice::ByteView,
+        text::column_value,
+    };
+
+    fn hash_of(value: &Value<'_>) -> u64 {
+        BuildHasherDefault::<DefaultHasher>::default().hash_one(value)
+    }
+
+    fn assert_same(left: &Value<'_>, right: &Value<'_>) {
+        assert_eq!(left, right);
+        assert_eq!(hash_of(left), hash_of(right), "{left:?} {right:?}");
+    }
+
+    #[test]
+    fn numbers() -> TestResult {
+        let one_and_quarter = 125_i64.to_le_bytes();
+        let d64 = Decimal64 {
+            precision: 2,
+            data: ByteView::try_from(&one_and_quarter[..])?,
+        };
+        let wide = I256([0xFF; 32]);
+        let mut bytes = [0; 32];
+        bytes[..8].copy_from_slice(&12_500_i64.to_le_bytes());
+        let d256 = Decimal256 {
+            precision: 4,
+            data: ByteView::try_from(&bytes[..])?,
+        };
+
+        assert_same(&Value::Int8(1), &Value::UInt64(1));
+        assert_same(&Value::Bool(true), &Value::Int32(1));
+        assert_same(&Value::Decimal64(0, &d64), &Value::Float64(1.25));
+        assert_same(&Value::Decimal64(0, &d64), &Value::Decimal256(0, &d256));
+        assert_same(&Value::Int256(&wide), &Value::Int8(-1));
+        assert_same(&Value::Float32(0.0), &Value::Float64(-0.0));
+
+        assert!(Value::Decimal64(0, &d64) < Value::Int8(2));
+        assert!(Value::Decimal64(0, &d64) > Value::Float32(1.2));
+        assert!(Value::Int256(&wide) < Value::UInt8(0));
+        assert_ne!(Value::Int8(1), Value::String("1"));
+        assert!(Value::Int8(1) < Value::String("1"));
+
+        // floats compare by their exact values
+        let tenth = 1_i64.to_le_bytes();
+        let tenth = Decimal64 {
+            precision: 1,
+            data: ByteView::try_from(&tenth[..])?,
+        };
+        assert_ne!(Value::Decimal64(0, &tenth), Value::Float64(0.1));
+        assert!(Value::Decimal64(0, &tenth) < Value::Float64(0.1));
+        assert!(Value::Decimal64(0, &tenth) < Value::Float32(0.1));
+        assert!(Value::Float32(0.1) > Value::Float64(0.1));
+        Ok(())
+    }
+
+    #[test]
+    fn nulls_and_nan() {
+        let nan = Value::Float64(f64::NAN);
+        assert_same(&nan, &Value::Float32(-f32::NAN));
+        assert!(nan > Value::Float64(f64::INFINITY));
+        assert!(nan > Value::UInt128(&U128::new(u128::MAX)));
+        assert!(nan < Value::String(""));
+
+        assert_same(&Value::Empty, &Value::Empty);
+        assert!(Value::Empty > Value::Int8(i8::MAX));
+        assert!(Value::String("") < Value::Empty);
+    }
+
+    #[test]
+    fn datetimes() -> TestResult {
+        let seconds = 1_700_000_000_u32.to_le_bytes();
+        let datetime = DateTime {
+            tz: Tz::UTC,
+            data: ByteView::try_from(&seconds[..])?,
+        };
+        let mut ticks = Vec::new();
+        for millis in [1_700_000_000_000_i64, 1_700_000_000_001] {
+            ticks.extend(millis.to_le_bytes());
+        }
+        let datetime64 = DateTime64 {
+            precision: 3,
+            tz: Tz::Europe__Berlin,
+            data: ByteView::try_from(&ticks[..])?,
+        };
+
+        assert_same(
+            &Value::DateTime(0, &datetime),
+            &Value::DateTime64(0, &datetime64),
+        );
+        assert!(Value::DateTime(0, &datetime) < Value::DateTime64(1, &datetime64));
+        Ok(())
+    }
+
+    #[test]
+    fn arrays_and_enums() -> TestResult {
+        let numbers = [1, 2, 3].map(I32::new);
+        assert!(Value::Int32Slice(&numbers[..2]) < Value::Int32Slice(&numbers));
+        assert!(Value::Int32Slice(&numbers) < Value::Int32Slice(&numbers[1..]));
+        assert_same(&Value::Int32Slice(&[]), &Value::StringSlice(&[]));
+
+        let enum8 = Enum8 {
+            variants: vec![("b".into(), 1), ("a".into(), 2)],
+            data: ByteView::try_from(&[2_u8, 1][..])?,
+        };
+        let a = Value::Enum8Slice {
+            mark: &enum8,
+            range: TinyRange::try_from(0..1)?,
+        };
+        let b = Value::Enum8Slice {
+            mark: &enum8,
+            range: TinyRange::try_from(1..2)?,
+        };
+        // by value, not by name
+        assert!(a > b);
+        assert_same(&a, &Value::Int8Slice(&[2]));
+        assert_ne!(a, Value::StringSlice(&[b"a"]));
+
+        let (Some(a), Some(b)) = (enum8.get(0), enum8.get(1)) else {
+            panic!("enum values out of range");
+        };
+        assert!(matches!(a, Value::Enum8("a", 2)));
+        assert!(a > b);
+        assert_same(&b, &Value::UInt64(1));
+        assert_ne!(a, Value::String("a"));
+        Ok(())
+    }
+
+    #[test]
+    fn is_transitive() -> TestResult {
+        let tenth = 1_i64.to_le_bytes();
+        let tenth = Decimal64 {
+            precision: 1,
+            data: ByteView::try_from(&tenth[..])?,
+        };
+        let wide = I256([0xFF; 32]);
+        let big = 1_i64 << f64::MANTISSA_DIGITS;
+        #[expect(clippy::cast_precision_loss)]
+        let values = [
+            Value::Int64(big),
+            Value::Int64(big + 1),
+            Value::UInt64(big.cast_unsigned() + 1),
+            Value::Float64(big as f64),
+            Value::Float32(big as f32),
+            Value::Decimal64(0, &tenth),
+            Value::Float64(0.1),
+            Value::Float32(0.1),
+            Value::Float64(f64::NAN),
+            Value::Float64(-0.0),
+            Value::Int256(&wide),
+            Value::Int8(-1),
+            Value::Int8(0),
+            Value::Int8(1),
+            Value::Bool(true),
+            Value::Enum16("a", 1),
+            Value::String("a"),
+            Value::StringSlice(&[b"a"]),
+            Value::Empty,
+        ];
+
+        // `big` and `big + 1` have the same nearest float, but only one of them equals it
+        assert_same(&values[0], &values[3]);
+        assert!(values[1] > values[3]);
+        assert_same(&values[1], &values[2]);
+
+        for left in &values {
+            for middle in &values {
+                assert_eq!(left.cmp(middle), middle.cmp(left).reverse());
+                if left == middle {
+                    assert_eq!(hash_of(left), hash_of(middle), "{left:?} {middle:?}");
+                }
+                for right in &values {
+                    if left <= middle && middle <= right {
+                        assert!(left <= right, "{left:?} {middle:?} {right:?}");
+                    }
+                    if left == middle && middle == right {
+                        assert_eq!(left, right, "{left:?} {middle:?} {right:?}");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn keys_hash_sets() {
+        let values = [
+            Value::Int8(1),
+            Value::UInt64(1),
+            Value::Float64(1.0),
+            Value::Bool(true),
+            Value::Enum8("one", 1),
+            Value::String("1"),
+            Value::String("one"),
+            Value::Float64(f64::NAN),
+            Value::Float32(f32::NAN),
+            Value::Empty,
+            Value::Empty,
+        ];
+        let set = values.iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(set.len(), 5);
+        assert!(set.contains(&Value::Int128(&I128::new(1))));
+        assert!(!set.contains(&Value::Int8(2)));
+    }
+
+    #[test]
+    fn testdata_values_equal_themselves() -> TestResult {
+        for (path, data) in load_testdata()? {
+            let (left, right) = (parse_many(&data)?, parse_many(&data)?);
+            for (left, right) in left.iter().zip(&right) {
+                for (mark, other) in left.markers.iter().zip(&right.markers) {
+                    for row in 0..left.num_rows {
+                        let (left, right) = (column_value(mark, row)?, column_value(other, row)?);
+                        assert_eq!(left, right, "{}", path.display());
+                        assert_eq!(hash_of(&left), hash_of(&right), "{}", path.display());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
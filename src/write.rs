@@ -0,0 +1,33 @@
+//! Low-level helpers for producing the binary formats the parsers read.
+
+use crate::{parse::consts::LOW_CARDINALITY_VERSION, types::Type};
+
+pub fn varuint(out: &mut Vec<u8>, value: u64) {
+    let mut buf = unsigned_varint::encode::u64_buffer();
+    out.extend_from_slice(unsigned_varint::encode::u64(value, &mut buf));
+}
+
+pub fn var_str(out: &mut Vec<u8>, bytes: &[u8]) {
+    varuint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Writes the state prefix a Native column of type `typ` starts with: the serialization
+/// version of every `LowCardinality` and the mode of every `Variant` in it.
+pub fn prefix(out: &mut Vec<u8>, typ: &Type<'_>) {
+    match typ {
+        Type::LowCardinality(_) => out.extend_from_slice(&LOW_CARDINALITY_VERSION.to_le_bytes()),
+        Type::Nullable(inner) | Type::Array(inner) => prefix(out, inner),
+        Type::Map(key, value) => {
+            prefix(out, key);
+            prefix(out, value);
+        }
+        Type::Tuple(types, _) => types.iter().for_each(|typ| prefix(out, typ)),
+        Type::Nested(fields) => fields.iter().for_each(|field| prefix(out, &field.typ)),
+        Type::Variant(types) => {
+            out.extend_from_slice(&0_u64.to_le_bytes());
+            types.iter().for_each(|typ| prefix(out, typ));
+        }
+        _ => {}
+    }
+}
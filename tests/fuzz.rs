@@ -0,0 +1,166 @@
+//! Deterministic fuzzing of the decoders: truncated and randomly mutated inputs must produce
+//! errors, never panics.
+
+use std::{collections::HashSet, fs, num::NonZeroUsize};
+
+use chbr::parse::{
+    block::{parse_many, parse_many_parallel, parse_many_projected, scan_many},
+    typ::parse_type,
+};
+
+/// xorshift64*, good enough to shake the inputs and reproducible between runs
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        usize::try_from(self.next() % bound as u64).unwrap()
+    }
+
+    fn byte(&mut self) -> u8 {
+        self.next().to_le_bytes()[0]
+    }
+}
+
+fn native_files() -> Vec<(String, Vec<u8>)> {
+    let mut files = fs::read_dir("./testdata")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "native"))
+        .map(|path| {
+            let data = fs::read(&path).unwrap();
+            (path.display().to_string(), data)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    assert!(!files.is_empty());
+    files
+}
+
+fn decode_all_ways(input: &[u8]) {
+    let _ = parse_many(input);
+    let _ = parse_many_projected(input, &HashSet::new());
+    let _ = scan_many(input).map(|blocks| {
+        blocks
+            .into_iter()
+            .map(chbr::LazyBlock::into_parsed)
+            .collect::<Vec<_>>()
+    });
+}
+
+fn mutate(rng: &mut Rng, data: &mut Vec<u8>) {
+    for _ in 0..=rng.below(4) {
+        if data.is_empty() {
+            data.push(rng.byte());
+            continue;
+        }
+        let pos = rng.below(data.len());
+        match rng.below(6) {
+            0 => data[pos] = rng.byte(),
+            1 => data[pos] ^= 1 << rng.below(8),
+            2 => data[pos] = 0xff,
+            3 => data[pos] = 0,
+            4 => data.insert(pos, rng.byte()),
+            _ => {
+                data.remove(pos);
+            }
+        }
+    }
+}
+
+#[test]
+fn parse_many_truncated() {
+    for (_, data) in native_files() {
+        let step = (data.len() / 300).max(1);
+        for len in (0..data.len()).step_by(step) {
+            decode_all_ways(&data[..len]);
+        }
+    }
+}
+
+#[test]
+fn parse_many_mutated() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for (_, data) in native_files() {
+        let iterations = if data.len() > 10_000 { 20 } else { 300 };
+        for _ in 0..iterations {
+            let mut mutated = data.clone();
+            mutate(&mut rng, &mut mutated);
+            decode_all_ways(&mutated);
+        }
+    }
+}
+
+#[test]
+fn parse_many_parallel_mutated() {
+    let mut rng = Rng(42);
+    let mut data = Vec::new();
+    for (_, file) in native_files().into_iter().take(8) {
+        data.extend(file);
+    }
+    for _ in 0..50 {
+        let mut mutated = data.clone();
+        mutate(&mut rng, &mut mutated);
+        let _ = parse_many_parallel(&mutated, NonZeroUsize::new(4).unwrap());
+    }
+}
+
+const TYPES: &[&str] = &[
+    "Array(LowCardinality(Nullable(String)))",
+    "Map(LowCardinality(String), Array(Nullable(Int64)))",
+    "Tuple(UInt8, Decimal(38, 10), DateTime64(6, 'Europe/Amsterdam'))",
+    "Variant(String, Array(UInt64), Tuple(Float32, Bool))",
+    "Nested(id UInt32, name String, tags Array(String))",
+    "Enum8('red' = -1, 'green' = 2)",
+    "Enum16('a' = 1000, 'b(' = 2000)",
+    "FixedString(16)",
+    "MultiPolygon",
+    "JSON",
+    "Dynamic",
+];
+
+#[test]
+fn parse_type_mutated() {
+    let mut rng = Rng(7);
+    for typ in TYPES {
+        assert!(parse_type(typ.as_bytes()).is_ok(), "{typ}");
+        for _ in 0..2000 {
+            let mut mutated = typ.as_bytes().to_vec();
+            mutate(&mut rng, &mut mutated);
+            let _ = parse_type(&mutated);
+        }
+    }
+
+    for _ in 0..2000 {
+        let len = rng.below(64);
+        let random = (0..len).map(|_| rng.byte()).collect::<Vec<_>>();
+        let _ = parse_type(&random);
+    }
+}
+
+#[test]
+fn parse_type_deep_nesting() {
+    let depth = 100_000;
+    let typ = format!("{}UInt8{}", "Array(".repeat(depth), ")".repeat(depth));
+    assert!(parse_type(typ.as_bytes()).is_err());
+
+    let typ = format!("{}UInt8{}", "Array(".repeat(32), ")".repeat(32));
+    assert!(parse_type(typ.as_bytes()).is_ok());
+
+    // an escaped quote must not hide the rest of the type from the nesting check
+    let typ = format!(
+        r"Tuple(Enum8('\'' = 1), {}UInt8{})",
+        "Array(".repeat(depth),
+        ")".repeat(depth + 1)
+    );
+    assert!(parse_type(typ.as_bytes()).is_err());
+
+    let typ = r"Tuple(Enum8('\'(' = 1), Array(UInt8))";
+    assert!(parse_type(typ.as_bytes()).is_ok());
+}